@@ -23,6 +23,7 @@ use tracing::{debug, error, info, warn};
 
 use crate::{
     cli::BouncerConfig,
+    geoip::IpPrefix,
     routes::{ErrorCause, RateLimitCause},
 };
 
@@ -55,6 +56,8 @@ struct Metrics {
 
 pub struct Bouncer {
     firewall: Arc<dyn Firewall>,
+    // IPs matching these prefixes bypass the bouncer entirely
+    allowlist: Vec<IpPrefix>,
     buckets: Cache<IpAddr, Arc<Bucket>>,
     decisions: DashMap<IpAddr, Decision>,
     ban_time: Duration,
@@ -73,6 +76,7 @@ impl Bouncer {
         ban_time: Duration,
         max_buckets: u64,
         bucket_expiry: Duration,
+        allowlist: Vec<IpPrefix>,
         firewall: Arc<dyn Firewall>,
         registry: &Registry,
     ) -> Result<Self, Error> {
@@ -107,6 +111,7 @@ impl Bouncer {
         Ok(Self {
             burst_size,
             firewall,
+            allowlist,
             buckets,
             decisions: DashMap::new(),
             ban_time,
@@ -134,6 +139,11 @@ impl Bouncer {
 
     // Counts the request against a bucket and returns if it should be allowed or not
     fn acquire_token(&self, ip: IpAddr) -> bool {
+        // Allowlisted IPs are never limited, even if they were banned before
+        if self.allowlist.iter().any(|x| x.contains(ip)) {
+            return true;
+        }
+
         // Check if the IP is already banned
         if self.decisions.contains_key(&ip) {
             return false;
@@ -282,6 +292,7 @@ pub fn setup(cli: &BouncerConfig, registry: &Registry) -> Result<Arc<Bouncer>, E
             Duration::from_secs(cli.bouncer_ban_seconds),
             cli.bouncer_max_buckets,
             Duration::from_secs(cli.bouncer_bucket_ttl),
+            cli.bouncer_allowlist.clone(),
             firewall,
             registry,
         )
@@ -27,6 +27,7 @@ async fn test_bouncer() {
         Duration::from_secs(100),
         100,
         Duration::from_secs(10),
+        vec![],
         fw.clone(),
         &Registry::new(),
     )
@@ -68,3 +69,56 @@ async fn test_bouncer() {
     assert!(bouncer.apply().await.is_ok());
     assert!(fw.0.is_empty());
 }
+
+#[tokio::test]
+async fn test_bouncer_allowlist() {
+    let fw = Arc::new(MockFirewall(DashSet::new()));
+
+    let bouncer = Bouncer::new(
+        10,
+        15,
+        Duration::from_secs(100),
+        100,
+        Duration::from_secs(10),
+        vec![
+            IpPrefix::from_str("10.0.0.0/8").unwrap(),
+            IpPrefix::from_str("2001:db8::/32").unwrap(),
+        ],
+        fw.clone(),
+        &Registry::new(),
+    )
+    .unwrap();
+
+    let ip1 = IpAddr::from_str("10.1.2.3").unwrap();
+    let ip2 = IpAddr::from_str("2001:db8::1").unwrap();
+    let ip3 = IpAddr::from_str("11.1.2.3").unwrap();
+
+    // Allowlisted IPs are never denied even way above the burst
+    for _ in 0..100 {
+        assert!(bouncer.acquire_token(ip1));
+        assert!(bouncer.acquire_token(ip2));
+    }
+
+    // Others are still limited
+    for _ in 0..15 {
+        assert!(bouncer.acquire_token(ip3));
+    }
+    assert!(!bouncer.acquire_token(ip3));
+
+    // Only the non-allowlisted IP is banned
+    assert!(bouncer.apply().await.is_ok());
+    assert!(!fw.0.contains(&ip1));
+    assert!(!fw.0.contains(&ip2));
+    assert!(fw.0.contains(&ip3));
+
+    // Allowlist beats an existing ban decision
+    bouncer.decisions.insert(
+        ip1,
+        Decision {
+            ip: ip1,
+            when: Instant::now(),
+            length: Duration::from_secs(100),
+        },
+    );
+    assert!(bouncer.acquire_token(ip1));
+}
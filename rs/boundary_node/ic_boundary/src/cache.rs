@@ -15,9 +15,15 @@ use http::{
     response, Version,
 };
 use ic_bn_lib::http::body::buffer_body;
-use moka::future::{Cache as MokaCache, CacheBuilder as MokaCacheBuilder};
+use moka::{
+    future::{Cache as MokaCache, CacheBuilder as MokaCacheBuilder},
+    Expiry,
+};
 
-use crate::routes::{ApiError, ErrorCause, RequestContext};
+use crate::{
+    cli,
+    routes::{ApiError, ErrorCause, RequestContext},
+};
 
 // A list of possible Cache-Control directives that ask us not to cache the response
 const SKIP_CACHE_DIRECTIVES: &[&str] = &["no-store", "no-cache", "max-age=0"];
@@ -74,18 +80,91 @@ impl fmt::Display for CacheStatus {
     }
 }
 
+// Time-to-live of the cached responses per status class.
+// Responses with a status class that has no TTL are not cached.
+#[derive(Clone, Debug)]
+pub struct CacheTtls {
+    pub success: Duration,
+    pub redirection: Option<Duration>,
+    pub client_error: Option<Duration>,
+    pub server_error: Option<Duration>,
+}
+
+impl CacheTtls {
+    // Caches only 2xx responses
+    pub fn new(success: Duration) -> Self {
+        Self {
+            success,
+            redirection: None,
+            client_error: None,
+            server_error: None,
+        }
+    }
+
+    fn ttl(&self, status: StatusCode) -> Option<Duration> {
+        if status.is_success() {
+            Some(self.success)
+        } else if status.is_redirection() {
+            self.redirection
+        } else if status.is_client_error() {
+            self.client_error
+        } else if status.is_server_error() {
+            self.server_error
+        } else {
+            None
+        }
+    }
+}
+
+impl From<&cli::CacheConfig> for CacheTtls {
+    fn from(c: &cli::CacheConfig) -> Self {
+        Self {
+            success: Duration::from_secs(c.cache_ttl_seconds),
+            redirection: c.cache_ttl_seconds_redirect.map(Duration::from_secs),
+            client_error: c.cache_ttl_seconds_client_error.map(Duration::from_secs),
+            server_error: c.cache_ttl_seconds_server_error.map(Duration::from_secs),
+        }
+    }
+}
+
 #[derive(Clone)]
 struct CacheItem {
     status: StatusCode,
     version: Version,
     headers: HeaderMap,
     body: Bytes,
+    ttl: Duration,
+}
+
+// Expires each cache item after its own TTL
+struct CacheItemExpiry;
+
+impl Expiry<Arc<RequestContext>, CacheItem> for CacheItemExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &Arc<RequestContext>,
+        value: &CacheItem,
+        _created_at: std::time::Instant,
+    ) -> Option<Duration> {
+        Some(value.ttl)
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &Arc<RequestContext>,
+        value: &CacheItem,
+        _updated_at: std::time::Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        Some(value.ttl)
+    }
 }
 
 #[derive(Clone)]
 pub struct Cache {
     cache: MokaCache<Arc<RequestContext>, CacheItem>,
     max_item_size: u64,
+    ttls: CacheTtls,
     cache_non_anonymous: bool,
 }
 
@@ -114,7 +193,7 @@ impl Cache {
     pub fn new(
         cache_size: u64,
         max_item_size: u64,
-        ttl: Duration,
+        ttls: CacheTtls,
         cache_non_anonymous: bool,
     ) -> Result<Self, Error> {
         if max_item_size >= cache_size {
@@ -124,25 +203,33 @@ impl Cache {
         }
 
         let cache = MokaCacheBuilder::new(cache_size)
-            .time_to_live(ttl)
+            .expire_after(CacheItemExpiry)
             .weigher(weigh_entry)
             .build();
 
         Ok(Self {
             cache,
             max_item_size,
+            ttls,
             cache_non_anonymous,
         })
     }
 
     // Stores the response components in the cache
     // Response itself cannot be stored since it's not cloneable, so we have to rebuild it
-    async fn store(&self, ctx: Arc<RequestContext>, parts: &response::Parts, body: Bytes) {
+    async fn store(
+        &self,
+        ctx: Arc<RequestContext>,
+        parts: &response::Parts,
+        body: Bytes,
+        ttl: Duration,
+    ) {
         let item = CacheItem {
             status: parts.status,
             version: parts.version,
             headers: parts.headers.clone(),
             body,
+            ttl,
         };
 
         // Insert the response into the cache & wait for it to persist there
@@ -238,9 +325,23 @@ pub async fn cache_middleware(
     // If not found - pass the request down the stack
     let response = next.run(request).await;
 
-    // Do not cache non-2xx responses
-    if !response.status().is_success() {
-        return Ok(CacheStatus::Bypass(CacheBypassReason::HTTPError).with_response(response));
+    // Do not cache responses with a status class that has no TTL configured
+    let ttl = match cache.ttls.ttl(response.status()) {
+        Some(v) => v,
+        None => {
+            return Ok(CacheStatus::Bypass(CacheBypassReason::HTTPError).with_response(response))
+        }
+    };
+
+    // Do not cache responses that the upstream asks us not to store
+    if let Some(v) = response.headers().get(CACHE_CONTROL) {
+        if let Ok(hdr) = v.to_str() {
+            if hdr.contains("no-store") {
+                return Ok(
+                    CacheStatus::Bypass(CacheBypassReason::CacheControl).with_response(response)
+                );
+            }
+        }
     }
 
     let content_length = extract_content_length(&response).map_err(|_| {
@@ -267,7 +368,7 @@ pub async fn cache_middleware(
         .context("unable to read body")?;
 
     // Insert the response into the cache
-    cache.store(ctx, &parts, body.clone()).await;
+    cache.store(ctx, &parts, body.clone(), ttl).await;
 
     // Reconstruct the response from components
     let response = Response::from_parts(parts, Body::from(body));
@@ -58,6 +58,18 @@ fn gen_request(canister_id: &str, nonce: bool) -> Request<Body> {
     gen_request_with_params(canister_id, nonce, DEFAULT_SIZE, 0, true, StatusCode::OK)
 }
 
+// Generate a response with a requested size
+async fn handler_no_store(
+    Extension(size): Extension<u64>,
+    Extension(status_code): Extension<StatusCode>,
+) -> impl IntoResponse {
+    (
+        status_code,
+        [(CACHE_CONTROL, "no-store")],
+        "a".repeat(size as usize),
+    )
+}
+
 // Generate a response with a requested size
 async fn handler(
     Extension(size): Extension<u64>,
@@ -69,12 +81,12 @@ async fn handler(
 #[tokio::test]
 async fn test_cache() -> Result<(), Error> {
     // Check that we fail if item size >= max size
-    assert!(Cache::new(1024, 1024, Duration::from_secs(60), false).is_err());
+    assert!(Cache::new(1024, 1024, CacheTtls::new(Duration::from_secs(60)), false).is_err());
 
     let cache = Cache::new(
         MAX_MEM_SIZE,
         MAX_RESP_SIZE,
-        Duration::from_secs(3600),
+        CacheTtls::new(Duration::from_secs(3600)),
         false,
     )?;
     let cache = Arc::new(cache);
@@ -203,3 +215,71 @@ async fn test_cache() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_cache_ttl_per_status_class() -> Result<(), Error> {
+    let ttls = CacheTtls {
+        success: Duration::from_secs(2),
+        redirection: None,
+        client_error: Some(Duration::from_millis(500)),
+        server_error: None,
+    };
+
+    let cache = Arc::new(Cache::new(MAX_MEM_SIZE, MAX_RESP_SIZE, ttls, false)?);
+
+    let mut app = Router::new()
+        .route("/", post(handler))
+        .route("/no-store", post(handler_no_store))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&cache),
+            cache_middleware,
+        ));
+
+    let mut call = |canister_id: &str, status_code: StatusCode| {
+        let req = gen_request_with_params(canister_id, false, DEFAULT_SIZE, 0, true, status_code);
+        app.call(req)
+    };
+
+    // Both the 200 and the 404 are cached
+    for _ in 0..2 {
+        let res = call(CANISTER_1, StatusCode::OK).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = call(CANISTER_2, StatusCode::NOT_FOUND).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+    assert_eq!(cache.len(), 2);
+
+    let cache_status = |res: Response| res.extensions().get::<CacheStatus>().cloned().unwrap();
+
+    // The 404 expires first
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let res = call(CANISTER_1, StatusCode::OK).await.unwrap();
+    assert_eq!(cache_status(res), CacheStatus::Hit);
+    let res = call(CANISTER_2, StatusCode::NOT_FOUND).await.unwrap();
+    assert_eq!(cache_status(res), CacheStatus::Miss);
+
+    // Then the 200
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let res = call(CANISTER_1, StatusCode::OK).await.unwrap();
+    assert_eq!(cache_status(res), CacheStatus::Miss);
+
+    // Statuses without a TTL are not cached
+    let res = call(CANISTER_1, StatusCode::SERVICE_UNAVAILABLE)
+        .await
+        .unwrap();
+    assert_eq!(
+        cache_status(res),
+        CacheStatus::Bypass(CacheBypassReason::HTTPError)
+    );
+
+    // Responses with `Cache-Control: no-store` are not cached
+    let mut req = gen_request(CANISTER_2, false);
+    *req.uri_mut() = "/no-store".parse().unwrap();
+    let res = app.call(req).await.unwrap();
+    assert_eq!(
+        cache_status(res),
+        CacheStatus::Bypass(CacheBypassReason::CacheControl)
+    );
+
+    Ok(())
+}
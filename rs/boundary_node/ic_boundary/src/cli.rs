@@ -1,9 +1,13 @@
 use std::{net::SocketAddr, path::PathBuf};
 
+use candid::Principal;
 use clap::{Args, Parser};
 use url::Url;
 
-use crate::core::{AUTHOR_NAME, SERVICE_NAME};
+use crate::{
+    core::{AUTHOR_NAME, SERVICE_NAME},
+    geoip::{GeoIpOverride, IpPrefix},
+};
 
 #[derive(Parser)]
 #[clap(name = SERVICE_NAME)]
@@ -197,6 +201,10 @@ pub struct HealthChecksConfig {
     /// Maximum block height lag for a replica to be included in the routing table
     #[clap(long, default_value = "50")]
     pub max_height_lag: u64,
+
+    /// Maximum age of the registry snapshot in seconds for it to be reported as fresh by the health endpoint
+    #[clap(long, default_value = "3600")]
+    pub max_snapshot_age: u64,
 }
 
 #[derive(Args)]
@@ -271,6 +279,12 @@ pub struct MonitoringConfig {
     /// Path to a GeoIP country database file
     #[clap(long)]
     pub geoip_db: Option<PathBuf>,
+
+    /// Override the GeoIP country for clients from a given IP prefix, in <prefix>=<country_code> format
+    /// e.g. 10.0.0.0/8=CH. Can be specified multiple times, the most specific prefix wins.
+    /// Overrides take precedence over the GeoIP database.
+    #[clap(long)]
+    pub geoip_override: Vec<GeoIpOverride>,
 }
 
 #[derive(Args)]
@@ -282,6 +296,13 @@ pub struct RateLimitingConfig {
     /// Allowed number of update calls per second per ip per boundary node. Panics if 0 is passed!
     #[clap(long)]
     pub rate_limit_per_second_per_ip: Option<u32>,
+
+    /// Allowed number of update calls per second to a specific subnet per boundary node,
+    /// in <subnet_id>=<limit> format. Can be specified multiple times.
+    /// These are applied in addition to --rate-limit-per-second-per-subnet.
+    #[clap(long, value_parser = parse_subnet_rate_limit)]
+    pub rate_limit_per_second_for_subnet: Vec<(Principal, u32)>,
+
     /// Path to a generic rate-limiter rules, if the file does not exist - no rules are applied.
     /// File is checked every 10sec and is reloaded if the changes are detected.
     /// Expecting YAML list with objects that have (canister_id, methods, limit) fields.
@@ -302,6 +323,20 @@ pub struct RateLimitingConfig {
     pub rate_limit_generic: PathBuf,
 }
 
+fn parse_subnet_rate_limit(s: &str) -> Result<(Principal, u32), String> {
+    let (subnet_id, limit) = s
+        .split_once('=')
+        .ok_or("expected <subnet_id>=<limit>".to_string())?;
+
+    let subnet_id = Principal::from_text(subnet_id).map_err(|e| e.to_string())?;
+    let limit = limit.parse::<u32>().map_err(|e| e.to_string())?;
+    if limit == 0 {
+        return Err("rate limit cannot be 0".into());
+    }
+
+    Ok((subnet_id, limit))
+}
+
 #[derive(Args)]
 pub struct CacheConfig {
     /// Maximum size of in-memory cache in bytes. Specify a size to enable caching.
@@ -312,10 +347,25 @@ pub struct CacheConfig {
     #[clap(long, default_value = "131072")]
     pub cache_max_item_size_bytes: u64,
 
-    /// Time-to-live for cache entries in seconds
+    /// Time-to-live for cache entries of 2xx responses in seconds
     #[clap(long, default_value = "1")]
     pub cache_ttl_seconds: u64,
 
+    /// Time-to-live for cache entries of 3xx responses in seconds.
+    /// If not specified - such responses are not cached.
+    #[clap(long)]
+    pub cache_ttl_seconds_redirect: Option<u64>,
+
+    /// Time-to-live for cache entries of 4xx responses in seconds.
+    /// If not specified - such responses are not cached.
+    #[clap(long)]
+    pub cache_ttl_seconds_client_error: Option<u64>,
+
+    /// Time-to-live for cache entries of 5xx responses in seconds.
+    /// If not specified - such responses are not cached.
+    #[clap(long)]
+    pub cache_ttl_seconds_server_error: Option<u64>,
+
     /// Whether to cache non-anonymous requests
     #[clap(long, default_value = "false")]
     pub cache_non_anonymous: bool,
@@ -336,6 +386,16 @@ pub struct RetryConfig {
     /// Whether to use latency-based routing for /call
     #[clap(long, default_value = "false")]
     pub disable_latency_routing: bool,
+
+    /// Number of consecutive failed requests to a replica after which it's skipped for the cooldown period.
+    /// If not specified - the circuit breaker is disabled.
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub retry_circuit_breaker_threshold: Option<u32>,
+
+    /// For how long to skip a replica after its circuit breaker opens, in seconds.
+    /// After that a single request is let through to check if it has recovered.
+    #[clap(long, default_value = "30")]
+    pub retry_circuit_breaker_cooldown: u64,
 }
 
 #[derive(Args)]
@@ -397,4 +457,9 @@ pub struct BouncerConfig {
     /// NFTables set name for IPv6
     #[clap(long, default_value = "blackhole6")]
     pub bouncer_v6_set: String,
+
+    /// IP prefix that is never rate limited or banned by the bouncer, e.g. 10.0.0.0/8.
+    /// Can be specified multiple times. Takes precedence over any ban decision.
+    #[clap(long)]
+    pub bouncer_allowlist: Vec<IpPrefix>,
 }
@@ -1,11 +1,14 @@
 use std::{
     error::Error as StdError,
+    fs, io,
     net::{Ipv6Addr, SocketAddr},
+    os::unix::{fs::FileTypeExt, net::UnixStream},
+    path::Path,
     sync::Arc,
     time::{Duration, Instant},
 };
 
-use anyhow::{anyhow, Context, Error};
+use anyhow::{anyhow, bail, Context, Error};
 use arc_swap::ArcSwapOption;
 use async_scoped::TokioScope;
 use async_trait::async_trait;
@@ -35,7 +38,7 @@ use tracing::{debug, error, warn};
 
 use crate::{
     bouncer,
-    cache::{cache_middleware, Cache},
+    cache::{cache_middleware, Cache, CacheTtls},
     check::{Checker, Runner as CheckRunner},
     cli::Cli,
     dns::DnsResolver,
@@ -47,8 +50,8 @@ use crate::{
         WithMetricsCheck, WithMetricsPersist, WithMetricsSnapshot, HTTP_DURATION_BUCKETS,
     },
     persist::{Persist, Persister, Routes},
-    rate_limiting::{generic, RateLimit},
-    retry::{retry_request, RetryParams},
+    rate_limiting::{generic, RateLimit, SubnetRateLimits},
+    retry::{retry_request, CircuitBreaker, RetryParams},
     routes::{self, ErrorCause, Health, Lookup, Proxy, ProxyRouter, RootKey},
     snapshot::{
         generate_stub_snapshot, generate_stub_subnet, RegistrySnapshot, SnapshotPersister,
@@ -187,7 +190,7 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
             Cache::new(
                 x,
                 cli.cache.cache_max_item_size_bytes,
-                Duration::from_secs(cli.cache.cache_ttl_seconds),
+                CacheTtls::from(&cli.cache),
                 cli.cache.cache_non_anonymous,
             )
             .expect("unable to initialize cache"),
@@ -243,15 +246,23 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
     });
 
     // HTTP Unix Socket
-    let server_http_unix = cli.listen.http_unix_socket.as_ref().map(|x| {
-        http::Server::new(
-            http::server::Addr::Unix(x.clone()),
-            router.clone(),
-            server_opts,
-            http_metrics.clone(),
-            None,
-        )
-    });
+    let server_http_unix = cli
+        .listen
+        .http_unix_socket
+        .as_ref()
+        .map(|x| -> Result<_, Error> {
+            remove_stale_unix_socket(x)?;
+
+            Ok(http::Server::new(
+                http::server::Addr::Unix(x.clone()),
+                router.clone(),
+                server_opts,
+                http_metrics.clone(),
+                None,
+            ))
+        })
+        .transpose()
+        .context("unable to setup HTTP Unix socket")?;
 
     // HTTPS
     #[cfg(feature = "tls")]
@@ -534,6 +545,27 @@ fn setup_registry(
     ))
 }
 
+// Removes a socket file left behind by a previous run, otherwise binding to it fails.
+// Paths that are not sockets or that still have a live listener are left untouched.
+fn remove_stale_unix_socket(path: &Path) -> Result<(), Error> {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(v) => v,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(Error::new(e).context(format!("unable to stat {}", path.display()))),
+    };
+
+    if !meta.file_type().is_socket() {
+        bail!("{} exists and is not a socket", path.display());
+    }
+
+    if UnixStream::connect(path).is_ok() {
+        bail!("{} is in use by another process", path.display());
+    }
+
+    warn!("Removing stale Unix socket {}", path.display());
+    fs::remove_file(path).with_context(|| format!("unable to remove {}", path.display()))
+}
+
 #[cfg(feature = "tls")]
 fn setup_tls_resolver_stub(cli: &cli::TlsConfig) -> Result<Arc<dyn ResolvesServerCert>, Error> {
     use ic_bn_lib::tls;
@@ -668,6 +700,7 @@ pub fn setup_router(
         http_client.clone(),
         Arc::clone(&routing_table),
         Arc::clone(&registry_snapshot),
+        Duration::from_secs(cli.health.max_snapshot_age),
     );
 
     let proxy_router = Arc::new(proxy_router);
@@ -708,6 +741,22 @@ pub fn setup_router(
                 .add_subnet_rate_limiting(route)
         }
 
+        if !cli
+            .rate_limiting
+            .rate_limit_per_second_for_subnet
+            .is_empty()
+        {
+            route = SubnetRateLimits::new(
+                cli.rate_limiting
+                    .rate_limit_per_second_for_subnet
+                    .iter()
+                    .copied()
+                    .collect(),
+            )
+            .expect("unable to setup per-subnet rate limits")
+            .add_rate_limiting(route)
+        }
+
         route
     };
 
@@ -726,7 +775,10 @@ pub fn setup_router(
 
     let middleware_geoip = option_layer(cli.monitoring.geoip_db.as_ref().map(|x| {
         middleware::from_fn_with_state(
-            Arc::new(geoip::GeoIp::new(x).expect("unable to load GeoIP")),
+            Arc::new(
+                geoip::GeoIp::new(x, cli.monitoring.geoip_override.clone())
+                    .expect("unable to load GeoIP"),
+            ),
             geoip::middleware,
         )
     }));
@@ -750,6 +802,12 @@ pub fn setup_router(
             retry_count: cli.retry.retry_count as usize,
             retry_update_call: cli.retry.retry_update_call,
             disable_latency_routing: cli.retry.disable_latency_routing,
+            circuit_breaker: cli.retry.retry_circuit_breaker_threshold.map(|x| {
+                Arc::new(CircuitBreaker::new(
+                    x,
+                    Duration::from_secs(cli.retry.retry_circuit_breaker_cooldown),
+                ))
+            }),
         },
         retry_request,
     );
@@ -792,6 +850,7 @@ pub fn setup_router(
         .layer(middlware_bouncer)
         .layer(middleware_geoip)
         .set_x_request_id(MakeRequestUuid)
+        .propagate_x_request_id()
         .layer(middleware_metrics)
         .layer(middleware_concurrency)
         .layer(middleware_shedding)
@@ -917,3 +976,6 @@ pub fn error_source<E: StdError + 'static>(error: &impl StdError) -> Option<&E>
 
     None
 }
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,92 @@
+use super::*;
+
+use std::os::unix::net::UnixListener;
+
+use axum::routing::get;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[test]
+fn test_remove_stale_unix_socket() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+
+    // Missing path is fine
+    let path = dir.path().join("missing.sock");
+    remove_stale_unix_socket(&path)?;
+
+    // Regular files are not touched
+    let path = dir.path().join("file");
+    fs::write(&path, "foo")?;
+    assert!(remove_stale_unix_socket(&path).is_err());
+    assert!(path.exists());
+
+    // Sockets with a live listener are not touched
+    let path = dir.path().join("live.sock");
+    let listener = UnixListener::bind(&path)?;
+    assert!(remove_stale_unix_socket(&path).is_err());
+    assert!(path.exists());
+
+    // Once the listener is gone the socket is stale and gets removed
+    drop(listener);
+    remove_stale_unix_socket(&path)?;
+    assert!(!path.exists());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_serve_unix_socket() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("ic-boundary.sock");
+
+    // Leave a stale socket behind as if from a previous run
+    drop(UnixListener::bind(&path)?);
+    remove_stale_unix_socket(&path)?;
+
+    let router = Router::new().route("/health", get(|| async { "ok" }));
+    let server = http::Server::new(
+        http::server::Addr::Unix(path.clone()),
+        router,
+        http::server::Options {
+            backlog: 128,
+            http1_header_read_timeout: Duration::from_secs(10),
+            http2_max_streams: 100,
+            http2_keepalive_interval: Duration::from_secs(10),
+            http2_keepalive_timeout: Duration::from_secs(10),
+            grace_period: Duration::from_secs(1),
+            max_requests_per_conn: None,
+        },
+        http::server::Metrics::new(&Registry::new()),
+        None,
+    );
+
+    let token = CancellationToken::new();
+    let child = token.clone();
+    let handle = tokio::spawn(async move { server.serve(child).await });
+
+    // Wait for the server to bind
+    let mut stream = None;
+    for _ in 0..50 {
+        if let Ok(v) = tokio::net::UnixStream::connect(&path).await {
+            stream = Some(v);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let mut stream = stream.expect("unable to connect to the Unix socket");
+
+    stream
+        .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.ends_with("ok"));
+
+    token.cancel();
+    handle
+        .await?
+        .map_err(|e| anyhow!("unable to serve: {e:#}"))?;
+
+    Ok(())
+}
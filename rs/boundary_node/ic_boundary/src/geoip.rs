@@ -1,6 +1,6 @@
-use std::{net::IpAddr, path::PathBuf, sync::Arc};
+use std::{net::IpAddr, path::PathBuf, str::FromStr, sync::Arc};
 
-use anyhow::Error;
+use anyhow::{anyhow, Context, Error};
 use axum::{
     extract::{Request, State},
     middleware::Next,
@@ -18,18 +18,114 @@ pub struct GeoData {
     pub country_code: String,
 }
 
+// IP network in CIDR notation, e.g. 10.0.0.0/8 or 2001:db8::/32
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IpPrefix {
+    addr: IpAddr,
+    len: u8,
+}
+
+impl IpPrefix {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.len as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.len as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpPrefix {
+    type Err = Error;
+
+    // A bare address is treated as a single-host prefix
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, len) = match s.split_once('/') {
+            Some((addr, len)) => (addr, Some(len)),
+            None => (s, None),
+        };
+
+        let addr = addr
+            .parse::<IpAddr>()
+            .context("unable to parse IP address")?;
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+
+        let len = match len {
+            Some(v) => v.parse::<u8>().context("unable to parse prefix length")?,
+            None => max_len,
+        };
+
+        if len > max_len {
+            return Err(anyhow!("prefix length should be <= {max_len}"));
+        }
+
+        Ok(Self { addr, len })
+    }
+}
+
+// Forces the clients from the given prefix to resolve to the given country code
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeoIpOverride {
+    pub prefix: IpPrefix,
+    pub country_code: String,
+}
+
+impl FromStr for GeoIpOverride {
+    type Err = Error;
+
+    // Expects <prefix>=<country_code> e.g. 10.0.0.0/8=CH
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prefix, country_code) = s
+            .split_once('=')
+            .ok_or(anyhow!("expected <prefix>=<country_code>"))?;
+
+        if country_code.is_empty() {
+            return Err(anyhow!("country code should not be empty"));
+        }
+
+        Ok(Self {
+            prefix: prefix.parse()?,
+            country_code: country_code.into(),
+        })
+    }
+}
+
+// Finds the most specific override that matches the given IP
+fn lookup_override(overrides: &[GeoIpOverride], ip: IpAddr) -> Option<&str> {
+    overrides
+        .iter()
+        .filter(|x| x.prefix.contains(ip))
+        .max_by_key(|x| x.prefix.len)
+        .map(|x| x.country_code.as_str())
+}
+
 pub struct GeoIp {
     db: maxminddb::Reader<Vec<u8>>,
+    overrides: Vec<GeoIpOverride>,
 }
 
 impl GeoIp {
-    pub fn new(db_path: &PathBuf) -> Result<Self, Error> {
+    pub fn new(db_path: &PathBuf, overrides: Vec<GeoIpOverride>) -> Result<Self, Error> {
         Ok(Self {
             db: maxminddb::Reader::open_readfile(db_path)?,
+            overrides,
         })
     }
 
+    // Overrides take precedence over the database
     pub fn lookup(&self, ip: IpAddr) -> String {
+        if let Some(v) = lookup_override(&self.overrides, ip) {
+            return v.into();
+        }
+
         let country: Option<geoip2::Country> = self.db.lookup(ip).ok();
 
         country
@@ -66,3 +162,6 @@ pub async fn middleware(
 
     Ok(response)
 }
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,74 @@
+use super::*;
+
+fn overrides(v: &[&str]) -> Vec<GeoIpOverride> {
+    v.iter().map(|x| x.parse().unwrap()).collect()
+}
+
+#[test]
+fn test_ip_prefix() {
+    let p = IpPrefix::from_str("10.0.0.0/8").unwrap();
+    assert!(p.contains("10.1.2.3".parse().unwrap()));
+    assert!(!p.contains("11.1.2.3".parse().unwrap()));
+    assert!(!p.contains("::ffff:10.1.2.3".parse().unwrap()));
+
+    let p = IpPrefix::from_str("2001:db8::/32").unwrap();
+    assert!(p.contains("2001:db8:1::1".parse().unwrap()));
+    assert!(!p.contains("2001:db9::1".parse().unwrap()));
+
+    // Zero-length prefix matches everything of the same family
+    let p = IpPrefix::from_str("0.0.0.0/0").unwrap();
+    assert!(p.contains("1.2.3.4".parse().unwrap()));
+
+    // Bare address is a single host
+    let p = IpPrefix::from_str("1.2.3.4").unwrap();
+    assert!(p.contains("1.2.3.4".parse().unwrap()));
+    assert!(!p.contains("1.2.3.5".parse().unwrap()));
+
+    assert!(IpPrefix::from_str("10.0.0.0/33").is_err());
+    assert!(IpPrefix::from_str("2001:db8::/129").is_err());
+    assert!(IpPrefix::from_str("foo/8").is_err());
+}
+
+#[test]
+fn test_geoip_override_parse() {
+    assert_eq!(
+        GeoIpOverride::from_str("10.0.0.0/8=CH").unwrap(),
+        GeoIpOverride {
+            prefix: IpPrefix::from_str("10.0.0.0/8").unwrap(),
+            country_code: "CH".into(),
+        }
+    );
+
+    assert!(GeoIpOverride::from_str("10.0.0.0/8").is_err());
+    assert!(GeoIpOverride::from_str("10.0.0.0/8=").is_err());
+}
+
+#[test]
+fn test_lookup_override() {
+    let overrides = overrides(&["10.0.0.0/8=CH", "10.1.0.0/16=DE", "2001:db8::/32=US"]);
+
+    assert_eq!(
+        lookup_override(&overrides, "10.2.3.4".parse().unwrap()),
+        Some("CH")
+    );
+    assert_eq!(
+        lookup_override(&overrides, "2001:db8::1".parse().unwrap()),
+        Some("US")
+    );
+    assert_eq!(
+        lookup_override(&overrides, "8.8.8.8".parse().unwrap()),
+        None
+    );
+
+    // The most specific prefix wins regardless of the order
+    assert_eq!(
+        lookup_override(&overrides, "10.1.2.3".parse().unwrap()),
+        Some("DE")
+    );
+    let mut reversed = overrides.clone();
+    reversed.reverse();
+    assert_eq!(
+        lookup_override(&reversed, "10.1.2.3".parse().unwrap()),
+        Some("DE")
+    );
+}
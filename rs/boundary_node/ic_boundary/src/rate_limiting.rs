@@ -1,10 +1,17 @@
-use std::{net::IpAddr, sync::Arc};
-
-use anyhow::anyhow;
-use axum::Router;
+use std::{collections::HashMap, net::IpAddr, sync::Arc};
+
+use anyhow::{anyhow, Error};
+use axum::{
+    body::Body,
+    extract::{Extension, State},
+    middleware::{self, Next},
+    response::IntoResponse,
+    Router,
+};
 use candid::Principal;
 use http::request::Request;
 use ic_bn_lib::http::ConnInfo;
+use ratelimit::Ratelimiter;
 use serde::{Deserialize, Serialize};
 use std::{convert::TryFrom, time::Duration};
 use tower::ServiceBuilder;
@@ -13,7 +20,10 @@ use tower_governor::{
     GovernorLayer,
 };
 
-use crate::persist::RouteSubnet;
+use crate::{
+    persist::RouteSubnet,
+    routes::{ErrorCause, RateLimitCause},
+};
 
 pub struct RateLimit {
     requests_per_second: u32, // requests per second allowed
@@ -104,6 +114,67 @@ impl RateLimit {
     }
 }
 
+// Rate limits for specific subnets, e.g. to protect a subnet under stress
+// without throttling the traffic to all other subnets.
+pub struct SubnetRateLimits {
+    limiters: HashMap<Principal, Ratelimiter>,
+}
+
+impl SubnetRateLimits {
+    // Allow the given number of requests per second to each subnet. Refill the rate over 1 second.
+    pub fn new(limits: HashMap<Principal, u32>) -> Result<Self, Error> {
+        let limiters = limits
+            .into_iter()
+            .map(|(subnet_id, requests_per_second)| {
+                let limit = RateLimit::try_from(requests_per_second)?;
+
+                let limiter = Ratelimiter::builder(
+                    1,
+                    Duration::from_secs(1)
+                        .checked_div(limit.requests_per_second)
+                        .unwrap(),
+                )
+                .max_tokens(limit.requests_per_second as u64)
+                .initial_available(limit.requests_per_second as u64)
+                .build()
+                .map_err(|e| anyhow!("unable to build rate limiter: {e:?}"))?;
+
+                Ok((subnet_id, limiter))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self { limiters })
+    }
+
+    // Subnets without a configured limit are not limited
+    fn acquire_token(&self, subnet_id: Principal) -> bool {
+        self.limiters
+            .get(&subnet_id)
+            .map(|x| x.try_wait().is_ok())
+            .unwrap_or(true)
+    }
+
+    pub fn add_rate_limiting(self, router: Router) -> Router {
+        router.layer(middleware::from_fn_with_state(
+            Arc::new(self),
+            subnet_rate_limits_middleware,
+        ))
+    }
+}
+
+async fn subnet_rate_limits_middleware(
+    State(state): State<Arc<SubnetRateLimits>>,
+    Extension(subnet): Extension<Arc<RouteSubnet>>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<impl IntoResponse, ErrorCause> {
+    if !state.acquire_token(subnet.id) {
+        return Err(ErrorCause::RateLimited(RateLimitCause::Normal));
+    }
+
+    Ok(next.run(request).await)
+}
+
 pub mod generic;
 
 #[cfg(test)]
@@ -143,6 +143,43 @@ async fn test_subnet_rate_limit() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_subnet_rate_limits() -> Result<(), Error> {
+    let subnet_id_1 = "f7crg-kabae";
+    let subnet_id_2 = "sqjm4-qahae-aq";
+
+    let app = Router::new().route("/", post(dummy_call));
+    let app = SubnetRateLimits::new(HashMap::from([(
+        Principal::from_text(subnet_id_1).unwrap(),
+        1,
+    )]))?
+    .add_rate_limiting(app);
+    let mut app = app
+        .layer(middleware::from_fn(body_to_subnet_context))
+        .layer(middleware::from_fn(add_ip_to_request));
+
+    // The limited subnet gets throttled
+    let response = app.call(request_with_subnet_id(subnet_id_1)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.call(request_with_subnet_id(subnet_id_1)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Other subnets are not affected
+    for _ in 0..3 {
+        let response = app.call(request_with_subnet_id(subnet_id_2)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Zero limits are rejected
+    assert!(SubnetRateLimits::new(HashMap::from([(
+        Principal::from_text(subnet_id_1).unwrap(),
+        0
+    )]))
+    .is_err());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_subnet_rate_limit_with_router() -> Result<(), Error> {
     let (mut app, _) = setup_test_router(false, false, 10, 1, 1024, Some(1));
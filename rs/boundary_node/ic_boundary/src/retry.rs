@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{
     body::{to_bytes, Body},
@@ -7,6 +10,8 @@ use axum::{
     response::{IntoResponse, Response},
     Extension,
 };
+use candid::Principal;
+use dashmap::DashMap;
 use http::StatusCode;
 
 use crate::{
@@ -20,6 +25,82 @@ pub struct RetryParams {
     pub retry_count: usize,
     pub retry_update_call: bool,
     pub disable_latency_routing: bool,
+    pub circuit_breaker: Option<Arc<CircuitBreaker>>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    // Number of consecutive failed requests
+    failures: u32,
+    // When the breaker was opened (or the last probe request was let through)
+    opened_at: Option<Instant>,
+}
+
+// Stops routing requests to the nodes that have failed `threshold` times in a row.
+// This complements the health checks, which take some time to notice a dead node.
+//
+// Once opened, the breaker stays open for the `cooldown` period. After that it's half-open:
+// a single probe request is let through every `cooldown` until one succeeds and closes the breaker.
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    states: DashMap<Principal, BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            states: DashMap::new(),
+        }
+    }
+
+    // Checks if requests can be sent to the node.
+    // If the breaker is half-open this lets the probe request through and re-arms the cooldown.
+    fn is_available(&self, node_id: &Principal) -> bool {
+        let mut state = match self.states.get_mut(node_id) {
+            Some(v) => v,
+            None => return true,
+        };
+
+        match state.opened_at {
+            Some(v) if v.elapsed() < self.cooldown => false,
+            Some(_) => {
+                state.opened_at = Some(Instant::now());
+                true
+            }
+            None => true,
+        }
+    }
+
+    fn record(&self, node_id: &Principal, failed: bool) {
+        if !failed {
+            self.states.remove(node_id);
+            return;
+        }
+
+        let mut state = self.states.entry(*node_id).or_default();
+        state.failures += 1;
+        if state.failures >= self.threshold {
+            state.opened_at = Some(Instant::now());
+        }
+    }
+
+    // Removes the nodes with an open breaker, unless it's all of them
+    fn filter_nodes(&self, nodes: Vec<Arc<Node>>) -> Vec<Arc<Node>> {
+        let available = nodes
+            .iter()
+            .filter(|x| self.is_available(&x.id))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if available.is_empty() {
+            nodes
+        } else {
+            available
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -59,12 +140,25 @@ pub async fn retry_request(
     mut request: Request,
     next: Next,
 ) -> Result<impl IntoResponse, ApiError> {
-    // Select up to 1+retry_count nodes from the subnet if there are any
+    // Select up to 1+retry_count nodes from the subnet if there are any.
+    // With the circuit breaker we pick all the candidates to be able to skip the ones with an open breaker.
+    let count = 1 + params.retry_count;
+    let breaker = params.circuit_breaker.as_ref();
+
     let nodes = if !params.disable_latency_routing && (ctx.request_type.is_call()) {
         let factor = subnet.fault_tolerance_factor() + 1;
-        subnet.pick_n_out_of_m_closest(1 + params.retry_count, factor)?
+        subnet.pick_n_out_of_m_closest(breaker.map_or(count, |_| factor), factor)?
     } else {
-        subnet.pick_random_nodes(1 + params.retry_count)?
+        subnet.pick_random_nodes(breaker.map_or(count, |_| subnet.nodes.len()))?
+    };
+
+    let nodes = match breaker {
+        Some(v) => {
+            let mut nodes = v.filter_nodes(nodes);
+            nodes.truncate(count);
+            nodes
+        }
+        None => nodes,
     };
 
     // Skip retrying in certain cases
@@ -74,6 +168,9 @@ pub async fn retry_request(
         let node = nodes[0].clone();
         request.extensions_mut().insert(node.clone());
         let mut response = next.run(request).await;
+        if let Some(v) = breaker {
+            v.record(&node.id, request_needs_retrying(&response));
+        }
         response.extensions_mut().insert(node);
         return Ok(response);
     }
@@ -94,9 +191,13 @@ pub async fn retry_request(
         let mut request = Request::from_parts(parts.clone(), Body::from(body.clone()));
         request.extensions_mut().insert(node.clone());
         let mut response = next.clone().run(request).await;
+        let needs_retrying = request_needs_retrying(&response);
+        if let Some(v) = breaker {
+            v.record(&node.id, needs_retrying);
+        }
 
         // Stop if the request does not need retrying
-        if !request_needs_retrying(&response) {
+        if !needs_retrying {
             if retry_result.retries > 0 {
                 retry_result.success = true;
                 response.extensions_mut().insert(retry_result);
@@ -3,6 +3,7 @@ use super::*;
 use std::{
     str::FromStr,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 
 use anyhow::Error;
@@ -15,7 +16,10 @@ use http::StatusCode;
 use ic_types::CanisterId;
 use tower::Service;
 
-use crate::routes::{test::test_route_subnet, RequestType};
+use crate::routes::{
+    test::{test_node, test_route_subnet},
+    RequestType,
+};
 
 struct TestState {
     failures: u8,
@@ -78,6 +82,7 @@ async fn test_retry() -> Result<(), Error> {
                 retry_count: 3,
                 retry_update_call: false,
                 disable_latency_routing: true,
+                circuit_breaker: None,
             },
             retry_request,
         ));
@@ -144,6 +149,7 @@ async fn test_retry() -> Result<(), Error> {
                 retry_count: 3,
                 retry_update_call: true,
                 disable_latency_routing: true,
+                circuit_breaker: None,
             },
             retry_request,
         ));
@@ -159,3 +165,81 @@ async fn test_retry() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_circuit_breaker() {
+    let cb = CircuitBreaker::new(3, Duration::from_millis(100));
+    let node = test_node(0).id;
+    let other = test_node(1).id;
+
+    // Stays closed below the threshold, success resets the counter
+    cb.record(&node, true);
+    cb.record(&node, true);
+    cb.record(&node, false);
+    cb.record(&node, true);
+    cb.record(&node, true);
+    assert!(cb.is_available(&node));
+
+    // Opens after N consecutive failures
+    cb.record(&node, true);
+    assert!(!cb.is_available(&node));
+    assert!(cb.is_available(&other));
+
+    // Half-opens after the cooldown and lets a single probe through
+    std::thread::sleep(Duration::from_millis(150));
+    assert!(cb.is_available(&node));
+    assert!(!cb.is_available(&node));
+
+    // Failed probe keeps it open for another cooldown
+    cb.record(&node, true);
+    assert!(!cb.is_available(&node));
+    std::thread::sleep(Duration::from_millis(150));
+    assert!(cb.is_available(&node));
+
+    // Successful probe closes it
+    cb.record(&node, false);
+    assert!(cb.is_available(&node));
+    assert!(cb.is_available(&node));
+}
+
+// Fails the requests routed to the first test node
+async fn handler_failing_node(Extension(node): Extension<Arc<Node>>) -> impl IntoResponse {
+    if node.id == test_node(0).id {
+        StatusCode::INTERNAL_SERVER_ERROR
+    } else {
+        StatusCode::OK
+    }
+}
+
+#[tokio::test]
+async fn test_retry_circuit_breaker() -> Result<(), Error> {
+    let cb = Arc::new(CircuitBreaker::new(2, Duration::from_secs(3600)));
+
+    let mut app =
+        Router::new()
+            .route("/", post(handler_failing_node))
+            .layer(middleware::from_fn_with_state(
+                RetryParams {
+                    retry_count: 0,
+                    retry_update_call: false,
+                    disable_latency_routing: true,
+                    circuit_breaker: Some(Arc::clone(&cb)),
+                },
+                retry_request,
+            ));
+
+    // Only the first N requests to the failing node get through, then it's skipped
+    let mut failures = 0;
+    for _ in 0..100 {
+        let req = gen_request(RequestType::Query);
+        let res = app.call(req).await.unwrap();
+        if res.status() != StatusCode::OK {
+            failures += 1;
+        }
+    }
+
+    assert_eq!(failures, 2);
+    assert!(!cb.is_available(&test_node(0).id));
+
+    Ok(())
+}
@@ -3,7 +3,7 @@ use std::{
     hash::{Hash, Hasher},
     str::FromStr,
     sync::Arc,
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::anyhow;
@@ -19,7 +19,9 @@ use axum::{
 };
 use bytes::Bytes;
 use candid::{CandidType, Decode, Principal};
-use http::header::{HeaderValue, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS};
+use http::header::{
+    HeaderName, HeaderValue, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+};
 use ic_bn_lib::http::{
     body::buffer_body, headers::*, proxy, Client as HttpClient, Error as IcBnError,
 };
@@ -49,6 +51,10 @@ const IC_API_VERSION: &str = "0.18.0";
 pub const ANONYMOUS_PRINCIPAL: Principal = Principal::anonymous();
 const METHOD_HTTP: &str = "http_request";
 
+const X_IC_SNAPSHOT_VERSION: HeaderName = HeaderName::from_static("x-ic-snapshot-version");
+const X_IC_SNAPSHOT_TIMESTAMP: HeaderName = HeaderName::from_static("x-ic-snapshot-timestamp");
+const X_IC_SNAPSHOT_FRESH: HeaderName = HeaderName::from_static("x-ic-snapshot-fresh");
+
 const HEADERS_HIDE_HTTP_REQUEST: [&str; 4] =
     ["x-real-ip", "x-forwarded-for", "x-request-id", "user-agent"];
 
@@ -307,9 +313,39 @@ pub trait Lookup: Sync + Send {
     fn lookup_subnet_by_id(&self, id: &SubnetId) -> Result<Arc<RouteSubnet>, ErrorCause>;
 }
 
+// Information about the last published registry snapshot
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotHealth {
+    pub version: u64,
+    // Seconds since UNIX epoch
+    pub timestamp: u64,
+    // Whether the snapshot is not older than the configured max age
+    pub fresh: bool,
+}
+
+impl SnapshotHealth {
+    pub fn new(version: u64, timestamp: u64, now: SystemTime, max_age: Duration) -> Self {
+        let now = now
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_secs();
+
+        Self {
+            version,
+            timestamp,
+            fresh: now.saturating_sub(timestamp) <= max_age.as_secs(),
+        }
+    }
+}
+
 #[async_trait]
 pub trait Health: Sync + Send {
     async fn health(&self) -> ReplicaHealthStatus;
+
+    // Returns None if no snapshot was published yet
+    async fn snapshot_health(&self) -> Option<SnapshotHealth> {
+        None
+    }
 }
 
 #[async_trait]
@@ -324,6 +360,7 @@ pub struct ProxyRouter {
     http_client: Arc<dyn HttpClient>,
     published_routes: Arc<ArcSwapOption<Routes>>,
     published_registry_snapshot: Arc<ArcSwapOption<RegistrySnapshot>>,
+    snapshot_max_age: Duration,
 }
 
 impl ProxyRouter {
@@ -331,11 +368,13 @@ impl ProxyRouter {
         http_client: Arc<dyn HttpClient>,
         published_routes: Arc<ArcSwapOption<Routes>>,
         published_registry_snapshot: Arc<ArcSwapOption<RegistrySnapshot>>,
+        snapshot_max_age: Duration,
     ) -> Self {
         Self {
             http_client,
             published_routes,
             published_registry_snapshot,
+            snapshot_max_age,
         }
     }
 }
@@ -408,6 +447,17 @@ impl Health for ProxyRouter {
             None => ReplicaHealthStatus::Starting,
         }
     }
+
+    async fn snapshot_health(&self) -> Option<SnapshotHealth> {
+        self.published_registry_snapshot.load_full().map(|x| {
+            SnapshotHealth::new(
+                x.version,
+                x.timestamp,
+                SystemTime::now(),
+                self.snapshot_max_age,
+            )
+        })
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -751,12 +801,27 @@ pub async fn postprocess_response(request: Request, next: Next) -> impl IntoResp
 }
 
 // Handler: emit an HTTP status code that signals the service's state
+// and the version, timestamp & freshness of the last registry snapshot in headers
 pub async fn health(State(h): State<Arc<dyn Health>>) -> impl IntoResponse {
-    if h.health().await == ReplicaHealthStatus::Healthy {
+    let status = if h.health().await == ReplicaHealthStatus::Healthy {
         StatusCode::NO_CONTENT
     } else {
         StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let mut response = status.into_response();
+
+    if let Some(v) = h.snapshot_health().await {
+        let headers = response.headers_mut();
+        headers.insert(X_IC_SNAPSHOT_VERSION, HeaderValue::from(v.version));
+        headers.insert(X_IC_SNAPSHOT_TIMESTAMP, HeaderValue::from(v.timestamp));
+        headers.insert(
+            X_IC_SNAPSHOT_FRESH,
+            HeaderValue::from_static(if v.fresh { "true" } else { "false" }),
+        );
     }
+
+    response
 }
 
 // Handler: processes IC status call
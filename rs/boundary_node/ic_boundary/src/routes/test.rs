@@ -70,6 +70,7 @@ fn assert_header(headers: &http::HeaderMap, name: HeaderName, expected_value: &s
 struct ProxyRouter {
     root_key: Vec<u8>,
     health: Arc<Mutex<ReplicaHealthStatus>>,
+    snapshot_health: Arc<Mutex<Option<SnapshotHealth>>>,
 }
 
 impl ProxyRouter {
@@ -112,6 +113,10 @@ impl Health for ProxyRouter {
     async fn health(&self) -> ReplicaHealthStatus {
         *self.health.lock().unwrap()
     }
+
+    async fn snapshot_health(&self) -> Option<SnapshotHealth> {
+        self.snapshot_health.lock().unwrap().clone()
+    }
 }
 
 #[tokio::test]
@@ -323,6 +328,7 @@ async fn test_health() -> Result<(), Error> {
     let proxy_router = Arc::new(ProxyRouter {
         root_key: root_key.clone(),
         health: Arc::new(Mutex::new(ReplicaHealthStatus::Healthy)),
+        snapshot_health: Arc::new(Mutex::new(None)),
     });
 
     let state_health = proxy_router.clone() as Arc<dyn Health>;
@@ -353,6 +359,83 @@ async fn test_health() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_snapshot_health_freshness() {
+    let max_age = Duration::from_secs(60);
+    let published = UNIX_EPOCH + Duration::from_secs(1_000_000);
+
+    let h = SnapshotHealth::new(7, 1_000_000, published, max_age);
+    assert_eq!(
+        h,
+        SnapshotHealth {
+            version: 7,
+            timestamp: 1_000_000,
+            fresh: true,
+        }
+    );
+
+    // Still fresh at the threshold
+    let h = SnapshotHealth::new(7, 1_000_000, published + max_age, max_age);
+    assert!(h.fresh);
+
+    // Stale once the time advances past the threshold
+    let h = SnapshotHealth::new(
+        7,
+        1_000_000,
+        published + max_age + Duration::from_secs(1),
+        max_age,
+    );
+    assert!(!h.fresh);
+}
+
+#[tokio::test]
+async fn test_health_snapshot_headers() -> Result<(), Error> {
+    let proxy_router = Arc::new(ProxyRouter {
+        root_key: vec![],
+        health: Arc::new(Mutex::new(ReplicaHealthStatus::Healthy)),
+        snapshot_health: Arc::new(Mutex::new(None)),
+    });
+
+    let state_health = proxy_router.clone() as Arc<dyn Health>;
+    let mut app = Router::new().route(PATH_HEALTH, get(health).with_state(state_health));
+
+    let request = || {
+        Request::builder()
+            .method("GET")
+            .uri("http://localhost/health")
+            .body(Body::from(""))
+            .unwrap()
+    };
+
+    // No snapshot yet -> no headers
+    let resp = app.call(request()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert!(!resp.headers().contains_key(X_IC_SNAPSHOT_FRESH));
+
+    *proxy_router.snapshot_health.lock().unwrap() = Some(SnapshotHealth {
+        version: 42,
+        timestamp: 1_000_000,
+        fresh: true,
+    });
+    let resp = app.call(request()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_header(resp.headers(), X_IC_SNAPSHOT_VERSION, "42");
+    assert_header(resp.headers(), X_IC_SNAPSHOT_TIMESTAMP, "1000000");
+    assert_header(resp.headers(), X_IC_SNAPSHOT_FRESH, "true");
+
+    proxy_router
+        .snapshot_health
+        .lock()
+        .unwrap()
+        .as_mut()
+        .unwrap()
+        .fresh = false;
+    let resp = app.call(request()).await.unwrap();
+    assert_header(resp.headers(), X_IC_SNAPSHOT_FRESH, "false");
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_status() -> Result<(), Error> {
     let root_key = vec![8, 6, 7, 5, 3, 0, 9];
@@ -360,6 +443,7 @@ async fn test_status() -> Result<(), Error> {
     let proxy_router = Arc::new(ProxyRouter {
         root_key: root_key.clone(),
         health: Arc::new(Mutex::new(ReplicaHealthStatus::Healthy)),
+        snapshot_health: Arc::new(Mutex::new(None)),
     });
 
     let (state_rootkey, state_health) = (
@@ -680,3 +764,90 @@ async fn test_all_call_types() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[derive(Clone, Default)]
+struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl std::io::Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_request_id_propagation() -> Result<(), Error> {
+    let logs = LogBuffer::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let (mut app, _) = setup_test_router(false, true, 10, 1, 1024, None);
+
+    let sender = Principal::from_text("sqjm4-qahae-aq").unwrap();
+    let canister_id = CanisterId::from_u64(100);
+
+    let content = HttpQueryContent::Query {
+        query: HttpUserQuery {
+            canister_id: Blob(canister_id.get().as_slice().to_vec()),
+            method_name: "foobar".to_string(),
+            arg: Blob(vec![]),
+            sender: Blob(sender.as_slice().to_vec()),
+            nonce: None,
+            ingress_expiry: 1234,
+        },
+    };
+
+    let envelope = HttpRequestEnvelope::<HttpQueryContent> {
+        content,
+        sender_delegation: None,
+        sender_pubkey: None,
+        sender_sig: None,
+    };
+
+    // No 'x-request-id' header, a random uuid should be generated and echoed back
+    let request = Request::builder()
+        .method("POST")
+        .uri(format!(
+            "http://localhost/api/v2/canister/{canister_id}/query"
+        ))
+        .body(Body::from(serde_cbor::to_vec(&envelope).unwrap()))
+        .unwrap();
+
+    let resp = app.call(request).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let request_id = resp
+        .headers()
+        .get(X_REQUEST_ID)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(UUID_REGEX.is_match(&request_id));
+
+    // The request is logged once the body is fully streamed
+    axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    let mut found = false;
+    for _ in 0..50 {
+        let logs = String::from_utf8_lossy(&logs.0.lock().unwrap()).to_string();
+        if logs.contains(&request_id) {
+            found = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(found, "request id {request_id} not found in the logs");
+
+    Ok(())
+}
@@ -37,7 +37,7 @@ use rand::Rng;
 use reqwest;
 
 use crate::{
-    cache::Cache,
+    cache::{Cache, CacheTtls},
     cli::Cli,
     core::setup_router,
     persist::{Persist, Persister, Routes},
@@ -323,7 +323,13 @@ pub fn setup_test_router(
         &cli,
         &metrics_registry,
         enable_cache.then_some(Arc::new(
-            Cache::new(10485760, 262144, Duration::from_secs(1), false).unwrap(),
+            Cache::new(
+                10485760,
+                262144,
+                CacheTtls::new(Duration::from_secs(1)),
+                false,
+            )
+            .unwrap(),
         )),
     );
 
@@ -19,37 +19,60 @@ pub struct ConfigIniSettings {
     pub verbose: bool,
 }
 
-// Prefix should have a max length of 19 ("1234:6789:1234:6789")
-// It could have fewer characters though. Parsing as an ip address with trailing '::' should work.
-fn is_valid_ipv6_prefix(ipv6_prefix: &str) -> bool {
-    ipv6_prefix.len() <= 19 && format!("{ipv6_prefix}::").parse::<Ipv6Addr>().is_ok()
+// Parsing the prefix as an ip address with trailing '::' should work, e.g. "1234:6789:1234:6789"
+// for a /64 subnet. All bits beyond `prefix_length` (the host bits) must be zero.
+//
+// Addresses are generated by appending the interface identifier to the prefix as text, so the
+// prefix must also consist of exactly the groups covering `prefix_length`, without '::'.
+pub(crate) fn is_valid_ipv6_prefix(ipv6_prefix: &str, prefix_length: u8) -> bool {
+    if prefix_length > 128
+        || ipv6_prefix.contains("::")
+        || ipv6_prefix.split(':').count() != usize::from(prefix_length.div_ceil(16))
+    {
+        return false;
+    }
+    match format!("{ipv6_prefix}::").parse::<Ipv6Addr>() {
+        Ok(address) => {
+            let host_mask = u128::MAX.checked_shr(u32::from(prefix_length)).unwrap_or(0);
+            u128::from(address) & host_mask == 0
+        }
+        Err(_) => false,
+    }
+}
+
+/// Returns the value of a required `key`, or a "Missing required config key"
+/// error if it is absent.
+pub fn require_key<'a>(config_map: &'a ConfigMap, key: &str) -> Result<&'a str> {
+    config_map
+        .get(key)
+        .map(String::as_str)
+        .with_context(|| format!("Missing required config key: {}", key))
+}
+
+/// Returns the value of an optional `key`, if present.
+pub fn optional_key<'a>(config_map: &'a ConfigMap, key: &str) -> Option<&'a str> {
+    config_map.get(key).map(String::as_str)
 }
 
 pub fn get_config_ini_settings(config_file_path: &Path) -> Result<ConfigIniSettings> {
     let config_map: ConfigMap = config_map_from_path(config_file_path)?;
 
-    let ipv6_prefix = config_map
-        .get("ipv6_prefix")
-        .context("Missing config parameter: ipv6_prefix")
-        .and_then(|prefix| {
-            if is_valid_ipv6_prefix(prefix) {
-                Ok(prefix.clone())
-            } else {
-                bail!("Invalid ipv6 prefix: {}", prefix)
-            }
-        })?;
-
     // Per PFOPS - ipv6_prefix_length will always be 64
     let ipv6_prefix_length = 64_u8;
 
-    let ipv6_gateway = config_map
-        .get("ipv6_gateway")
-        .context("Missing config parameter: ipv6_gateway")?
+    let ipv6_prefix = require_key(&config_map, "ipv6_prefix").and_then(|prefix| {
+        if is_valid_ipv6_prefix(prefix, ipv6_prefix_length) {
+            Ok(prefix.to_string())
+        } else {
+            bail!("Invalid ipv6 prefix: {}", prefix)
+        }
+    })?;
+
+    let ipv6_gateway = require_key(&config_map, "ipv6_gateway")?
         .parse::<Ipv6Addr>()
         .context("Invalid IPv6 gateway address")?;
 
-    let ipv4_address = config_map
-        .get("ipv4_address")
+    let ipv4_address = optional_key(&config_map, "ipv4_address")
         .map(|address| {
             address
                 .parse::<Ipv4Addr>()
@@ -57,8 +80,7 @@ pub fn get_config_ini_settings(config_file_path: &Path) -> Result<ConfigIniSetti
         })
         .transpose()?;
 
-    let ipv4_gateway = config_map
-        .get("ipv4_gateway")
+    let ipv4_gateway = optional_key(&config_map, "ipv4_gateway")
         .map(|address| {
             address
                 .parse::<Ipv4Addr>()
@@ -66,8 +88,7 @@ pub fn get_config_ini_settings(config_file_path: &Path) -> Result<ConfigIniSetti
         })
         .transpose()?;
 
-    let ipv4_prefix_length = config_map
-        .get("ipv4_prefix_length")
+    let ipv4_prefix_length = optional_key(&config_map, "ipv4_prefix_length")
         .map(|prefix| {
             let prefix = prefix
                 .parse::<u8>()
@@ -82,11 +103,10 @@ pub fn get_config_ini_settings(config_file_path: &Path) -> Result<ConfigIniSetti
         })
         .transpose()?;
 
-    let domain = config_map.get("domain").cloned();
+    let domain = optional_key(&config_map, "domain").map(str::to_string);
 
-    let verbose = config_map
-        .get("verbose")
-        .is_some_and(|s| s.eq_ignore_ascii_case("true"));
+    let verbose =
+        optional_key(&config_map, "verbose").is_some_and(|s| s.eq_ignore_ascii_case("true"));
 
     Ok(ConfigIniSettings {
         ipv6_prefix,
@@ -161,14 +181,22 @@ mod tests {
     #[test]
     fn test_is_valid_ipv6_prefix() {
         // Valid prefixes
-        assert!(is_valid_ipv6_prefix("2a00:1111:1111:1111"));
-        assert!(is_valid_ipv6_prefix("2a00:111:11:11"));
-        assert!(is_valid_ipv6_prefix("2602:fb2b:100:10"));
+        assert!(is_valid_ipv6_prefix("2a00:1111:1111:1111", 64));
+        assert!(is_valid_ipv6_prefix("2a00:111:11:11", 64));
+        assert!(is_valid_ipv6_prefix("2602:fb2b:100:10", 64));
+        assert!(is_valid_ipv6_prefix("2a00:fb01:400", 48));
+        // Longer than 19 characters
+        assert!(is_valid_ipv6_prefix("2a00:fb01:0400:0200:0001", 80));
 
         // Invalid prefixes
-        assert!(!is_valid_ipv6_prefix("2a00:1111:1111:1111:")); // Trailing colon
-        assert!(!is_valid_ipv6_prefix("2a00:1111:1111:1111:1111:1111")); // Too long
-        assert!(!is_valid_ipv6_prefix("abcd::1234:5678")); // Contains "::"
+        assert!(!is_valid_ipv6_prefix("2a00:1111:1111:1111:", 64)); // Trailing colon
+        assert!(!is_valid_ipv6_prefix("2a00:1111:1111:1111:1111:1111", 64)); // Too long
+        assert!(!is_valid_ipv6_prefix("abcd::1234:5678", 64)); // Contains "::"
+        assert!(!is_valid_ipv6_prefix("2a00:fb01:400:200", 48)); // Host bits set
+        assert!(!is_valid_ipv6_prefix("2a00:fb01:400:200:0", 64)); // Too many groups
+        assert!(!is_valid_ipv6_prefix("2a00:fb01:0400:0000:0000", 48)); // Too many groups
+        assert!(!is_valid_ipv6_prefix("2a00:fb01", 48)); // Too few groups
+        assert!(!is_valid_ipv6_prefix("2a00:fb01:400:200", 129)); // Invalid length
     }
 
     #[test]
@@ -296,6 +324,15 @@ mod tests {
         let result = get_config_ini_settings(temp_file_path);
         assert!(result.is_err());
 
+        // Test missing gateway
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "ipv6_prefix=2a00:fb01:400:200")?;
+        let result = get_config_ini_settings(temp_file.path());
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Missing required config key: ipv6_gateway"
+        );
+
         Ok(())
     }
 }
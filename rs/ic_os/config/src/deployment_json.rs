@@ -1,7 +1,8 @@
 use std::fs::File;
 use std::path::Path;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use url::Url;
@@ -47,6 +48,35 @@ pub fn get_deployment_settings(deployment_json: &Path) -> Result<DeploymentSetti
     serde_json::from_reader(&file).context("Invalid json content")
 }
 
+/// Validate `deployment_settings` and write them to `deployment_json`.
+pub fn write_deployment_json(
+    deployment_json: &Path,
+    deployment_settings: &DeploymentSettings,
+) -> Result<()> {
+    validate_deployment_settings(deployment_settings)?;
+    crate::serialize_and_write_config(deployment_json, deployment_settings)
+        .context("failed to write deployment config file")
+}
+
+fn validate_deployment_settings(deployment_settings: &DeploymentSettings) -> Result<()> {
+    let deployment = &deployment_settings.deployment;
+    if deployment.name.trim().is_empty() {
+        bail!("Deployment name must not be empty");
+    }
+
+    if let Some(mgmt_mac) = &deployment.mgmt_mac {
+        let mac_regex = Regex::new(r"^([0-9A-Fa-f]{2}:){5}[0-9A-Fa-f]{2}$").unwrap();
+        if !mac_regex.is_match(mgmt_mac) {
+            bail!(
+                "Invalid mgmt_mac. Must be formatted as MAC address with colons: {}",
+                mgmt_mac
+            );
+        }
+    }
+
+    Ok(())
+}
+
 mod comma_urls {
     use serde::{de, Deserialize, Deserializer, Serializer};
     use url::Url;
@@ -391,4 +421,40 @@ mod test {
 
         assert_eq!(MULTI_URL_STR, serialized_deployment);
     }
+
+    #[test]
+    fn write_and_read_deployment() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deployment.json");
+
+        let mut deployment = DeploymentSettings {
+            deployment: Deployment {
+                name: "mainnet".to_string(),
+                mgmt_mac: Some("00:1A:2b:3c:4D:5e".to_string()),
+            },
+            logging: Logging {
+                hosts: DEPLOYMENT_STRUCT.logging.hosts.clone(),
+            },
+            nns: Nns {
+                url: DEPLOYMENT_STRUCT.nns.url.clone(),
+            },
+            resources: Resources {
+                memory: 490,
+                cpu: Some("kvm".to_string()),
+            },
+        };
+
+        write_deployment_json(&path, &deployment).unwrap();
+        assert_eq!(deployment, get_deployment_settings(&path).unwrap());
+
+        deployment.deployment.mgmt_mac = Some("00:1a:2b:3c:4d".to_string());
+        assert!(write_deployment_json(&path, &deployment).is_err());
+
+        deployment.deployment.mgmt_mac = Some("00:1a:2b:3c:4d:zz".to_string());
+        assert!(write_deployment_json(&path, &deployment).is_err());
+
+        deployment.deployment.mgmt_mac = None;
+        deployment.deployment.name = "".to_string();
+        assert!(write_deployment_json(&path, &deployment).is_err());
+    }
 }
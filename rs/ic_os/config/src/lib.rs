@@ -21,9 +21,14 @@ pub static DEFAULT_SETUPOS_HOSTOS_CONFIG_OBJECT_PATH: &str = "/var/ic/config/con
 pub static DEFAULT_HOSTOS_CONFIG_INI_FILE_PATH: &str = "/boot/config/config.ini";
 pub static DEFAULT_HOSTOS_DEPLOYMENT_JSON_PATH: &str = "/boot/config/deployment.json";
 
+/// Renders `config` as pretty-printed JSON, as written by
+/// `serialize_and_write_config`, without touching the filesystem.
+pub fn render_config<T: Serialize>(config: &T) -> Result<String> {
+    serde_json::to_string_pretty(config).context("Failed to serialize configuration")
+}
+
 pub fn serialize_and_write_config<T: Serialize>(path: &Path, config: &T) -> Result<()> {
-    let serialized_config =
-        serde_json::to_string_pretty(config).expect("Failed to serialize configuration");
+    let serialized_config = render_config(config)?;
 
     if let Some(parent) = path.parent() {
         create_dir_all(parent)?;
@@ -131,5 +136,11 @@ mod tests {
         serialize_and_deserialize(&setupos_config_struct);
         serialize_and_deserialize(&hostos_config_struct);
         serialize_and_deserialize(&guestos_config_struct);
+
+        // Test rendering without writing to disk
+        let rendered = render_config(&setupos_config_struct).expect("Failed to render config");
+        let deserialized_config: SetupOSConfig =
+            serde_json::from_str(&rendered).expect("Failed to deserialize rendered config");
+        assert_eq!(setupos_config_struct, deserialized_config);
     }
 }
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::{Parser, Subcommand};
 use config::config_ini::{get_config_ini_settings, ConfigIniSettings};
 use config::deployment_json::get_deployment_settings;
@@ -97,6 +97,9 @@ pub fn main() -> Result<()> {
                 ipv6_config: Ipv6Config::Deterministic(deterministic_config),
                 ipv4_config,
             };
+            network_settings
+                .validate()
+                .map_err(|errors| anyhow!("Invalid network settings: {}", errors.join("; ")))?;
 
             // get deployment.json variables
             let deployment_json_settings = get_deployment_settings(&deployment_json_path)?;
@@ -165,6 +168,10 @@ pub fn main() -> Result<()> {
 
             let setupos_config: SetupOSConfig =
                 serde_json::from_reader(File::open(setupos_config_json_path)?)?;
+            setupos_config
+                .network_settings
+                .validate()
+                .map_err(|errors| anyhow!("Invalid network settings: {}", errors.join("; ")))?;
 
             let hostos_config = HostOSConfig {
                 network_settings: setupos_config.network_settings,
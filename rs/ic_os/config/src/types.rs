@@ -1,3 +1,4 @@
+use crate::config_ini::is_valid_ipv6_prefix;
 use ic_types::malicious_behaviour::MaliciousBehaviour;
 use serde::{Deserialize, Serialize};
 use std::net::{Ipv4Addr, Ipv6Addr};
@@ -121,6 +122,107 @@ pub struct NetworkSettings {
     pub ipv4_config: Option<Ipv4Config>,
 }
 
+impl NetworkSettings {
+    /// Check the settings for consistency, returning every problem found
+    /// rather than stopping at the first one.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        match &self.ipv6_config {
+            Ipv6Config::Deterministic(config) => {
+                if !is_valid_ipv6_prefix(&config.prefix, config.prefix_length) {
+                    errors.push(format!("Invalid IPv6 prefix: {}", config.prefix));
+                }
+                if config.prefix_length == 0 || config.prefix_length > 128 {
+                    errors.push(format!(
+                        "IPv6 prefix length must be between 1 and 128, got {}",
+                        config.prefix_length
+                    ));
+                }
+                if !is_usable_ipv6_address(&config.gateway) {
+                    errors.push(format!("Invalid IPv6 gateway: {}", config.gateway));
+                }
+            }
+            Ipv6Config::Fixed(config) => {
+                if !is_usable_ipv6_address(&config.address) {
+                    errors.push(format!("Invalid IPv6 address: {}", config.address));
+                }
+                if !is_usable_ipv6_address(&config.gateway) {
+                    errors.push(format!("Invalid IPv6 gateway: {}", config.gateway));
+                }
+                if config.address == config.gateway {
+                    errors.push(format!(
+                        "IPv6 address and gateway must differ, both are {}",
+                        config.address
+                    ));
+                }
+            }
+            Ipv6Config::RouterAdvertisement => {}
+        }
+
+        if let Some(config) = &self.ipv4_config {
+            if config.prefix_length > 32 {
+                errors.push(format!(
+                    "IPv4 prefix length must be between 0 and 32, got {}",
+                    config.prefix_length
+                ));
+            }
+            if !is_usable_ipv4_address(&config.address) {
+                errors.push(format!("Invalid IPv4 address: {}", config.address));
+            }
+            if !is_usable_ipv4_address(&config.gateway) {
+                errors.push(format!("Invalid IPv4 gateway: {}", config.gateway));
+            }
+            if config.address == config.gateway {
+                errors.push(format!(
+                    "IPv4 address and gateway must differ, both are {}",
+                    config.address
+                ));
+            } else if config.prefix_length <= 32 {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(config.prefix_length))
+                    .unwrap_or(0);
+                if u32::from(config.address) & mask != u32::from(config.gateway) & mask {
+                    errors.push(format!(
+                        "IPv4 gateway {} is not in the subnet of {}/{}",
+                        config.gateway, config.address, config.prefix_length
+                    ));
+                }
+            }
+            if !is_valid_domain(&config.domain) {
+                errors.push(format!("Invalid domain: {:?}", config.domain));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn is_usable_ipv6_address(address: &Ipv6Addr) -> bool {
+    !address.is_unspecified() && !address.is_multicast()
+}
+
+fn is_usable_ipv4_address(address: &Ipv4Addr) -> bool {
+    !address.is_unspecified() && !address.is_multicast() && !address.is_broadcast()
+}
+
+// Domain names are up to 253 characters of dot-separated labels, each 1-63
+// alphanumeric characters or hyphens, not starting or ending with a hyphen.
+fn is_valid_domain(domain: &str) -> bool {
+    domain.len() <= 253
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Ipv4Config {
     pub address: Ipv4Addr,
@@ -148,3 +250,78 @@ pub struct FixedIpv6Config {
     pub address: Ipv6Addr,
     pub gateway: Ipv6Addr,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_network_settings() {
+        let valid = NetworkSettings {
+            ipv6_config: Ipv6Config::Deterministic(DeterministicIpv6Config {
+                prefix: "2a00:fb01:400:200".to_string(),
+                prefix_length: 64,
+                gateway: "2a00:fb01:400:200::1".parse().unwrap(),
+            }),
+            ipv4_config: Some(Ipv4Config {
+                address: "212.71.124.178".parse().unwrap(),
+                gateway: "212.71.124.177".parse().unwrap(),
+                prefix_length: 28,
+                domain: "example.com".to_string(),
+            }),
+        };
+        assert_eq!(Ok(()), valid.validate());
+
+        let invalid = NetworkSettings {
+            ipv6_config: Ipv6Config::Deterministic(DeterministicIpv6Config {
+                prefix: "2a00:fb01:400:200:1234".to_string(),
+                prefix_length: 0,
+                gateway: "::".parse().unwrap(),
+            }),
+            ipv4_config: Some(Ipv4Config {
+                address: "212.71.124.178".parse().unwrap(),
+                gateway: "212.71.125.1".parse().unwrap(),
+                prefix_length: 28,
+                domain: "-example..com".to_string(),
+            }),
+        };
+        assert_eq!(
+            Err(vec![
+                "Invalid IPv6 prefix: 2a00:fb01:400:200:1234".to_string(),
+                "IPv6 prefix length must be between 1 and 128, got 0".to_string(),
+                "Invalid IPv6 gateway: ::".to_string(),
+                "IPv4 gateway 212.71.125.1 is not in the subnet of 212.71.124.178/28".to_string(),
+                "Invalid domain: \"-example..com\"".to_string(),
+            ]),
+            invalid.validate()
+        );
+
+        let invalid = NetworkSettings {
+            ipv6_config: Ipv6Config::Fixed(FixedIpv6Config {
+                address: "2a00:fb01:400:200::1".parse().unwrap(),
+                gateway: "2a00:fb01:400:200::1".parse().unwrap(),
+            }),
+            ipv4_config: Some(Ipv4Config {
+                address: "0.0.0.0".parse().unwrap(),
+                gateway: "255.255.255.255".parse().unwrap(),
+                prefix_length: 33,
+                domain: "example.com".to_string(),
+            }),
+        };
+        assert_eq!(
+            Err(vec![
+                "IPv6 address and gateway must differ, both are 2a00:fb01:400:200::1".to_string(),
+                "IPv4 prefix length must be between 0 and 32, got 33".to_string(),
+                "Invalid IPv4 address: 0.0.0.0".to_string(),
+                "Invalid IPv4 gateway: 255.255.255.255".to_string(),
+            ]),
+            invalid.validate()
+        );
+
+        let router_advertisement = NetworkSettings {
+            ipv6_config: Ipv6Config::RouterAdvertisement,
+            ipv4_config: None,
+        };
+        assert_eq!(Ok(()), router_advertisement.validate());
+    }
+}
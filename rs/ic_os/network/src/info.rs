@@ -2,7 +2,7 @@ use std::net::Ipv6Addr;
 
 use anyhow::{bail, Context, Result};
 
-use config::config_ini::ConfigMap;
+use config::config_ini::{require_key, ConfigMap};
 
 #[derive(Debug)]
 pub struct NetworkInfo {
@@ -20,20 +20,15 @@ impl NetworkInfo {
         // Per PFOPS - this will never not be 64
         let ipv6_subnet = 64_u8;
 
-        let ipv6_prefix = config_map
-            .get("ipv6_prefix")
-            .context("Missing config parameter: ipv6_prefix")
-            .and_then(|prefix| {
-                if is_valid_prefix(prefix) {
-                    Ok(prefix.clone())
-                } else {
-                    bail!("Invalid ipv6 prefix: {}", prefix)
-                }
-            })?;
+        let ipv6_prefix = require_key(config_map, "ipv6_prefix").and_then(|prefix| {
+            if is_valid_prefix(prefix) {
+                Ok(prefix.to_string())
+            } else {
+                bail!("Invalid ipv6 prefix: {}", prefix)
+            }
+        })?;
 
-        let ipv6_gateway = config_map
-            .get("ipv6_gateway")
-            .context("Missing config parameter: ipv6_gateway")?;
+        let ipv6_gateway = require_key(config_map, "ipv6_gateway")?;
         let ipv6_gateway = ipv6_gateway
             .parse::<Ipv6Addr>()
             .context(format!("Invalid ipv6 address: {}", ipv6_gateway))?;
@@ -1,8 +1,10 @@
+use std::collections::HashMap;
 use std::net::Ipv6Addr;
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 
 use crate::mac_address::UnformattedMacAddress;
+use crate::node_type::NodeType;
 use utils::intersperse;
 
 /// Generate a deterministic ipv6 address
@@ -35,9 +37,29 @@ pub fn generate_ipv6_address(
     Ok(address)
 }
 
+/// Error if any two of the given nodes were assigned the same ipv6 address
+pub fn assert_unique_ipv6(nodes: &[(NodeType, Ipv6Addr)]) -> Result<()> {
+    let mut seen: HashMap<Ipv6Addr, usize> = HashMap::new();
+    for (index, (node_type, address)) in nodes.iter().enumerate() {
+        if let Some(previous) = seen.insert(*address, index) {
+            bail!(
+                "Duplicate ipv6 address {}: node {} ({:?}) collides with node {} ({:?})",
+                address,
+                previous,
+                nodes[previous].0,
+                index,
+                node_type
+            );
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::mac_address::{generate_mac_address, FormattedMacAddress};
+
     #[test]
     fn test_generate_ipv6_address() {
         assert_eq!(
@@ -83,4 +105,30 @@ pub mod tests {
                 .unwrap()
         );
     }
+
+    #[test]
+    fn test_assert_unique_ipv6() {
+        let mgmt_mac = FormattedMacAddress::try_from("de:ad:de:ad:de:ad").unwrap();
+        let prefix = "2a00:fb01:400:100";
+        let address = |node_type: &NodeType| {
+            let mac = generate_mac_address(&mgmt_mac, "mainnet", node_type).unwrap();
+            generate_ipv6_address(prefix, &mac).unwrap()
+        };
+
+        let nodes = [
+            (NodeType::HostOS, address(&NodeType::HostOS)),
+            (NodeType::GuestOS, address(&NodeType::GuestOS)),
+        ];
+        assert!(assert_unique_ipv6(&nodes).is_ok());
+
+        // Same MAC and prefix generates the same address
+        let nodes = [
+            (NodeType::HostOS, address(&NodeType::HostOS)),
+            (NodeType::GuestOS, address(&NodeType::GuestOS)),
+            (NodeType::SetupOS, address(&NodeType::HostOS)),
+        ];
+        let err = assert_unique_ipv6(&nodes).unwrap_err().to_string();
+        assert!(err.contains("node 0 (HostOS)"), "{err}");
+        assert!(err.contains("node 2 (SetupOS)"), "{err}");
+    }
 }
@@ -1,7 +1,9 @@
+use std::fmt;
 use std::str::FromStr;
 
 use anyhow::{anyhow, Error, Result};
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum NodeType {
     SetupOS,
@@ -22,6 +24,43 @@ impl NodeType {
     }
 }
 
+impl NodeType {
+    /// Parse `s` as a node type, erroring if it is not one of the node types
+    /// `supported` by `tool`.
+    pub fn parse_supported(s: &str, supported: &[NodeType], tool: &str) -> Result<Self> {
+        let node_type = s.parse::<NodeType>()?;
+        if !supported.contains(&node_type) {
+            let supported = supported
+                .iter()
+                .map(|node_type| format!("{:?}", node_type))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(anyhow!(
+                "Node type {:?} is not supported by {}. Supported node types: {}",
+                node_type,
+                tool,
+                supported
+            ));
+        }
+        Ok(node_type)
+    }
+}
+
+impl fmt::Display for NodeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use NodeType::*;
+        let name = match self {
+            SetupOS => "SetupOS",
+            HostOS => "HostOS",
+            GuestOS => "GuestOS",
+            Boundary => "Boundary",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Parsing is case-insensitive, so that e.g. both `guestos` and `GuestOS` are
+/// accepted. It is the inverse of the `Display` implementation.
 impl FromStr for NodeType {
     type Err = Error;
 
@@ -36,3 +75,44 @@ impl FromStr for NodeType {
         }
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_supported() {
+        let supported = [NodeType::HostOS, NodeType::GuestOS];
+
+        assert_eq!(
+            NodeType::parse_supported("guestos", &supported, "hostos_tool").unwrap(),
+            NodeType::GuestOS
+        );
+        assert_eq!(
+            NodeType::parse_supported("HostOS", &supported, "hostos_tool").unwrap(),
+            NodeType::HostOS
+        );
+
+        let err = NodeType::parse_supported("SetupOS", &supported, "hostos_tool").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Node type SetupOS is not supported by hostos_tool. Supported node types: HostOS, GuestOS"
+        );
+
+        let err = NodeType::parse_supported("foo", &supported, "hostos_tool").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid node type: foo");
+    }
+
+    #[test]
+    fn test_display_from_str_roundtrip() {
+        use NodeType::*;
+        for node_type in [SetupOS, HostOS, GuestOS, Boundary] {
+            let name = node_type.to_string();
+            assert_eq!(name.parse::<NodeType>().unwrap(), node_type);
+            assert_eq!(name.to_lowercase().parse::<NodeType>().unwrap(), node_type);
+            assert_eq!(name.to_uppercase().parse::<NodeType>().unwrap(), node_type);
+            // `Display` and `Debug` agree, so error messages are consistent.
+            assert_eq!(name, format!("{:?}", node_type));
+        }
+    }
+}
@@ -14,6 +14,10 @@ use network::node_type::NodeType;
 use network::systemd::DEFAULT_SYSTEMD_NETWORK_DIR;
 use utils::to_cidr;
 
+/// Node types whose addresses are meaningful to generate from this tool.
+/// HostOS generates the address of this host, GuestOS that of the GuestOS VM it runs.
+const SUPPORTED_NODE_TYPES: &[NodeType] = &[NodeType::HostOS, NodeType::GuestOS];
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Generate systemd network configuration files. Bridges available NIC's for IC IPv6 connectivity.
@@ -24,10 +28,12 @@ pub enum Commands {
     },
     GenerateMacAddress {
         #[arg(short, long, default_value = "HostOS")]
+        /// One of HostOS, GuestOS
         node_type: String,
     },
     GenerateIpv6Address {
         #[arg(short, long, default_value = "HostOS")]
+        /// One of HostOS, GuestOS
         node_type: String,
     },
 }
@@ -108,7 +114,8 @@ pub fn main() -> Result<()> {
                 ))?;
             eprintln!("Deployment config: {:?}", deployment_settings);
 
-            let node_type = node_type.parse::<NodeType>()?;
+            let node_type =
+                NodeType::parse_supported(&node_type, SUPPORTED_NODE_TYPES, "hostos_tool")?;
             let mgmt_mac = match deployment_settings.deployment.mgmt_mac {
                 Some(config_mac) => {
                     let mgmt_mac = FormattedMacAddress::try_from(config_mac.as_str())?;
@@ -146,7 +153,8 @@ pub fn main() -> Result<()> {
                 ))?;
             eprintln!("Deployment config: {:?}", deployment_settings);
 
-            let node_type = node_type.parse::<NodeType>()?;
+            let node_type =
+                NodeType::parse_supported(&node_type, SUPPORTED_NODE_TYPES, "hostos_tool")?;
             let mgmt_mac = match deployment_settings.deployment.mgmt_mac {
                 Some(config_mac) => {
                     let mgmt_mac = FormattedMacAddress::try_from(config_mac.as_str())?;
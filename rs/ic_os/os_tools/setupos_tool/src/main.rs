@@ -14,6 +14,11 @@ use network::node_type::NodeType;
 use network::systemd::DEFAULT_SYSTEMD_NETWORK_DIR;
 use utils::to_cidr;
 
+/// Node types whose addresses are meaningful to generate from this tool.
+/// SetupOS generates the address used during installation, HostOS and GuestOS those of
+/// the node that is being installed.
+const SUPPORTED_NODE_TYPES: &[NodeType] = &[NodeType::SetupOS, NodeType::HostOS, NodeType::GuestOS];
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Generate systemd network configuration files. Bridges available NIC's for IC IPv6 connectivity.
@@ -24,6 +29,7 @@ pub enum Commands {
     },
     GenerateIpv6Address {
         #[arg(short, long, default_value = "SetupOS")]
+        /// One of SetupOS, HostOS, GuestOS
         node_type: String,
     },
 }
@@ -104,7 +110,8 @@ pub fn main() -> Result<()> {
                 ))?;
             eprintln!("Deployment config: {:?}", deployment_settings);
 
-            let node_type = node_type.parse::<NodeType>()?;
+            let node_type =
+                NodeType::parse_supported(&node_type, SUPPORTED_NODE_TYPES, "setupos_tool")?;
             let mgmt_mac = match deployment_settings.deployment.mgmt_mac {
                 Some(config_mac) => {
                     let mgmt_mac = FormattedMacAddress::try_from(config_mac.as_str())?;
@@ -194,6 +194,11 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Archive<Rt, Wasm> {
     pub fn nodes(&self) -> &[CanisterId] {
         &self.nodes
     }
+
+    /// Whether there are outstanding calls to the archive at the moment.
+    pub fn archiving_in_progress(&self) -> bool {
+        self.archiving_in_progress
+    }
 }
 
 /// Grabs a write lock on the archive and executes a synchronous function under the lock.
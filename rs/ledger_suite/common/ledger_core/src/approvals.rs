@@ -2,6 +2,7 @@ use crate::timestamp::TimeStamp;
 use crate::tokens::{CheckedSub, TokensType, Zero};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
 
 #[cfg(test)]
 mod tests;
@@ -68,7 +69,21 @@ pub trait AllowancesData {
     #[allow(clippy::type_complexity)]
     fn pop_first_expiry(&mut self) -> Option<(TimeStamp, (Self::AccountId, Self::AccountId))>;
 
-    fn oldest_arrivals(&self, n: usize) -> Vec<(Self::AccountId, Self::AccountId)>;
+    /// Returns up to `n` arrivals strictly after `start` (or from the
+    /// beginning of the queue if `start` is `None`), in arrival order.
+    #[allow(clippy::type_complexity)]
+    fn arrivals_after(
+        &self,
+        start: Option<(TimeStamp, (Self::AccountId, Self::AccountId))>,
+        n: usize,
+    ) -> Vec<(TimeStamp, (Self::AccountId, Self::AccountId))>;
+
+    fn oldest_arrivals(&self, n: usize) -> Vec<(Self::AccountId, Self::AccountId)> {
+        self.arrivals_after(None, n)
+            .into_iter()
+            .map(|(_t, key)| key)
+            .collect()
+    }
 
     fn len_allowances(&self) -> usize;
 
@@ -167,15 +182,20 @@ where
         self.expiration_queue.pop_first()
     }
 
-    fn oldest_arrivals(&self, n: usize) -> Vec<(Self::AccountId, Self::AccountId)> {
-        let mut result = vec![];
-        for (_t, key) in &self.arrival_queue {
-            if result.len() >= n {
-                break;
-            }
-            result.push(key.clone());
-        }
-        result
+    fn arrivals_after(
+        &self,
+        start: Option<(TimeStamp, (Self::AccountId, Self::AccountId))>,
+        n: usize,
+    ) -> Vec<(TimeStamp, (Self::AccountId, Self::AccountId))> {
+        let lower = match start {
+            Some(start) => Bound::Excluded(start),
+            None => Bound::Unbounded,
+        };
+        self.arrival_queue
+            .range((lower, Bound::Unbounded))
+            .take(n)
+            .cloned()
+            .collect()
     }
 
     fn len_allowances(&self) -> usize {
@@ -212,6 +232,11 @@ impl<Tokens: Zero> Default for Allowance<Tokens> {
 #[serde(transparent)]
 pub struct AllowanceTable<AD: AllowancesData> {
     allowances_data: AD,
+    /// The last arrival examined by `trim_orphaned_arrivals`, if the previous
+    /// call did not reach the end of the arrival queue. Not persisted.
+    #[serde(skip)]
+    #[allow(clippy::type_complexity)]
+    arrivals_trim_cursor: Option<(TimeStamp, (AD::AccountId, AD::AccountId))>,
 }
 
 impl<AD> Default for AllowanceTable<AD>
@@ -234,6 +259,7 @@ where
     pub fn new() -> Self {
         Self {
             allowances_data: Default::default(),
+            arrivals_trim_cursor: None,
         }
     }
 
@@ -379,6 +405,16 @@ where
         self.allowances_data.len_allowances()
     }
 
+    /// Returns the number of entries in the expiration queue.
+    pub fn get_num_expirations(&self) -> usize {
+        self.allowances_data.len_expirations()
+    }
+
+    /// Returns the number of entries in the arrival queue.
+    pub fn get_num_arrivals(&self) -> usize {
+        self.allowances_data.len_arrivals()
+    }
+
     /// Consumes amount from the spender's allowance for the account.
     /// Returns an error if the allowance would go negative.
     pub fn use_allowance(
@@ -462,6 +498,32 @@ where
         })
     }
 
+    /// Examines up to `max` arrival entries, continuing where the previous call
+    /// left off, and removes those that no longer correspond to an allowance
+    /// (i.e. there is no allowance for the pair, or it arrived at a different
+    /// time). Returns the number of entries removed.
+    pub fn trim_orphaned_arrivals(&mut self, max: usize) -> usize {
+        let arrivals = self
+            .allowances_data
+            .arrivals_after(self.arrivals_trim_cursor.take(), max);
+        if arrivals.len() == max {
+            // Start from the beginning of the queue once the end is reached.
+            self.arrivals_trim_cursor = arrivals.last().cloned();
+        }
+
+        let mut removed = 0;
+        for (timestamp, key) in arrivals {
+            match self.allowances_data.get_allowance(&key) {
+                Some(allowance) if allowance.arrived_at == timestamp => {}
+                _ => {
+                    self.allowances_data.remove_arrival(timestamp, key);
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+
     pub fn len(&self) -> usize {
         self.allowances_data.len_allowances()
     }
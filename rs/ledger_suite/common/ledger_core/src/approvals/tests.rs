@@ -545,6 +545,48 @@ fn arrival_table_updated_correctly() {
     }
 }
 
+#[test]
+fn trim_orphaned_arrivals() {
+    let mut table = TestAllowanceTable::default();
+
+    for i in 2..8 {
+        table
+            .approve(&Account(1), &Account(i), tokens(5), None, ts(i), None)
+            .unwrap();
+    }
+    // Remove three allowances directly, leaving their arrivals behind.
+    for i in [2, 4, 7] {
+        table
+            .allowances_data
+            .remove_allowance(&(Account(1), Account(i)));
+    }
+    // And leave a stale arrival for a pair whose allowance arrived later.
+    table
+        .allowances_data
+        .insert_arrival(ts(1), (Account(1), Account(3)));
+    assert_eq!(table.allowances_data.len_allowances(), 3);
+    assert_eq!(table.allowances_data.len_arrivals(), 7);
+
+    // Each call examines at most `max` arrivals, in arrival order.
+    assert_eq!(table.trim_orphaned_arrivals(3), 2);
+    assert_eq!(table.allowances_data.len_arrivals(), 5);
+    assert_eq!(table.trim_orphaned_arrivals(3), 1);
+    assert_eq!(table.allowances_data.len_arrivals(), 4);
+    assert_eq!(table.trim_orphaned_arrivals(3), 1);
+    assert_eq!(table.allowances_data.len_arrivals(), 3);
+    table.check_postconditions();
+    let remaining: Vec<u64> = table
+        .select_approvals_to_trim(10)
+        .iter()
+        .map(|(_account, spender)| spender.0)
+        .collect();
+    assert_eq!(remaining, vec![3, 5, 6]);
+
+    // Once the index is in sync, trimming is a no-op.
+    assert_eq!(table.trim_orphaned_arrivals(10), 0);
+    assert_eq!(table.allowances_data.len_arrivals(), 3);
+}
+
 #[test]
 fn expected_allowance_not_checked_against_expired() {
     let mut table = TestAllowanceTable::default();
@@ -619,3 +661,39 @@ fn expected_allowance_if_zero_no_approval() {
         }
     );
 }
+
+#[test]
+fn arrivals_after_paginates() {
+    let mut table = TestAllowanceTable::default();
+
+    for i in 1..=7 {
+        table
+            .approve(&Account(i), &Account(0), tokens(i), None, ts(i), None)
+            .unwrap();
+    }
+
+    let mut seen = vec![];
+    let mut start = None;
+    loop {
+        let page = table.allowances_data.arrivals_after(start.clone(), 3);
+        if page.is_empty() {
+            break;
+        }
+        assert!(page.len() <= 3);
+        seen.extend(page.iter().map(|(t, (account, _))| (*t, account.0)));
+        start = page.last().cloned();
+    }
+
+    let expected: Vec<_> = (1..=7).map(|i| (ts(i), i)).collect();
+    assert_eq!(seen, expected);
+
+    assert_eq!(
+        table
+            .allowances_data
+            .oldest_arrivals(2)
+            .into_iter()
+            .map(|(account, _)| account.0)
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+}
@@ -5,6 +5,7 @@ use ic_ledger_canister_core::blockchain::Blockchain;
 use ic_ledger_canister_core::ledger::{
     self as core_ledger, LedgerContext, LedgerData, TransactionInfo,
 };
+use ic_ledger_canister_core::range_utils;
 use ic_ledger_core::{
     approvals::AllowanceTable, approvals::HeapAllowancesData, balances::Balances,
     block::EncodedBlock, timestamp::TimeStamp,
@@ -15,7 +16,8 @@ use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemor
 use ic_stable_structures::DefaultMemoryImpl;
 use icp_ledger::{
     AccountIdentifier, Block, FeatureFlags, LedgerAllowances, LedgerBalances, Memo, Operation,
-    PaymentError, Transaction, TransferError, TransferFee, UpgradeArgs, DEFAULT_TRANSFER_FEE,
+    PaymentError, Transaction, TransactionWindowUsage, TransferError, TransferFee, UpgradeArgs,
+    DEFAULT_TRANSFER_FEE,
 };
 use icrc_ledger_types::icrc1::account::Account;
 use intmap::IntMap;
@@ -76,6 +78,53 @@ thread_local! {
         RefCell::new(memory_manager.borrow().get(UPGRADES_MEMORY_ID)));
 }
 
+/// A snapshot of the ledger's block archiving state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArchiveStatus {
+    /// The number of blocks stored in the ledger itself.
+    pub local_blocks: u64,
+    /// The number of blocks that have been sent to the archive.
+    pub archived_blocks: u64,
+    /// The number of local blocks which, when reached, triggers archiving.
+    /// `None` if the ledger has no archive configured.
+    pub trigger_threshold: Option<usize>,
+    /// Whether there are outstanding calls to the archive at the moment.
+    pub archiving_in_progress: bool,
+}
+
+impl ArchiveStatus {
+    /// Returns true if enough blocks accumulated locally to trigger archiving.
+    pub fn archiving_pending(&self) -> bool {
+        self.trigger_threshold
+            .is_some_and(|threshold| self.local_blocks >= threshold as u64)
+    }
+}
+
+/// A snapshot of the sizes and counters of the ledger state exported as metrics.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LedgerMetrics {
+    /// The number of accounts in the balance store.
+    pub balance_store_entries: usize,
+    /// The total number of tokens in the pool.
+    pub token_pool: Tokens,
+    /// The number of allowances.
+    pub num_approvals: usize,
+    /// The number of entries in the allowance expiration queue.
+    pub num_allowance_expirations: usize,
+    /// The number of entries in the allowance arrival queue.
+    pub num_allowance_arrivals: usize,
+    /// The number of entries in the `transactions_by_hash` cache.
+    pub transactions_by_hash_entries: usize,
+    /// The number of entries in the `transactions_by_height` queue.
+    pub transactions_by_height_entries: usize,
+    pub transaction_window_usage: TransactionWindowUsage,
+    pub archive_status: ArchiveStatus,
+    /// The number of archive canisters.
+    pub num_archives: usize,
+    /// The timestamp of the most recent block.
+    pub most_recent_block_time: TimeStamp,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Ledger {
     pub balances: LedgerBalances,
@@ -111,6 +160,10 @@ pub struct Ledger {
     /// The fee to pay to perform a transfer.
     #[serde(default = "default_transfer_fee")]
     pub transfer_fee: Tokens,
+    /// The fee to pay to perform an approve. Falls back to `transfer_fee` if
+    /// not set.
+    #[serde(default)]
+    pub approve_fee: Option<Tokens>,
 
     /// Token symbol
     #[serde(default = "unknown_token")]
@@ -121,6 +174,10 @@ pub struct Ledger {
 
     #[serde(default)]
     pub feature_flags: FeatureFlags,
+
+    /// Principals that are not allowed to send tokens, in addition to the anonymous principal.
+    #[serde(default)]
+    blocked_senders: HashSet<PrincipalId>,
 }
 
 impl LedgerContext for Ledger {
@@ -236,9 +293,11 @@ impl Default for Ledger {
             send_whitelist: HashSet::new(),
             max_transactions_in_window: Self::DEFAULT_MAX_TRANSACTIONS_IN_WINDOW,
             transfer_fee: DEFAULT_TRANSFER_FEE,
+            approve_fee: None,
             token_symbol: unknown_token(),
             token_name: unknown_token(),
             feature_flags: FeatureFlags::default(),
+            blocked_senders: HashSet::new(),
         }
     }
 }
@@ -275,7 +334,7 @@ impl Ledger {
             Operation::Transfer { .. } => self.transfer_fee,
             Operation::Mint { .. } => Tokens::from_e8s(0),
             Operation::Burn { .. } => Tokens::from_e8s(0),
-            Operation::Approve { .. } => self.transfer_fee,
+            Operation::Approve { .. } => self.approve_fee(),
         };
         core_ledger::apply_transaction(
             self,
@@ -322,7 +381,16 @@ impl Ledger {
 
     /// This adds a pre created block to the ledger. This should only be used
     /// during canister migration or upgrade.
+    ///
+    /// Blocks whose parent hash does not match the hash of the current chain tip
+    /// are rejected before their operation is applied.
     pub fn add_block(&mut self, block: Block) -> Result<BlockIndex, String> {
+        if block.parent_hash != self.blockchain.last_hash {
+            return Err(format!(
+                "cannot add block {:?}: its parent hash does not match the chain tip hash {:?}",
+                block, self.blockchain.last_hash
+            ));
+        }
         icp_ledger::apply_operation(self, &block.transaction.operation, block.timestamp)
             .map_err(|e| format!("failed to execute transfer {:?}: {:?}", block, e))?;
         self.blockchain.add_block(block)
@@ -343,6 +411,7 @@ impl Ledger {
         feature_flags: Option<FeatureFlags>,
         maximum_number_of_accounts: Option<usize>,
         accounts_overflow_trim_quantity: Option<usize>,
+        approve_fee: Option<Tokens>,
     ) {
         self.token_symbol = token_symbol.unwrap_or_else(|| "ICP".to_string());
         self.token_name = token_name.unwrap_or_else(|| "Internet Computer".to_string());
@@ -367,6 +436,7 @@ impl Ledger {
         if let Some(transfer_fee) = transfer_fee {
             self.transfer_fee = transfer_fee;
         }
+        self.approve_fee = approve_fee;
         if let Some(feature_flags) = feature_flags {
             self.feature_flags = feature_flags;
         }
@@ -422,8 +492,55 @@ impl Ledger {
             .get_blocks_for_archiving(trigger_threshold, num_blocks)
     }
 
+    /// Returns the locally held blocks in `[start, start + length)`. Archived
+    /// blocks are never included, so a range that lies entirely below the
+    /// local window is empty and has to be fetched from the archive instead.
+    pub fn get_blocks_range(&self, start: BlockIndex, length: usize) -> Vec<EncodedBlock> {
+        let requested = range_utils::make_range(start, length);
+        match range_utils::intersect(&requested, &self.blockchain.local_block_range()) {
+            Ok(local_blocks) => self.blockchain.block_slice(local_blocks).to_vec(),
+            Err(_) => vec![],
+        }
+    }
+
+    pub fn archive_status(&self) -> ArchiveStatus {
+        let archive_guard = self.blockchain.archive.read().unwrap();
+        let archive = archive_guard.as_ref();
+        ArchiveStatus {
+            local_blocks: self.blockchain.num_unarchived_blocks(),
+            archived_blocks: self.blockchain.num_archived_blocks(),
+            trigger_threshold: archive.map(|archive| archive.trigger_threshold),
+            archiving_in_progress: archive.is_some_and(|archive| archive.archiving_in_progress()),
+        }
+    }
+
+    /// Returns all the sizes and counters of the ledger state exported as
+    /// metrics, so that the metrics endpoint reads them in one place.
+    pub fn snapshot_metrics(&self) -> LedgerMetrics {
+        let num_archives = self
+            .blockchain
+            .archive
+            .read()
+            .unwrap()
+            .as_ref()
+            .map_or(0, |archive| archive.nodes().len());
+        LedgerMetrics {
+            balance_store_entries: self.balances.store.len(),
+            token_pool: self.balances.token_pool,
+            num_approvals: self.approvals.get_num_approvals(),
+            num_allowance_expirations: self.approvals.get_num_expirations(),
+            num_allowance_arrivals: self.approvals.get_num_arrivals(),
+            transactions_by_hash_entries: self.transactions_by_hash_len(),
+            transactions_by_height_entries: self.transactions_by_height_len(),
+            transaction_window_usage: self.transaction_window_usage(),
+            archive_status: self.archive_status(),
+            num_archives,
+            most_recent_block_time: self.blockchain.last_timestamp,
+        }
+    }
+
     pub fn can_send(&self, principal_id: &PrincipalId) -> bool {
-        !principal_id.is_anonymous()
+        !principal_id.is_anonymous() && !self.blocked_senders.contains(principal_id)
     }
 
     /// Check if it's allowed to notify this canister.
@@ -440,12 +557,78 @@ impl Ledger {
         self.transactions_by_height.len()
     }
 
+    /// Returns the transactions within the deduplication window, in block height
+    /// order. Transactions purged from the window are not included.
+    pub fn recent_transactions(&self) -> impl Iterator<Item = &TransactionInfo<Transaction>> {
+        self.transactions_by_height.iter()
+    }
+
+    pub fn transaction_window_usage(&self) -> TransactionWindowUsage {
+        TransactionWindowUsage {
+            max_transactions_in_window: self.max_transactions_in_window as u64,
+            transactions_in_window: self.transactions_by_height.len() as u64,
+        }
+    }
+
+    /// Returns the minting account as an ICRC-1 `Account`.
+    ///
+    /// This is `icrc1_minting_account` if set. An `AccountIdentifier` is a hash
+    /// of the owner and subaccount and cannot be turned back into an `Account`,
+    /// so `minting_account_id` alone yields `None`.
+    pub fn effective_icrc1_minting_account(&self) -> Option<Account> {
+        self.icrc1_minting_account
+    }
+
+    /// Removes up to `max` expired allowances, in expiration order, and returns
+    /// the number of allowances removed.
+    pub fn prune_expired_allowances(&mut self, now: TimeStamp, max: usize) -> usize {
+        self.approvals.prune(now, max)
+    }
+
+    /// Examines up to `max` entries of the allowance arrival index and removes
+    /// those whose allowance no longer exists. Returns the number removed.
+    pub fn trim_orphaned_allowance_arrivals(&mut self, max: usize) -> usize {
+        self.approvals.trim_orphaned_arrivals(max)
+    }
+
+    /// Returns the expiration of the `spender`'s allowance on `owner`'s account,
+    /// or `None` if the allowance never expires or there is no live allowance.
+    pub fn allowance_expiry(
+        &self,
+        owner: &AccountIdentifier,
+        spender: &AccountIdentifier,
+        now: TimeStamp,
+    ) -> Option<TimeStamp> {
+        self.approvals.allowance(owner, spender, now).expires_at
+    }
+
     pub fn transfer_fee(&self) -> TransferFee {
         TransferFee {
             transfer_fee: self.transfer_fee,
         }
     }
 
+    /// Returns the fee to pay to perform an approve.
+    pub fn approve_fee(&self) -> Tokens {
+        self.approve_fee.unwrap_or(self.transfer_fee)
+    }
+
+    /// Sets the feature flags, refusing combinations that would leave the ledger
+    /// in an inconsistent state.
+    ///
+    /// ICRC-2 cannot be disabled while there are outstanding allowances, as their
+    /// owners would no longer be able to revoke them.
+    pub fn set_feature_flags(&mut self, feature_flags: FeatureFlags) -> Result<(), String> {
+        if !feature_flags.icrc2 && self.approvals.get_num_approvals() > 0 {
+            return Err(format!(
+                "Cannot disable ICRC-2 while there are {} outstanding approvals",
+                self.approvals.get_num_approvals()
+            ));
+        }
+        self.feature_flags = feature_flags;
+        Ok(())
+    }
+
     pub fn upgrade(&mut self, args: UpgradeArgs) {
         if let Some(icrc1_minting_account) = args.icrc1_minting_account {
             if Some(AccountIdentifier::from(icrc1_minting_account)) != self.minting_account_id {
@@ -456,7 +639,15 @@ impl Ledger {
             self.icrc1_minting_account = Some(icrc1_minting_account);
         }
         if let Some(feature_flags) = args.feature_flags {
-            self.feature_flags = feature_flags;
+            if let Err(err) = self.set_feature_flags(feature_flags) {
+                trap_with(&err);
+            }
+        }
+        if let Some(blocked_senders) = args.blocked_senders {
+            self.blocked_senders = blocked_senders.into_iter().collect();
+        }
+        if let Some(approve_fee) = args.approve_fee {
+            self.approve_fee = Some(approve_fee);
         }
     }
 }
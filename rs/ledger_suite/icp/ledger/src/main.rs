@@ -8,7 +8,7 @@ use dfn_core::{
     over_init, printer, setup,
 };
 use dfn_protobuf::protobuf;
-use ic_base_types::CanisterId;
+use ic_base_types::{CanisterId, PrincipalId};
 use ic_canister_log::{LogEntry, Sink};
 use ic_icrc1::endpoints::{convert_transfer_error, StandardRecord};
 use ic_ledger_canister_core::runtime::total_memory_size_bytes;
@@ -34,8 +34,8 @@ use icp_ledger::{
     BinaryAccountBalanceArgs, Block, BlockArg, BlockRes, CandidBlock, Decimals, FeatureFlags,
     GetBlocksArgs, InitArgs, IterBlocksArgs, LedgerCanisterPayload, Memo, Name, Operation,
     PaymentError, QueryBlocksResponse, QueryEncodedBlocksResponse, SendArgs, Subaccount, Symbol,
-    TipOfChainRes, TotalSupplyArgs, Transaction, TransferArgs, TransferError, TransferFee,
-    TransferFeeArgs, MEMO_SIZE_BYTES,
+    TipOfChainRes, TotalSupplyArgs, Transaction, TransactionWindowUsage, TransferArgs,
+    TransferError, TransferFee, TransferFeeArgs, MEMO_SIZE_BYTES,
 };
 use icrc_ledger_types::icrc1::transfer::TransferError as Icrc1TransferError;
 use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
@@ -93,6 +93,8 @@ impl Sink for DebugOutSink {
 /// * `token_symbol` - Token symbol.
 /// * `token_name` - Token name.
 /// * `feature_flags` - Features that are enabled on the ledger.
+/// * `approve_fee` - The fee to pay to perform an approve, if different from
+///   `transfer_fee`.
 #[allow(clippy::too_many_arguments)]
 fn init(
     minting_account: AccountIdentifier,
@@ -108,6 +110,7 @@ fn init(
     feature_flags: Option<FeatureFlags>,
     maximum_number_of_accounts: Option<usize>,
     accounts_overflow_trim_quantity: Option<usize>,
+    approve_fee: Option<Tokens>,
 ) {
     print(format!(
         "[ledger] init(): minting account is {}",
@@ -126,6 +129,7 @@ fn init(
         feature_flags,
         maximum_number_of_accounts,
         accounts_overflow_trim_quantity,
+        approve_fee,
     );
     match max_message_size_bytes {
         None => {
@@ -616,7 +620,7 @@ fn icrc1_supported_standards() -> Vec<StandardRecord> {
 
 #[candid_method(query, rename = "icrc1_minting_account")]
 fn icrc1_minting_account() -> Option<Account> {
-    LEDGER.read().unwrap().icrc1_minting_account
+    LEDGER.read().unwrap().effective_icrc1_minting_account()
 }
 
 #[candid_method(query, rename = "transfer_fee")]
@@ -624,6 +628,11 @@ fn transfer_fee(_: TransferFeeArgs) -> TransferFee {
     LEDGER.read().unwrap().transfer_fee()
 }
 
+#[candid_method(query, rename = "transaction_window_usage")]
+fn transaction_window_usage() -> TransactionWindowUsage {
+    LEDGER.read().unwrap().transaction_window_usage()
+}
+
 #[candid_method(query, rename = "icrc1_metadata")]
 fn icrc1_metadata() -> Vec<(String, Value)> {
     vec![
@@ -706,6 +715,7 @@ fn canister_init(arg: LedgerCanisterPayload) {
             arg.feature_flags,
             arg.maximum_number_of_accounts,
             arg.accounts_overflow_trim_quantity,
+            arg.approve_fee,
         ),
         LedgerCanisterPayload::Upgrade(_) => {
             trap_with("Cannot initialize the canister with an Upgrade argument. Please provide an Init argument.");
@@ -739,6 +749,7 @@ fn main() {
                         arg.feature_flags,
                         arg.maximum_number_of_accounts,
                         arg.accounts_overflow_trim_quantity,
+                        arg.approve_fee,
                     ),
                     Err(old_err) =>
                     trap_with(&format!("Unable to decode init argument.\nDecode as new init returned the error {}\nDecode as old init returned the error {}", new_err, old_err))
@@ -751,6 +762,12 @@ fn main() {
 // We use 8MiB buffer
 const BUFFER_SIZE: usize = 8388608;
 
+/// The maximum number of expired allowances pruned per heartbeat.
+const MAX_ALLOWANCES_TO_PRUNE_PER_HEARTBEAT: usize = 100;
+
+/// The maximum number of allowance arrival entries examined per heartbeat.
+const MAX_ALLOWANCE_ARRIVALS_TO_TRIM_PER_HEARTBEAT: usize = 100;
+
 fn post_upgrade(args: Option<LedgerCanisterPayload>) {
     let start = dfn_core::api::performance_counter(0);
 
@@ -881,6 +898,19 @@ fn pre_upgrade() {
     });
 }
 
+/// Prunes a bounded number of expired allowances, so that they do not pile up
+/// in between approvals (which only prune a few at a time), and trims a bounded
+/// number of orphaned entries from the allowance arrival index.
+#[export_name = "canister_heartbeat"]
+fn canister_heartbeat() {
+    // Skip this round if the ledger is locked, e.g. by an ongoing archiving.
+    if let Ok(mut ledger) = LEDGER.try_write() {
+        let now = TimeStamp::from(dfn_core::api::now());
+        ledger.prune_expired_allowances(now, MAX_ALLOWANCES_TO_PRUNE_PER_HEARTBEAT);
+        ledger.trim_orphaned_allowance_arrivals(MAX_ALLOWANCE_ARRIVALS_TO_TRIM_PER_HEARTBEAT);
+    }
+}
+
 struct Access;
 
 impl LedgerAccess for Access {
@@ -1016,11 +1046,27 @@ fn transfer() {
     over_async_may_reject(candid_one, |arg| async { Ok(transfer_candid(arg).await) })
 }
 
+/// Returns the reject message for a caller that is not allowed to send.
+fn sender_reject_message(caller: &PrincipalId, anonymous_message: &str) -> String {
+    if caller.is_anonymous() {
+        anonymous_message.to_string()
+    } else {
+        format!(
+            "Principal {} is blocked from sending on the ledger.",
+            caller
+        )
+    }
+}
+
 #[export_name = "canister_update icrc1_transfer"]
 fn icrc1_transfer_candid() {
     over_async_may_reject(candid_one, |arg: TransferArg| async {
-        if !LEDGER.read().unwrap().can_send(&caller()) {
-            return Err("Anonymous principal cannot hold tokens on the ledger.".to_string());
+        let caller = caller();
+        if !LEDGER.read().unwrap().can_send(&caller) {
+            return Err(sender_reject_message(
+                &caller,
+                "Anonymous principal cannot hold tokens on the ledger.",
+            ));
         }
 
         Ok(icrc1_transfer(arg).await)
@@ -1061,8 +1107,12 @@ async fn icrc2_transfer_from(arg: TransferFromArgs) -> Result<Nat, TransferFromE
 #[export_name = "canister_update icrc2_transfer_from"]
 fn icrc2_transfer_from_candid() {
     over_async_may_reject(candid_one, |arg: TransferFromArgs| async {
-        if !LEDGER.read().unwrap().can_send(&caller()) {
-            return Err("Anonymous principal cannot transfer tokens on the ledger.".to_string());
+        let caller = caller();
+        if !LEDGER.read().unwrap().can_send(&caller) {
+            return Err(sender_reject_message(
+                &caller,
+                "Anonymous principal cannot transfer tokens on the ledger.",
+            ));
         }
 
         Ok(icrc2_transfer_from(arg).await)
@@ -1186,6 +1236,11 @@ fn transfer_fee_candid() {
     over(candid_one, transfer_fee)
 }
 
+#[export_name = "canister_query transaction_window_usage"]
+fn transaction_window_usage_candid() {
+    over(candid_one, |()| transaction_window_usage())
+}
+
 #[export_name = "canister_query icrc1_fee"]
 fn icrc1_fee_candid() {
     over(candid_one, |()| icrc1_fee())
@@ -1364,11 +1419,7 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
             format!("Failed to get a LEDGER for read: {}", err),
         )
     })?;
-    let archive_guard = ledger.blockchain.archive.read().unwrap();
-    let num_archives = archive_guard
-        .as_ref()
-        .iter()
-        .fold(0, |sum, archive| sum + archive.nodes().iter().len());
+    let metrics = ledger.snapshot_metrics();
     w.encode_gauge(
         "ledger_max_message_size_bytes",
         *MAX_MESSAGE_SIZE_BYTES.read().unwrap() as f64,
@@ -1391,46 +1442,46 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
     )?;
     w.encode_gauge(
         "ledger_transactions_by_hash_cache_entries",
-        ledger.transactions_by_hash_len() as f64,
+        metrics.transactions_by_hash_entries as f64,
         "Total number of entries in the transactions_by_hash cache.",
     )?;
     w.encode_gauge(
         "ledger_transactions_by_height_entries",
-        ledger.transactions_by_height_len() as f64,
+        metrics.transactions_by_height_entries as f64,
         "Total number of entries in the transaction_by_height queue.",
     )?;
     w.encode_gauge(
         "ledger_blocks",
-        ledger.blockchain.blocks.len() as f64,
+        metrics.archive_status.local_blocks as f64,
         "Total number of blocks stored in the main memory.",
     )?;
     // This value can go down -- the number is increased before archiving, and if
     // archiving fails it is decremented.
     w.encode_gauge(
         "ledger_archived_blocks",
-        ledger.blockchain.num_archived_blocks as f64,
+        metrics.archive_status.archived_blocks as f64,
         "Total number of blocks sent to the archive.",
     )?;
     // The sum of the two gauges above. It is necessary to have this metric explicitly exported in
     // order to be able to accurately calculate the total block rate.
     w.encode_gauge(
         "ledger_total_blocks",
-        ledger.blockchain.num_archived_blocks.saturating_add(ledger.blockchain.blocks.len() as u64) as f64,
+        metrics.archive_status.archived_blocks.saturating_add(metrics.archive_status.local_blocks) as f64,
         "Total number of blocks stored in the main memory, plus total number of blocks sent to the archive.",
     )?;
     w.encode_gauge(
         "ledger_balances_token_pool",
-        ledger.balances.token_pool.get_tokens() as f64,
+        metrics.token_pool.get_tokens() as f64,
         "Total number of Tokens in the pool.",
     )?;
     w.encode_gauge(
         "ledger_balance_store_entries",
-        ledger.balances.store.len() as f64,
+        metrics.balance_store_entries as f64,
         "Total number of accounts in the balance store.",
     )?;
     w.encode_gauge(
         "ledger_most_recent_block_time_seconds",
-        ledger.blockchain.last_timestamp.as_nanos_since_unix_epoch() as f64 / 1_000_000_000.0,
+        metrics.most_recent_block_time.as_nanos_since_unix_epoch() as f64 / 1_000_000_000.0,
         "IC timestamp of the most recent block.",
     )?;
     w.encode_gauge(
@@ -1440,14 +1491,29 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
     )?;
     w.encode_counter(
         "ledger_num_archives",
-        num_archives as f64,
+        metrics.num_archives as f64,
         "Total number of archives.",
     )?;
     w.encode_gauge(
         "ledger_num_approvals",
-        ledger.approvals.get_num_approvals() as f64,
+        metrics.num_approvals as f64,
         "Total number of approvals.",
     )?;
+    w.encode_gauge(
+        "ledger_num_allowance_expirations",
+        metrics.num_allowance_expirations as f64,
+        "Total number of entries in the allowance expiration queue.",
+    )?;
+    w.encode_gauge(
+        "ledger_num_allowance_arrivals",
+        metrics.num_allowance_arrivals as f64,
+        "Total number of entries in the allowance arrival queue.",
+    )?;
+    w.encode_gauge(
+        "ledger_max_transactions_in_window",
+        metrics.transaction_window_usage.max_transactions_in_window as f64,
+        "Maximum number of transactions accepted within the transaction window.",
+    )?;
     let pre_upgrade_instructions = PRE_UPGRADE_INSTRUCTIONS_CONSUMED.with(|n| *n.borrow());
     let post_upgrade_instructions = POST_UPGRADE_INSTRUCTIONS_CONSUMED.with(|n| *n.borrow());
     w.encode_gauge(
@@ -1563,7 +1629,7 @@ async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
         None => None,
     };
 
-    let expected_fee = LEDGER.read().unwrap().transfer_fee;
+    let expected_fee = LEDGER.read().unwrap().approve_fee();
     if arg.fee.is_some() && arg.fee.as_ref() != Some(&Nat::from(expected_fee.get_e8s())) {
         return Err(ApproveError::BadFee {
             expected_fee: Nat::from(expected_fee.get_e8s()),
@@ -1610,10 +1676,12 @@ async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
 #[export_name = "canister_update icrc2_approve"]
 fn icrc2_approve_candid() {
     over_async_may_reject(candid_one, |arg: ApproveArgs| async {
-        if !LEDGER.read().unwrap().can_send(&caller()) {
-            return Err(
-                "Anonymous principal cannot approve token transfers on the ledger.".to_string(),
-            );
+        let caller = caller();
+        if !LEDGER.read().unwrap().can_send(&caller) {
+            return Err(sender_reject_message(
+                &caller,
+                "Anonymous principal cannot approve token transfers on the ledger.",
+            ));
         }
 
         Ok(icrc2_approve(arg).await)
@@ -1641,6 +1709,26 @@ fn icrc2_allowance_candid() {
     over(candid_one, icrc2_allowance)
 }
 
+#[candid_method(query, rename = "allowance_expiry")]
+fn allowance_expiry(arg: AllowanceArgs) -> Option<u64> {
+    if !LEDGER.read().unwrap().feature_flags.icrc2 {
+        trap_with("ICRC-2 features are not enabled on the ledger.");
+    }
+    let now = TimeStamp::from_nanos_since_unix_epoch(time_nanos());
+    let account = AccountIdentifier::from(arg.account);
+    let spender = AccountIdentifier::from(arg.spender);
+    LEDGER
+        .read()
+        .unwrap()
+        .allowance_expiry(&account, &spender, now)
+        .map(|t| t.as_nanos_since_unix_epoch())
+}
+
+#[export_name = "canister_query allowance_expiry"]
+fn allowance_expiry_candid() {
+    over(candid_one, allowance_expiry)
+}
+
 #[candid_method(update, rename = "icrc21_canister_call_consent_message")]
 fn icrc21_canister_call_consent_message(
     consent_msg_request: ConsentMessageRequest,
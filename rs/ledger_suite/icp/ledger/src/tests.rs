@@ -1,4 +1,4 @@
-use crate::{AccountIdentifier, Ledger};
+use crate::{AccountIdentifier, ArchiveStatus, Ledger, LedgerMetrics};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_ledger_canister_core::{
     archive::Archive,
@@ -12,9 +12,11 @@ use ic_ledger_core::{
     tokens::{CheckedAdd, CheckedSub, Tokens},
 };
 use icp_ledger::{
-    apply_operation, ArchiveOptions, Block, LedgerBalances, Memo, Operation, PaymentError,
-    Transaction, TransferError, DEFAULT_TRANSFER_FEE,
+    apply_operation, ArchiveOptions, Block, FeatureFlags, LedgerBalances, Memo, Operation,
+    PaymentError, Transaction, TransactionWindowUsage, TransferError, UpgradeArgs,
+    DEFAULT_TRANSFER_FEE,
 };
+use icrc_ledger_types::icrc1::account::Account;
 use std::collections::HashSet;
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, SystemTime};
@@ -263,6 +265,7 @@ fn serialize() {
         None,
         None,
         None,
+        None,
     );
 
     let txn = Transaction::new(
@@ -603,6 +606,7 @@ fn get_blocks_returns_correct_blocks() {
         None,
         None,
         None,
+        None,
     );
 
     for i in 0..10 {
@@ -668,6 +672,7 @@ fn test_purge() {
         None,
         None,
         None,
+        None,
     );
     let little_later = genesis + Duration::from_millis(1);
 
@@ -746,6 +751,42 @@ fn test_throttle_tx_per_second_nok() {
     apply_at(&mut ledger, &op, now + millis(1003));
 }
 
+#[test]
+fn test_transaction_window_usage() {
+    let millis = Duration::from_millis;
+
+    let mut ledger = Ledger {
+        transaction_window: millis(2000),
+        max_transactions_in_window: 3,
+        ..Ledger::default()
+    };
+
+    assert_eq!(
+        ledger.transaction_window_usage(),
+        TransactionWindowUsage {
+            max_transactions_in_window: 3,
+            transactions_in_window: 0,
+        }
+    );
+
+    let op = Operation::Mint {
+        to: PrincipalId::new_user_test_id(1).into(),
+        amount: Tokens::from_e8s(1000),
+    };
+
+    let now: TimeStamp = dfn_core::api::now().into();
+    apply_at(&mut ledger, &op, now + millis(1));
+    apply_at(&mut ledger, &op, now + millis(2));
+
+    assert_eq!(
+        ledger.transaction_window_usage(),
+        TransactionWindowUsage {
+            max_transactions_in_window: 3,
+            transactions_in_window: 2,
+        }
+    );
+}
+
 #[test]
 fn test_throttle_tx_per_second_ok() {
     let millis = Duration::from_millis;
@@ -1371,3 +1412,491 @@ fn test_approval_burn_from() {
     assert_eq!(ctx.balances().account_balance(&spender), Tokens::ZERO);
     assert_eq!(ctx.balances().total_supply().get_e8s(), 90_000);
 }
+
+#[test]
+fn test_blocked_senders() {
+    let mut ledger = Ledger::default();
+    let blocked = PrincipalId::new_user_test_id(1);
+    let allowed = PrincipalId::new_user_test_id(2);
+
+    assert!(ledger.can_send(&blocked));
+    assert!(ledger.can_send(&allowed));
+    assert!(!ledger.can_send(&PrincipalId::new_anonymous()));
+
+    ledger.upgrade(UpgradeArgs {
+        icrc1_minting_account: None,
+        feature_flags: None,
+        blocked_senders: Some(vec![blocked]),
+        approve_fee: None,
+    });
+    assert!(!ledger.can_send(&blocked));
+    assert!(ledger.can_send(&allowed));
+    assert!(!ledger.can_send(&PrincipalId::new_anonymous()));
+
+    // Upgrading without the field keeps the current set
+    ledger.upgrade(UpgradeArgs {
+        icrc1_minting_account: None,
+        feature_flags: None,
+        blocked_senders: None,
+        approve_fee: None,
+    });
+    assert!(!ledger.can_send(&blocked));
+
+    // An empty list unblocks everyone
+    ledger.upgrade(UpgradeArgs {
+        icrc1_minting_account: None,
+        feature_flags: None,
+        blocked_senders: Some(vec![]),
+        approve_fee: None,
+    });
+    assert!(ledger.can_send(&blocked));
+}
+
+#[test]
+fn test_archive_status() {
+    let mut state = Ledger::default();
+
+    assert_eq!(
+        state.archive_status(),
+        ArchiveStatus {
+            local_blocks: 0,
+            archived_blocks: 0,
+            trigger_threshold: None,
+            archiving_in_progress: false,
+        }
+    );
+
+    state.blockchain.archive = Arc::new(RwLock::new(Some(Archive::new(ArchiveOptions {
+        trigger_threshold: 5,
+        num_blocks_to_archive: 3,
+        node_max_memory_size_bytes: None,
+        max_message_size_bytes: None,
+        controller_id: CanisterId::from_u64(876).into(),
+        more_controller_ids: None,
+        cycles_for_archive_creation: Some(0),
+        max_transactions_per_response: None,
+    }))));
+
+    let mint = Operation::Mint {
+        to: test_account_id(1),
+        amount: Tokens::from_e8s(1000),
+    };
+
+    for i in 0..4 {
+        state.add_payment(Memo(i), mint.clone(), None).unwrap();
+    }
+    let status = state.archive_status();
+    assert_eq!(status.local_blocks, 4);
+    assert_eq!(status.trigger_threshold, Some(5));
+    assert!(!status.archiving_pending());
+
+    state.add_payment(Memo(4), mint.clone(), None).unwrap();
+    let status = state.archive_status();
+    assert_eq!(status.local_blocks, 5);
+    assert!(status.archiving_pending());
+    assert_eq!(state.get_blocks_for_archiving(5, 3).len(), 3);
+
+    state.remove_archived_blocks(3);
+    assert_eq!(
+        state.archive_status(),
+        ArchiveStatus {
+            local_blocks: 2,
+            archived_blocks: 3,
+            trigger_threshold: Some(5),
+            archiving_in_progress: false,
+        }
+    );
+    assert!(!state.archive_status().archiving_pending());
+}
+
+#[test]
+fn test_set_feature_flags() {
+    let mut ctx = Ledger::default();
+
+    let from = test_account_id(1);
+    let spender = test_account_id(2);
+    let now = ts(12345678);
+
+    // ICRC-2 can be toggled freely while there are no approvals.
+    ctx.set_feature_flags(FeatureFlags { icrc2: false })
+        .unwrap();
+    assert!(!ctx.feature_flags.icrc2);
+    ctx.set_feature_flags(FeatureFlags { icrc2: true }).unwrap();
+    assert!(ctx.feature_flags.icrc2);
+
+    ctx.balances_mut().mint(&from, tokens(100_000)).unwrap();
+    apply_operation(
+        &mut ctx,
+        &Operation::Approve {
+            from,
+            spender,
+            allowance: tokens(50_000),
+            expected_allowance: None,
+            expires_at: None,
+            fee: tokens(10_000),
+        },
+        now,
+    )
+    .unwrap();
+
+    // But it cannot be disabled while there are outstanding approvals.
+    assert!(ctx
+        .set_feature_flags(FeatureFlags { icrc2: false })
+        .is_err());
+    assert!(ctx.feature_flags.icrc2);
+    ctx.set_feature_flags(FeatureFlags { icrc2: true }).unwrap();
+}
+
+#[test]
+fn test_allowance_expiry() {
+    let mut ctx = Ledger::default();
+
+    let from = test_account_id(1);
+    let spender = test_account_id(2);
+    let other_spender = test_account_id(3);
+    let now = ts(12345678);
+    let expires_at = ts(12345678 + 1_000_000_000);
+
+    ctx.balances_mut().mint(&from, tokens(100_000)).unwrap();
+
+    // No allowance at all.
+    assert_eq!(ctx.allowance_expiry(&from, &spender, now), None);
+
+    let approve = |spender, expires_at| Operation::Approve {
+        from,
+        spender,
+        allowance: tokens(10_000),
+        expected_allowance: None,
+        expires_at,
+        fee: tokens(10_000),
+    };
+    apply_operation(&mut ctx, &approve(spender, Some(expires_at)), now).unwrap();
+    apply_operation(&mut ctx, &approve(other_spender, None), now).unwrap();
+
+    assert_eq!(ctx.allowance_expiry(&from, &spender, now), Some(expires_at));
+    // An allowance without expiration.
+    assert_eq!(ctx.allowance_expiry(&from, &other_spender, now), None);
+    // Once expired, the allowance no longer exists.
+    assert_eq!(ctx.allowance_expiry(&from, &spender, expires_at), None);
+}
+
+#[test]
+fn test_get_blocks_range() {
+    let mut state = Ledger::default();
+
+    let mint = Operation::Mint {
+        to: test_account_id(1),
+        amount: Tokens::from_e8s(1000),
+    };
+    for i in 0..5 {
+        state.add_payment(Memo(i), mint.clone(), None).unwrap();
+    }
+    let blocks = state.blockchain.blocks.clone();
+
+    assert_eq!(state.get_blocks_range(1, 2), blocks[1..3].to_vec());
+    assert_eq!(state.get_blocks_range(0, 0), vec![]);
+
+    // Blocks 0 and 1 move to the archive.
+    state.remove_archived_blocks(2);
+
+    // Entirely archived.
+    assert_eq!(state.get_blocks_range(0, 2), vec![]);
+    // Partially archived, clamped to the local blocks.
+    assert_eq!(state.get_blocks_range(1, 3), blocks[2..4].to_vec());
+    // Beyond the tip, clamped to the local blocks.
+    assert_eq!(state.get_blocks_range(3, 10), blocks[3..5].to_vec());
+    assert_eq!(state.get_blocks_range(5, 10), vec![]);
+    assert_eq!(state.get_blocks_range(100, 10), vec![]);
+}
+
+#[test]
+fn test_approve_fee() {
+    let mut ctx = Ledger::default();
+
+    let from = test_account_id(1);
+    let to = test_account_id(2);
+    let now = ts(12345678);
+
+    ctx.balances_mut().mint(&from, tokens(100_000)).unwrap();
+
+    // An unset approve fee falls back to the transfer fee.
+    ctx.transfer_fee = tokens(10_000);
+    assert_eq!(ctx.approve_fee(), tokens(10_000));
+
+    ctx.approve_fee = Some(tokens(1_000));
+    assert_eq!(ctx.approve_fee(), tokens(1_000));
+    assert_eq!(ctx.transfer_fee, tokens(10_000));
+
+    let approve = Operation::Approve {
+        from,
+        spender: to,
+        allowance: tokens(50_000),
+        expected_allowance: None,
+        expires_at: None,
+        fee: ctx.approve_fee(),
+    };
+    apply_operation(&mut ctx, &approve, now).unwrap();
+    assert_eq!(ctx.balances().account_balance(&from), tokens(99_000));
+
+    let transfer = Operation::Transfer {
+        from,
+        to,
+        spender: None,
+        amount: tokens(10_000),
+        fee: ctx.transfer_fee,
+    };
+    apply_operation(&mut ctx, &transfer, now).unwrap();
+    assert_eq!(ctx.balances().account_balance(&from), tokens(79_000));
+    assert_eq!(ctx.balances().account_balance(&to), tokens(10_000));
+}
+
+#[test]
+fn test_snapshot_metrics() {
+    let mut ctx = Ledger::default();
+    ctx.blockchain.archive = Arc::new(RwLock::new(Some(Archive::new(ArchiveOptions {
+        trigger_threshold: 5,
+        num_blocks_to_archive: 3,
+        node_max_memory_size_bytes: None,
+        max_message_size_bytes: None,
+        controller_id: CanisterId::from_u64(876).into(),
+        more_controller_ids: None,
+        cycles_for_archive_creation: Some(0),
+        max_transactions_per_response: None,
+    }))));
+
+    let from = test_account_id(1);
+    let now = TimeStamp::from(SystemTime::now());
+    for i in 0..3 {
+        ctx.add_payment_with_timestamp(
+            Memo(i),
+            Operation::Mint {
+                to: from,
+                amount: tokens(1_000_000),
+            },
+            Some(now),
+            now,
+        )
+        .unwrap();
+    }
+    for i in 2..4 {
+        apply_operation(
+            &mut ctx,
+            &Operation::Approve {
+                from,
+                spender: test_account_id(i),
+                allowance: tokens(10_000),
+                expected_allowance: None,
+                expires_at: Some(ts(now.as_nanos_since_unix_epoch() + 1_000)),
+                fee: tokens(10_000),
+            },
+            now,
+        )
+        .unwrap();
+    }
+
+    let metrics = ctx.snapshot_metrics();
+    assert_eq!(
+        metrics,
+        LedgerMetrics {
+            balance_store_entries: ctx.balances.store.len(),
+            token_pool: ctx.balances.token_pool,
+            num_approvals: ctx.approvals.get_num_approvals(),
+            num_allowance_expirations: ctx.approvals.get_num_expirations(),
+            num_allowance_arrivals: ctx.approvals.get_num_arrivals(),
+            transactions_by_hash_entries: ctx.transactions_by_hash_len(),
+            transactions_by_height_entries: ctx.transactions_by_height_len(),
+            transaction_window_usage: ctx.transaction_window_usage(),
+            archive_status: ctx.archive_status(),
+            num_archives: 0,
+            most_recent_block_time: ctx.blockchain.last_timestamp,
+        }
+    );
+    assert_eq!(metrics.balance_store_entries, 1);
+    assert_eq!(metrics.num_approvals, 2);
+    assert_eq!(metrics.num_allowance_expirations, 2);
+    assert_eq!(metrics.num_allowance_arrivals, 2);
+    assert_eq!(metrics.transactions_by_height_entries, 3);
+    assert_eq!(metrics.archive_status.local_blocks, 3);
+    assert_eq!(metrics.archive_status.trigger_threshold, Some(5));
+}
+
+#[test]
+fn test_recent_transactions() {
+    let mut ctx = Ledger::default();
+    let to = test_account_id(1);
+    let genesis = TimeStamp::from(SystemTime::now());
+    let block_times: Vec<_> = (0..4u64)
+        .map(|i| genesis + Duration::from_secs(60 * 60 * i))
+        .collect();
+    for (i, now) in block_times.iter().enumerate() {
+        ctx.add_payment_with_timestamp(
+            Memo(i as u64),
+            Operation::Mint {
+                to,
+                amount: tokens(1_000_000),
+            },
+            Some(*now),
+            *now,
+        )
+        .unwrap();
+    }
+    let recent_block_times: Vec<_> = ctx
+        .recent_transactions()
+        .map(|tx_info| tx_info.block_timestamp)
+        .collect();
+    assert_eq!(recent_block_times, block_times);
+
+    // Purge the first two transactions.
+    let now = block_times[1]
+        + ctx.transaction_window
+        + ic_limits::PERMITTED_DRIFT
+        + Duration::from_secs(1);
+    assert_eq!(core_ledger::purge_old_transactions(&mut ctx, now), 2);
+
+    let recent: Vec<_> = ctx
+        .recent_transactions()
+        .map(|tx_info| {
+            (
+                tx_info.block_timestamp,
+                ctx.transactions_by_hash[&tx_info.transaction_hash],
+            )
+        })
+        .collect();
+    assert_eq!(recent, vec![(block_times[2], 2), (block_times[3], 3)]);
+}
+
+#[test]
+fn test_prune_expired_allowances() {
+    let mut ctx = Ledger::default();
+
+    let from = test_account_id(1);
+    let now = ts(12345678);
+    let expires_at = ts(12345678 + 1_000);
+
+    ctx.balances_mut().mint(&from, tokens(1_000_000)).unwrap();
+
+    let approve = |spender, expires_at| Operation::Approve {
+        from,
+        spender,
+        allowance: tokens(10_000),
+        expected_allowance: None,
+        expires_at,
+        fee: tokens(10_000),
+    };
+    // Five allowances expiring at `expires_at` and one that never expires.
+    for i in 2..7 {
+        apply_operation(
+            &mut ctx,
+            &approve(test_account_id(i), Some(expires_at)),
+            now,
+        )
+        .unwrap();
+    }
+    apply_operation(&mut ctx, &approve(test_account_id(7), None), now).unwrap();
+    assert_eq!(ctx.approvals.len(), 6);
+
+    // Nothing has expired yet.
+    assert_eq!(ctx.prune_expired_allowances(now, 10), 0);
+    assert_eq!(ctx.approvals.len(), 6);
+
+    // A bounded sweep removes exactly `max` expired allowances.
+    let later = ts(12345678 + 2_000);
+    assert_eq!(ctx.prune_expired_allowances(later, 3), 3);
+    assert_eq!(ctx.approvals.len(), 3);
+
+    // The next sweep removes the remaining expired ones only.
+    assert_eq!(ctx.prune_expired_allowances(later, 10), 2);
+    assert_eq!(ctx.approvals.len(), 1);
+    assert_eq!(
+        ctx.approvals
+            .allowance(&from, &test_account_id(7), later)
+            .amount,
+        tokens(10_000)
+    );
+}
+
+#[test]
+fn test_effective_icrc1_minting_account() {
+    let minting_account = Account {
+        owner: PrincipalId::new_user_test_id(1000).0,
+        subaccount: None,
+    };
+    let minting_account_id = AccountIdentifier::from(minting_account);
+
+    let ledger_with = |minting_account_id, icrc1_minting_account| Ledger {
+        minting_account_id,
+        icrc1_minting_account,
+        ..Ledger::default()
+    };
+
+    // Neither set.
+    assert_eq!(
+        ledger_with(None, None).effective_icrc1_minting_account(),
+        None
+    );
+    // Only the account identifier set: it cannot be turned into an `Account`.
+    assert_eq!(
+        ledger_with(Some(minting_account_id), None).effective_icrc1_minting_account(),
+        None
+    );
+    // Only the ICRC-1 account set.
+    assert_eq!(
+        ledger_with(None, Some(minting_account)).effective_icrc1_minting_account(),
+        Some(minting_account)
+    );
+    // Both set.
+    assert_eq!(
+        ledger_with(Some(minting_account_id), Some(minting_account))
+            .effective_icrc1_minting_account(),
+        Some(minting_account)
+    );
+}
+
+#[test]
+fn test_add_block_rejects_wrong_parent_hash() {
+    let mut state = Ledger::default();
+
+    let mint = |to, amount| Block {
+        parent_hash: None,
+        transaction: Transaction {
+            operation: Operation::Mint { to, amount },
+            memo: Memo(0),
+            icrc1_memo: None,
+            created_at_time: None,
+        },
+        timestamp: ts(1_000),
+    };
+
+    let block = mint(test_account_id(1), tokens(1_000));
+    let block_hash = Block::block_hash(&block.clone().encode());
+    state.add_block(block).unwrap();
+
+    // The genesis block has no parent, but the chain already has a tip.
+    let forked = mint(test_account_id(2), tokens(500));
+    assert!(state
+        .add_block(forked)
+        .unwrap_err()
+        .contains("parent hash does not match"));
+
+    // A block with an arbitrary parent hash is rejected as well.
+    let mut misordered = mint(test_account_id(2), tokens(500));
+    misordered.parent_hash = Some(Block::block_hash(&misordered.clone().encode()));
+    assert!(state.add_block(misordered).is_err());
+
+    // Neither rejected block was applied.
+    assert_eq!(state.blockchain.chain_length(), 1);
+    assert_eq!(
+        state.balances().account_balance(&test_account_id(2)),
+        Tokens::ZERO
+    );
+    assert_eq!(state.balances().total_supply(), tokens(1_000));
+
+    // A block linked to the tip is accepted.
+    let mut linked = mint(test_account_id(2), tokens(500));
+    linked.parent_hash = Some(block_hash);
+    state.add_block(linked).unwrap();
+    assert_eq!(
+        state.balances().account_balance(&test_account_id(2)),
+        tokens(500)
+    );
+}
@@ -418,6 +418,7 @@ fn check_old_init() {
         feature_flags: None,
         maximum_number_of_accounts: None,
         accounts_overflow_trim_quantity: None,
+        approve_fee: None,
     })
     .unwrap();
     env.install_canister(ledger_wasm(), old_init, None)
@@ -1340,6 +1341,8 @@ fn test_feature_flags() {
         Encode!(&LedgerCanisterPayload::Upgrade(Some(UpgradeArgs {
             icrc1_minting_account: None,
             feature_flags: Some(FeatureFlags { icrc2: false }),
+            blocked_senders: None,
+            approve_fee: None,
         })))
         .unwrap(),
     )
@@ -1360,6 +1363,8 @@ fn test_feature_flags() {
         Encode!(&LedgerCanisterPayload::Upgrade(Some(UpgradeArgs {
             icrc1_minting_account: None,
             feature_flags: Some(FeatureFlags { icrc2: true }),
+            blocked_senders: None,
+            approve_fee: None,
         })))
         .unwrap(),
     )
@@ -424,6 +424,14 @@ impl BlockType for Block {
     }
 }
 
+/// The maximum number of transactions the ledger accepts within the
+/// transaction window, along with the number of transactions currently in it.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize, Serialize)]
+pub struct TransactionWindowUsage {
+    pub max_transactions_in_window: u64,
+    pub transactions_in_window: u64,
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize, Serialize)]
 pub struct TransferFee {
     /// The fee to pay to perform a transfer
@@ -458,6 +466,14 @@ pub struct UpgradeArgs {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub feature_flags: Option<FeatureFlags>,
+
+    /// Replaces the set of principals that are not allowed to send tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_senders: Option<Vec<PrincipalId>>,
+
+    /// Sets the fee to pay to perform an approve.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approve_fee: Option<Tokens>,
 }
 
 // This is how we pass arguments to 'init' in main.rs
@@ -476,6 +492,7 @@ pub struct InitArgs {
     pub feature_flags: Option<FeatureFlags>,
     pub maximum_number_of_accounts: Option<usize>,
     pub accounts_overflow_trim_quantity: Option<usize>,
+    pub approve_fee: Option<Tokens>,
 }
 
 impl LedgerCanisterInitPayload {
@@ -510,6 +527,7 @@ pub struct LedgerCanisterInitPayloadBuilder {
     feature_flags: Option<FeatureFlags>,
     maximum_number_of_accounts: Option<usize>,
     accounts_overflow_trim_quantity: Option<usize>,
+    approve_fee: Option<Tokens>,
 }
 
 impl LedgerCanisterInitPayloadBuilder {
@@ -528,6 +546,7 @@ impl LedgerCanisterInitPayloadBuilder {
             feature_flags: None,
             maximum_number_of_accounts: None,
             accounts_overflow_trim_quantity: None,
+            approve_fee: None,
         }
     }
 
@@ -571,6 +590,11 @@ impl LedgerCanisterInitPayloadBuilder {
         self
     }
 
+    pub fn approve_fee(mut self, approve_fee: Tokens) -> Self {
+        self.approve_fee = Some(approve_fee);
+        self
+    }
+
     pub fn token_symbol_and_name(mut self, token_symbol: &str, token_name: &str) -> Self {
         self.token_symbol = Some(token_symbol.to_string());
         self.token_name = Some(token_name.to_string());
@@ -634,6 +658,7 @@ impl LedgerCanisterInitPayloadBuilder {
                 feature_flags: self.feature_flags,
                 maximum_number_of_accounts: self.maximum_number_of_accounts,
                 accounts_overflow_trim_quantity: self.accounts_overflow_trim_quantity,
+                approve_fee: self.approve_fee,
             },
         )))
     }
@@ -642,6 +667,8 @@ impl LedgerCanisterInitPayloadBuilder {
 pub struct LedgerCanisterUpgradePayloadBuilder {
     icrc1_minting_account: Option<Account>,
     feature_flags: Option<FeatureFlags>,
+    blocked_senders: Option<Vec<PrincipalId>>,
+    approve_fee: Option<Tokens>,
 }
 
 impl LedgerCanisterUpgradePayloadBuilder {
@@ -649,6 +676,8 @@ impl LedgerCanisterUpgradePayloadBuilder {
         Self {
             icrc1_minting_account: None,
             feature_flags: None,
+            blocked_senders: None,
+            approve_fee: None,
         }
     }
 
@@ -657,11 +686,23 @@ impl LedgerCanisterUpgradePayloadBuilder {
         self
     }
 
+    pub fn blocked_senders(mut self, blocked_senders: Vec<PrincipalId>) -> Self {
+        self.blocked_senders = Some(blocked_senders);
+        self
+    }
+
+    pub fn approve_fee(mut self, approve_fee: Tokens) -> Self {
+        self.approve_fee = Some(approve_fee);
+        self
+    }
+
     pub fn build(self) -> Result<LedgerCanisterUpgradePayload, String> {
         Ok(LedgerCanisterUpgradePayload(
             LedgerCanisterPayload::Upgrade(Some(UpgradeArgs {
                 icrc1_minting_account: self.icrc1_minting_account,
                 feature_flags: self.feature_flags,
+                blocked_senders: self.blocked_senders,
+                approve_fee: self.approve_fee,
             })),
         ))
     }
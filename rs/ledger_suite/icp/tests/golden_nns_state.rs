@@ -158,6 +158,8 @@ impl Setup {
             LedgerCanisterPayload::Upgrade(Some(UpgradeArgs {
                 icrc1_minting_account: None,
                 feature_flags: Some(FeatureFlags { icrc2: true }),
+                blocked_senders: None,
+                approve_fee: None,
             }));
 
         self.state_machine
@@ -759,7 +759,8 @@ impl StreamHandlerImpl {
                         StateError::QueueFull { .. } => RejectCode::SysTransient,
                         StateError::OutOfMemory { .. } => RejectCode::CanisterError,
                         StateError::NonMatchingResponse { .. }
-                        | StateError::BitcoinNonMatchingResponse { .. } => {
+                        | StateError::BitcoinNonMatchingResponse { .. }
+                        | StateError::InvalidSubnetRejection { .. } => {
                             unreachable!("Not a user error: {}", err);
                         }
                     };
@@ -23,11 +23,12 @@ use ic_types::{
 use crate::FetchArtifact;
 
 use super::{
-    download::download_ingress,
+    download::{download_full_block, download_ingress},
     metrics::{FetchStrippedConsensusArtifactMetrics, IngressMessageSource, IngressSenderMetrics},
     stripper::Strippable,
     types::stripped::{
-        MaybeStrippedConsensusMessage, StrippedBlockProposal, StrippedConsensusMessageId,
+        MaybeStrippedConsensusMessage, MaybeStrippedIngress, StrippedBlockProposal,
+        StrippedConsensusMessageId, StrippedIngressCounts,
     },
 };
 
@@ -39,6 +40,7 @@ struct BouncerFactoryWrapper<Pool: ValidatedPoolReader<ConsensusMessage>> {
 
 struct ConsensusPoolWrapper<Pool: ValidatedPoolReader<ConsensusMessage>> {
     consensus_pool: Arc<RwLock<Pool>>,
+    min_ingress_bytes_to_strip: usize,
 }
 
 impl<Pool: ValidatedPoolReader<ConsensusMessage>> ValidatedPoolReader<MaybeStrippedConsensusMessage>
@@ -52,7 +54,7 @@ impl<Pool: ValidatedPoolReader<ConsensusMessage>> ValidatedPoolReader<MaybeStrip
             .read()
             .unwrap()
             .get(id.as_ref())
-            .map(Strippable::strip)
+            .map(|msg| msg.strip(self.min_ingress_bytes_to_strip))
     }
 }
 
@@ -75,6 +77,34 @@ impl<Pool: ValidatedPoolReader<ConsensusMessage>>
     }
 }
 
+/// Decides when the ingress messages stripped from a received block proposal should not be
+/// fetched one by one, but the whole, unstripped block proposal should be fetched instead.
+///
+/// Fetching each stripped ingress message takes a separate rpc, so when nearly all ingress
+/// messages of a block proposal are stripped, a single request for the full block proposal is
+/// usually faster.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FullBlockFallbackPolicy {
+    /// Minimum number of stripped ingress messages for the fallback to kick in.
+    pub min_stripped_ingress_messages: usize,
+    /// Minimum share, in percent, of the ingress messages in the block proposal which have to be
+    /// stripped for the fallback to kick in.
+    pub min_stripped_ingress_messages_percentage: u8,
+}
+
+impl FullBlockFallbackPolicy {
+    /// Returns `true` if the full block proposal should be fetched instead of the stripped
+    /// ingress messages.
+    pub(crate) fn should_fetch_full_block(&self, counts: StrippedIngressCounts) -> bool {
+        let total = counts.full + counts.stripped;
+
+        counts.stripped > 0
+            && counts.stripped >= self.min_stripped_ingress_messages
+            && counts.stripped * 100
+                >= total * usize::from(self.min_stripped_ingress_messages_percentage)
+    }
+}
+
 #[derive(Clone)]
 pub struct FetchStrippedConsensusArtifact {
     log: ReplicaLogger,
@@ -82,10 +112,20 @@ pub struct FetchStrippedConsensusArtifact {
     fetch_stripped: FetchArtifact<MaybeStrippedConsensusMessage>,
     transport: Arc<dyn Transport>,
     node_id: NodeId,
+    min_ingress_bytes_to_strip: usize,
+    full_block_fallback: Option<FullBlockFallbackPolicy>,
     metrics: Arc<FetchStrippedConsensusArtifactMetrics>,
 }
 
 impl FetchStrippedConsensusArtifact {
+    /// Creates the assembler and the router serving stripped ingress messages to peers.
+    ///
+    /// Only ingress messages of at least `min_ingress_bytes_to_strip` bytes are stripped from
+    /// the block proposals sent to peers; smaller ones are sent inline, because refetching them
+    /// costs more than sending them along. With `0`, all ingress messages are stripped.
+    ///
+    /// When `full_block_fallback` is set, received block proposals with too many stripped
+    /// ingress messages are fetched in full instead; see [`FullBlockFallbackPolicy`].
     pub fn new<Pool: ValidatedPoolReader<ConsensusMessage> + Send + Sync + 'static>(
         log: ReplicaLogger,
         rt: tokio::runtime::Handle,
@@ -94,6 +134,8 @@ impl FetchStrippedConsensusArtifact {
         bouncer_factory: Arc<dyn BouncerFactory<ConsensusMessageId, Pool>>,
         metrics_registry: MetricsRegistry,
         node_id: NodeId,
+        min_ingress_bytes_to_strip: usize,
+        full_block_fallback: Option<FullBlockFallbackPolicy>,
     ) -> (impl Fn(Arc<dyn Transport>) -> Self, axum::Router) {
         let ingress_pool_clone = ingress_pool.clone();
         let consensus_pool_clone = consensus_pool.clone();
@@ -107,7 +149,10 @@ impl FetchStrippedConsensusArtifact {
         let (fetch_stripped_fn, subrouter) = FetchArtifact::new(
             log.clone(),
             rt,
-            Arc::new(RwLock::new(ConsensusPoolWrapper { consensus_pool })),
+            Arc::new(RwLock::new(ConsensusPoolWrapper {
+                consensus_pool,
+                min_ingress_bytes_to_strip,
+            })),
             Arc::new(BouncerFactoryWrapper { bouncer_factory }),
             metrics_registry.clone(),
         );
@@ -123,6 +168,8 @@ impl FetchStrippedConsensusArtifact {
                 fetch_stripped,
                 transport,
                 node_id,
+                min_ingress_bytes_to_strip,
+                full_block_fallback,
                 metrics: Arc::new(FetchStrippedConsensusArtifactMetrics::new(
                     &metrics_registry,
                 )),
@@ -137,7 +184,7 @@ impl ArtifactAssembler<ConsensusMessage, MaybeStrippedConsensusMessage>
     for FetchStrippedConsensusArtifact
 {
     fn disassemble_message(&self, msg: ConsensusMessage) -> MaybeStrippedConsensusMessage {
-        msg.strip()
+        msg.strip(self.min_ingress_bytes_to_strip)
     }
 
     async fn assemble_message<P: Peers + Clone + Send + 'static>(
@@ -161,15 +208,36 @@ impl ArtifactAssembler<ConsensusMessage, MaybeStrippedConsensusMessage>
             }
         };
 
+        let counts = stripped_block_proposal.stripped_ingress_payload.counts();
+        self.metrics.report_received_ingress_messages_counts(counts);
+
+        if self
+            .full_block_fallback
+            .is_some_and(|policy| policy.should_fetch_full_block(counts))
+        {
+            self.metrics.full_block_fallbacks.inc();
+
+            return Ok(download_full_block(
+                self.transport.clone(),
+                id.as_ref().clone(),
+                &self.log,
+                &self.metrics,
+                peer_rx,
+            )
+            .await);
+        }
+
         let mut join_set = tokio::task::JoinSet::new();
 
         let timer = self
             .metrics
             .download_missing_ingress_messages_duration
             .start_timer();
-        let mut assembler = BlockProposalAssembler::new(stripped_block_proposal);
 
-        let missing_ingress_ids = assembler.missing_ingress_messages();
+        let missing_ingress_ids = stripped_block_proposal
+            .stripped_ingress_payload
+            .stripped_ingress_messages();
+        let missing_ingress_ids_count = missing_ingress_ids.len();
         // For each stripped object in the message, try to fetch it either from the local pools
         // or from a random peer who is advertising it.
         for missing_ingress_id in missing_ingress_ids {
@@ -185,6 +253,7 @@ impl ArtifactAssembler<ConsensusMessage, MaybeStrippedConsensusMessage>
             ));
         }
 
+        let mut ingress_messages = Vec::with_capacity(missing_ingress_ids_count);
         let mut ingress_messages_from_ingress_pool = 0;
         let mut ingress_messages_from_peers = 0;
 
@@ -202,14 +271,7 @@ impl ArtifactAssembler<ConsensusMessage, MaybeStrippedConsensusMessage>
                 ingress_messages_from_peers += 1;
             }
 
-            if let Err(err) = assembler.try_insert_ingress_message(ingress) {
-                warn!(
-                    self.log,
-                    "Failed to ingress message {}. This is a bug.", err
-                );
-
-                return Err(Aborted {});
-            }
+            ingress_messages.push(ingress);
         }
 
         // Only report the metric if we actually downloaded some ingresses from peers
@@ -227,19 +289,15 @@ impl ArtifactAssembler<ConsensusMessage, MaybeStrippedConsensusMessage>
             ingress_messages_from_ingress_pool,
         );
 
-        let reconstructed_block_proposal = assembler.try_assemble().map_err(|err| {
-            warn!(
-                self.log,
-                "Failed to reassemble the block {}. This is a bug.", err
-            );
+        let reconstructed_consensus_message = stripped_block_proposal
+            .try_reconstruct(ingress_messages)
+            .map_err(|err| {
+                warn!(self.log, "Failed to reassemble the block {}", err);
 
-            Aborted {}
-        })?;
+                Aborted {}
+            })?;
 
-        Ok((
-            ConsensusMessage::BlockProposal(reconstructed_block_proposal),
-            peer,
-        ))
+        Ok((reconstructed_consensus_message, peer))
     }
 }
 
@@ -286,6 +344,56 @@ pub(crate) enum AssemblyError {
     Missing(IngressMessageId),
     #[error("The block proposal cannot be deserialized {0}")]
     DeserializationFailed(ProxyDecodeError),
+    #[error("Failed to insert an ingress message into the block proposal: {0}")]
+    InsertionFailed(InsertionError),
+    #[error(
+        "The reassembled block proposal has id {actual:?} but the stripped block proposal \
+        was advertised with id {expected:?}"
+    )]
+    IdMismatch {
+        expected: ConsensusMessageId,
+        actual: ConsensusMessageId,
+    },
+}
+
+impl StrippedBlockProposal {
+    /// Reconstructs the full [`ConsensusMessage`] from the stripped block proposal and the
+    /// ingress messages which were stripped from it.
+    ///
+    /// The ingress messages can be provided in any order; each one is put back at the position
+    /// of its [`IngressMessageId`] in the stripped ingress payload.
+    ///
+    /// Fails if any of the provided ingress messages was not stripped from the block, if some
+    /// stripped ingress message is not provided, or if the id of the reconstructed message
+    /// doesn't match `unstripped_consensus_message_id`, e.g. because a peer served an ingress
+    /// message with the right [`IngressMessageId`] but different signed content, or reordered
+    /// the ingress messages of the stripped ingress payload.
+    pub(crate) fn try_reconstruct(
+        self,
+        ingress_messages: Vec<SignedIngress>,
+    ) -> Result<ConsensusMessage, AssemblyError> {
+        let expected_id = self.unstripped_consensus_message_id.clone();
+        let mut assembler = BlockProposalAssembler::new(self);
+
+        for ingress_message in ingress_messages {
+            assembler
+                .try_insert_ingress_message(ingress_message)
+                .map_err(AssemblyError::InsertionFailed)?;
+        }
+
+        let reconstructed_consensus_message =
+            ConsensusMessage::BlockProposal(assembler.try_assemble()?);
+        let actual_id = reconstructed_consensus_message.id();
+
+        if actual_id != expected_id {
+            return Err(AssemblyError::IdMismatch {
+                expected: expected_id,
+                actual: actual_id,
+            });
+        }
+
+        Ok(reconstructed_consensus_message)
+    }
 }
 
 struct BlockProposalAssembler {
@@ -300,13 +408,21 @@ impl BlockProposalAssembler {
                 .stripped_ingress_payload
                 .ingress_messages
                 .iter()
-                .map(|ingress_message_id| (ingress_message_id.clone(), None))
+                .map(|maybe_stripped_ingress| match maybe_stripped_ingress {
+                    MaybeStrippedIngress::Full(ingress_message) => {
+                        (maybe_stripped_ingress.id(), Some(ingress_message.clone()))
+                    }
+                    MaybeStrippedIngress::Stripped(ingress_message_id) => {
+                        (ingress_message_id.clone(), None)
+                    }
+                })
                 .collect(),
             stripped_block_proposal,
         }
     }
 
     /// Returns the list of [`IngressMessageId`]s which have been stripped from the block.
+    #[cfg(test)]
     pub(crate) fn missing_ingress_messages(&self) -> Vec<IngressMessageId> {
         self.ingress_messages
             .iter()
@@ -353,6 +469,8 @@ impl BlockProposalAssembler {
             .stripped_block_proposal
             .block_proposal_without_ingresses_proto;
 
+        // `ingress_messages` follows the order of the stripped ingress payload, which is the
+        // order of the original ingress payload.
         let ingresses = self
             .ingress_messages
             .into_iter()
@@ -375,9 +493,14 @@ impl BlockProposalAssembler {
 
 #[cfg(test)]
 mod tests {
+    use ic_types::time::expiry_time_from_now;
+    use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+
     use crate::fetch_stripped_artifact::test_utils::{
         fake_block_proposal_with_ingresses, fake_ingress_message,
-        fake_ingress_message_with_arg_size, fake_stripped_block_proposal_with_ingresses,
+        fake_ingress_message_with_arg_size, fake_ingress_message_with_signature,
+        fake_stripped_block_proposal_with_ingresses,
+        fake_stripped_block_proposal_with_maybe_stripped_ingresses,
     };
 
     use super::*;
@@ -392,7 +515,7 @@ mod tests {
 
         // strip the block
         let MaybeStrippedConsensusMessage::StrippedBlockProposal(stripped_block_proposal) =
-            consensus_message.strip()
+            consensus_message.strip(0)
         else {
             panic!("Didn't properly strip the block proposal");
         };
@@ -419,7 +542,7 @@ mod tests {
 
         // strip the block
         let MaybeStrippedConsensusMessage::StrippedBlockProposal(stripped_block_proposal) =
-            consensus_message.strip()
+            consensus_message.strip(0)
         else {
             panic!("Didn't properly strip the block proposal");
         };
@@ -438,6 +561,198 @@ mod tests {
         }
     }
 
+    #[test]
+    fn strip_reconstruct_roundtrip_test() {
+        let (ingress_1, _ingress_id_1) = fake_ingress_message_with_arg_size("fake_1", 1024);
+        let (ingress_2, _ingress_id_2) = fake_ingress_message_with_arg_size("fake_2", 1024);
+        let consensus_message =
+            ConsensusMessage::BlockProposal(fake_block_proposal_with_ingresses(vec![
+                ingress_1.clone(),
+                ingress_2.clone(),
+            ]));
+
+        // strip the block
+        let MaybeStrippedConsensusMessage::StrippedBlockProposal(stripped_block_proposal) =
+            consensus_message.clone().strip(0)
+        else {
+            panic!("Didn't properly strip the block proposal");
+        };
+
+        // reconstruct the block, providing the ingress messages in a different order
+        let reconstructed_consensus_message = stripped_block_proposal
+            .try_reconstruct(vec![ingress_2, ingress_1])
+            .unwrap();
+
+        assert_eq!(reconstructed_consensus_message, consensus_message);
+    }
+
+    #[test]
+    fn strip_reconstruct_with_shuffled_ingress_messages_test() {
+        let ingress_messages: Vec<_> = (0..10)
+            .map(|i| fake_ingress_message(&format!("fake_{i}")).0)
+            .collect();
+        let consensus_message = ConsensusMessage::BlockProposal(
+            fake_block_proposal_with_ingresses(ingress_messages.clone()),
+        );
+
+        // strip the block
+        let MaybeStrippedConsensusMessage::StrippedBlockProposal(stripped_block_proposal) =
+            consensus_message.clone().strip(0)
+        else {
+            panic!("Didn't properly strip the block proposal");
+        };
+
+        // the refetched ingress messages arrive in random order
+        let mut refetched_ingress_messages = ingress_messages;
+        refetched_ingress_messages.shuffle(&mut SmallRng::seed_from_u64(42));
+
+        let reconstructed_consensus_message = stripped_block_proposal
+            .try_reconstruct(refetched_ingress_messages)
+            .unwrap();
+
+        assert_eq!(reconstructed_consensus_message, consensus_message);
+    }
+
+    #[test]
+    fn strip_reconstruct_rejects_reordered_ingress_payload_test() {
+        let (ingress_1, _ingress_id_1) = fake_ingress_message("fake_1");
+        let (ingress_2, _ingress_id_2) = fake_ingress_message("fake_2");
+        let consensus_message =
+            ConsensusMessage::BlockProposal(fake_block_proposal_with_ingresses(vec![
+                ingress_1.clone(),
+                ingress_2.clone(),
+            ]));
+
+        // strip the block
+        let MaybeStrippedConsensusMessage::StrippedBlockProposal(mut stripped_block_proposal) =
+            consensus_message.clone().strip(0)
+        else {
+            panic!("Didn't properly strip the block proposal");
+        };
+
+        // swap the order of the ingress messages in the stripped block
+        stripped_block_proposal
+            .stripped_ingress_payload
+            .ingress_messages
+            .swap(0, 1);
+
+        let reconstruction_error = stripped_block_proposal
+            .try_reconstruct(vec![ingress_1, ingress_2])
+            .unwrap_err();
+
+        match reconstruction_error {
+            AssemblyError::IdMismatch { expected, actual } => {
+                assert_eq!(expected, consensus_message.id());
+                assert_ne!(actual, expected);
+            }
+            _ => panic!("Wrong error"),
+        }
+    }
+
+    #[test]
+    fn strip_reconstruct_detects_tampered_ingress_test() {
+        let ingress_expiry = expiry_time_from_now();
+        let ingress_1 = fake_ingress_message_with_signature("fake_1", ingress_expiry, vec![1; 32]);
+        // Same content, and hence the same `IngressMessageId`, but a different signature.
+        let tampered_ingress_1 =
+            fake_ingress_message_with_signature("fake_1", ingress_expiry, vec![7; 32]);
+        assert_eq!(
+            IngressMessageId::from(&ingress_1),
+            IngressMessageId::from(&tampered_ingress_1)
+        );
+        let consensus_message =
+            ConsensusMessage::BlockProposal(fake_block_proposal_with_ingresses(vec![ingress_1]));
+
+        // strip the block
+        let MaybeStrippedConsensusMessage::StrippedBlockProposal(stripped_block_proposal) =
+            consensus_message.clone().strip(0)
+        else {
+            panic!("Didn't properly strip the block proposal");
+        };
+
+        // try to reconstruct the block with the tampered ingress message
+        let reconstruction_error = stripped_block_proposal
+            .try_reconstruct(vec![tampered_ingress_1])
+            .unwrap_err();
+
+        match reconstruction_error {
+            AssemblyError::IdMismatch { expected, actual } => {
+                assert_eq!(expected, consensus_message.id());
+                assert_ne!(actual, expected);
+            }
+            _ => panic!("Wrong error"),
+        }
+    }
+
+    #[test]
+    fn strip_reconstruct_fails_on_unknown_ingress_test() {
+        let (ingress_1, _ingress_id_1) = fake_ingress_message("fake_1");
+        let (ingress_2, _ingress_id_2) = fake_ingress_message("fake_2");
+        let consensus_message =
+            ConsensusMessage::BlockProposal(fake_block_proposal_with_ingresses(vec![ingress_1]));
+
+        // strip the block
+        let MaybeStrippedConsensusMessage::StrippedBlockProposal(stripped_block_proposal) =
+            consensus_message.strip(0)
+        else {
+            panic!("Didn't properly strip the block proposal");
+        };
+
+        let reconstruction_error = stripped_block_proposal
+            .try_reconstruct(vec![ingress_2])
+            .unwrap_err();
+
+        match reconstruction_error {
+            AssemblyError::InsertionFailed(InsertionError::NotNeeded) => (),
+            _ => panic!("Wrong error"),
+        }
+    }
+
+    #[test]
+    fn full_block_fallback_above_threshold_test() {
+        let policy = FullBlockFallbackPolicy {
+            min_stripped_ingress_messages: 3,
+            min_stripped_ingress_messages_percentage: 75,
+        };
+        let mut ingress_messages =
+            vec![MaybeStrippedIngress::Full(fake_ingress_message("fake_0").0)];
+        ingress_messages.extend(
+            (1..=3).map(|i| {
+                MaybeStrippedIngress::Stripped(fake_ingress_message(&format!("fake_{i}")).1)
+            }),
+        );
+        let stripped_block_proposal =
+            fake_stripped_block_proposal_with_maybe_stripped_ingresses(ingress_messages);
+
+        assert!(policy
+            .should_fetch_full_block(stripped_block_proposal.stripped_ingress_payload.counts()));
+    }
+
+    #[test]
+    fn full_block_fallback_below_threshold_test() {
+        let policy = FullBlockFallbackPolicy {
+            min_stripped_ingress_messages: 3,
+            min_stripped_ingress_messages_percentage: 75,
+        };
+
+        // Too few stripped ingress messages.
+        assert!(!policy.should_fetch_full_block(StrippedIngressCounts {
+            full: 0,
+            stripped: 2,
+        }));
+        // Too small share of stripped ingress messages.
+        assert!(!policy.should_fetch_full_block(StrippedIngressCounts {
+            full: 2,
+            stripped: 3,
+        }));
+        // No ingress messages at all.
+        assert!(!FullBlockFallbackPolicy {
+            min_stripped_ingress_messages: 0,
+            min_stripped_ingress_messages_percentage: 0,
+        }
+        .should_fetch_full_block(StrippedIngressCounts::default()));
+    }
+
     #[test]
     fn missing_ingress_messages_test() {
         let (_ingress_1, ingress_1_id) = fake_ingress_message("fake_1");
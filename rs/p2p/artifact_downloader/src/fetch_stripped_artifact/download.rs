@@ -32,6 +32,7 @@ use super::{
 type ValidatedPoolReaderRef<T> = Arc<RwLock<dyn ValidatedPoolReader<T> + Send + Sync>>;
 
 const URI: &str = "/block/ingress/rpc";
+const FULL_BLOCK_URI: &str = "/block/full/rpc";
 const MIN_ARTIFACT_RPC_TIMEOUT: Duration = Duration::from_secs(5);
 const MAX_ARTIFACT_RPC_TIMEOUT: Duration = Duration::from_secs(120);
 
@@ -94,11 +95,30 @@ impl Pools {
             }
         }
     }
+
+    /// Retrieves the requested full block proposal from the consensus pool.
+    fn get_block_proposal(
+        &self,
+        block_proposal_id: &ConsensusMessageId,
+    ) -> Result<ConsensusMessage, PoolsAccessError> {
+        let consensus_artifact = self
+            .consensus_pool
+            .read()
+            .unwrap()
+            .get(block_proposal_id)
+            .ok_or(PoolsAccessError::BlockNotFound)?;
+
+        match consensus_artifact {
+            ConsensusMessage::BlockProposal(_) => Ok(consensus_artifact),
+            _ => Err(PoolsAccessError::NotABlockProposal),
+        }
+    }
 }
 
 pub(super) fn build_axum_router(pools: Pools) -> Router {
     Router::new()
         .route(URI, any(rpc_handler))
+        .route(FULL_BLOCK_URI, any(full_block_rpc_handler))
         .with_state(pools)
         // Disable request size limit since consensus might push artifacts larger than limit.
         .layer(DefaultBodyLimit::disable())
@@ -134,6 +154,31 @@ async fn rpc_handler(State(pools): State<Pools>, payload: Bytes) -> Result<Bytes
     Ok(bytes)
 }
 
+async fn full_block_rpc_handler(
+    State(pools): State<Pools>,
+    payload: Bytes,
+) -> Result<Bytes, StatusCode> {
+    let join_handle = tokio::task::spawn_blocking(move || {
+        let block_proposal_id = pb::ConsensusMessageId::proxy_decode(&payload)
+            .and_then(|proto: pb::ConsensusMessageId| ConsensusMessageId::try_from(proto))
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+        match pools.get_block_proposal(&block_proposal_id) {
+            Ok(block_proposal) => Ok::<_, StatusCode>(Bytes::from(
+                pb::ConsensusMessage::proxy_encode(block_proposal),
+            )),
+            Err(PoolsAccessError::BlockNotFound) => Err(StatusCode::NOT_FOUND),
+            Err(_) => Err(StatusCode::BAD_REQUEST),
+        }
+    });
+
+    let bytes = join_handle
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+    Ok(bytes)
+}
+
 /// Downloads the missing ingress messages from a random peer.
 pub(crate) async fn download_ingress<P: Peers>(
     transport: Arc<dyn Transport>,
@@ -194,6 +239,59 @@ pub(crate) async fn download_ingress<P: Peers>(
     }
 }
 
+/// Downloads the full, unstripped block proposal from a random peer.
+pub(crate) async fn download_full_block<P: Peers>(
+    transport: Arc<dyn Transport>,
+    block_proposal_id: ConsensusMessageId,
+    log: &ReplicaLogger,
+    metrics: &FetchStrippedConsensusArtifactMetrics,
+    peer_rx: P,
+) -> (ConsensusMessage, NodeId) {
+    let mut artifact_download_timeout = ExponentialBackoffBuilder::new()
+        .with_initial_interval(MIN_ARTIFACT_RPC_TIMEOUT)
+        .with_max_interval(MAX_ARTIFACT_RPC_TIMEOUT)
+        .with_max_elapsed_time(None)
+        .build();
+
+    let mut rng = SmallRng::from_entropy();
+
+    let bytes = Bytes::from(pb::ConsensusMessageId::proxy_encode(
+        block_proposal_id.clone(),
+    ));
+    let request = Request::builder().uri(FULL_BLOCK_URI).body(bytes).unwrap();
+
+    loop {
+        let next_request_at = Instant::now()
+            + artifact_download_timeout
+                .next_backoff()
+                .unwrap_or(MAX_ARTIFACT_RPC_TIMEOUT);
+        if let Some(peer) = { peer_rx.peers().into_iter().choose(&mut rng) } {
+            match timeout_at(next_request_at, transport.rpc(&peer, request.clone())).await {
+                Ok(Ok(response)) if response.status() == StatusCode::OK => {
+                    let body = response.into_body();
+                    if let Ok(block_proposal) = pb::ConsensusMessage::proxy_decode(&body)
+                        .and_then(|proto: pb::ConsensusMessage| ConsensusMessage::try_from(proto))
+                    {
+                        if ConsensusMessageId::from(&block_proposal) == block_proposal_id {
+                            return (block_proposal, peer);
+                        } else {
+                            warn!(
+                                log,
+                                "Peer {} responded with wrong artifact for advert", peer
+                            );
+                        }
+                    }
+                }
+                _ => {
+                    metrics.total_full_block_download_errors.inc();
+                }
+            }
+        }
+
+        sleep_until(next_request_at).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::fetch_stripped_artifact::test_utils::{
@@ -377,6 +475,72 @@ mod tests {
         assert_eq!(response, (ingress_message, NODE_1));
     }
 
+    #[tokio::test]
+    async fn rpc_get_full_block_test() {
+        let ingress_message = SignedIngressBuilder::new().nonce(1).build();
+        let block = fake_block_proposal(vec![ingress_message]);
+        let mut consensus_pool = MockValidatedPoolReader::<ConsensusMessage>::default();
+        consensus_pool
+            .expect_get()
+            .with(mockall::predicate::eq(ConsensusMessageId::from(&block)))
+            .once()
+            .return_const(block.clone());
+        let router = build_axum_router(Pools {
+            consensus_pool: Arc::new(RwLock::new(consensus_pool)),
+            ingress_pool: Arc::new(RwLock::new(
+                MockValidatedPoolReader::<SignedIngress>::default(),
+            )),
+            metrics: IngressSenderMetrics::new(&MetricsRegistry::new()),
+        });
+        let request = Request::builder()
+            .uri(FULL_BLOCK_URI)
+            .body(Full::new(Bytes::from(
+                pb::ConsensusMessageId::proxy_encode(ConsensusMessageId::from(&block)),
+            )))
+            .unwrap();
+
+        let rpc_response = router
+            .oneshot(request)
+            .await
+            .expect("Should successfully handle the request");
+        let (parts, body) = rpc_response.into_parts();
+        assert_eq!(parts.status, StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        let response = pb::ConsensusMessage::proxy_decode(&bytes)
+            .and_then(|proto: pb::ConsensusMessage| ConsensusMessage::try_from(proto))
+            .expect("Should return a valid proto");
+
+        assert_eq!(response, block);
+    }
+
+    #[tokio::test]
+    async fn download_full_block_works() {
+        let block = fake_block_proposal(vec![SignedIngressBuilder::new().nonce(1).build()]);
+        let mut mock_transport = MockTransport::new();
+        let mut mock_peers = MockPeers::default();
+        let block_clone = block.clone();
+        mock_peers.expect_peers().return_const(vec![NODE_1]);
+        mock_transport.expect_rpc().returning(move |_, _| {
+            Ok(axum::response::Response::builder()
+                .body(Bytes::from(pb::ConsensusMessage::proxy_encode(
+                    block_clone.clone(),
+                )))
+                .unwrap())
+        });
+
+        let response = download_full_block(
+            Arc::new(mock_transport),
+            ConsensusMessageId::from(&block),
+            &no_op_logger(),
+            &FetchStrippedConsensusArtifactMetrics::new(&MetricsRegistry::new()),
+            mock_peers,
+        )
+        .await;
+
+        assert_eq!(response, (block, NODE_1));
+    }
+
     // Utility functions below
 
     fn fake_block_proposal(ingress_messages: Vec<SignedIngress>) -> ConsensusMessage {
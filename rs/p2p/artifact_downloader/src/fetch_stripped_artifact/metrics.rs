@@ -1,16 +1,22 @@
 use ic_metrics::{buckets::decimal_buckets_with_zero, MetricsRegistry};
 use prometheus::{Histogram, HistogramVec, IntCounter, IntGauge};
 
+use super::types::stripped::StrippedIngressCounts;
+
 const SOURCE_LABEL: &str = "source";
+const KIND_LABEL: &str = "kind";
 
 #[derive(Clone)]
 pub(super) struct FetchStrippedConsensusArtifactMetrics {
     pub(super) ingress_messages_in_a_block_count: HistogramVec,
+    pub(super) received_ingress_messages_count: HistogramVec,
     pub(super) download_missing_ingress_messages_duration: Histogram,
     pub(super) missing_ingress_messages_bytes: Histogram,
     pub(super) total_block_assembly_duration: Histogram,
     pub(super) active_ingress_message_downloads: IntGauge,
     pub(super) total_ingress_message_download_errors: IntCounter,
+    pub(super) full_block_fallbacks: IntCounter,
+    pub(super) total_full_block_download_errors: IntCounter,
 }
 
 #[derive(Copy, Clone)]
@@ -38,6 +44,13 @@ impl FetchStrippedConsensusArtifactMetrics {
                     decimal_buckets_with_zero(0, 3),
                     &[SOURCE_LABEL],
             ),
+            received_ingress_messages_count: metrics_registry.histogram_vec(
+                    "ic_stripped_consensus_artifact_downloader_received_ingress_messages_count",
+                    "Number of ingress messages in a received stripped block partitioned by \
+                    whether the ingress message was sent in full or stripped",
+                    decimal_buckets_with_zero(0, 3),
+                    &[KIND_LABEL],
+            ),
             download_missing_ingress_messages_duration: metrics_registry.histogram(
                     "ic_stripped_consensus_artifact_downloader_missing_stripped_ingress_messages_fetch_duration",
                     "Download time for all the missing ingress messages in the block, in seconds",
@@ -63,6 +76,16 @@ impl FetchStrippedConsensusArtifactMetrics {
                     "The total number of errors occurred while downloading \
                     missing ingress messages",
             ),
+            full_block_fallbacks: metrics_registry.int_counter(
+                    "ic_stripped_consensus_artifact_full_block_fallbacks",
+                    "The total number of received stripped block proposals which were fetched \
+                    in full instead of fetching their stripped ingress messages",
+            ),
+            total_full_block_download_errors: metrics_registry.int_counter(
+                    "ic_stripped_consensus_artifact_total_full_block_download_errors",
+                    "The total number of errors occurred while downloading \
+                    full block proposals",
+            ),
         }
     }
 
@@ -71,6 +94,15 @@ impl FetchStrippedConsensusArtifactMetrics {
             .with_label_values(&[source.as_str()])
             .observe(count as f64)
     }
+
+    pub(super) fn report_received_ingress_messages_counts(&self, counts: StrippedIngressCounts) {
+        self.received_ingress_messages_count
+            .with_label_values(&["full"])
+            .observe(counts.full as f64);
+        self.received_ingress_messages_count
+            .with_label_values(&["stripped"])
+            .observe(counts.stripped as f64);
+    }
 }
 
 #[derive(Clone)]
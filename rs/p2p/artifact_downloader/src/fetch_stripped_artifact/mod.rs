@@ -7,4 +7,4 @@ mod types;
 #[cfg(test)]
 mod test_utils;
 
-pub use assembler::FetchStrippedConsensusArtifact;
+pub use assembler::{FetchStrippedConsensusArtifact, FullBlockFallbackPolicy};
@@ -1,10 +1,11 @@
 use ic_protobuf::types::v1 as pb;
 use ic_types::{
-    artifact::IdentifiableArtifact, batch::IngressPayload, consensus::ConsensusMessage,
+    artifact::IdentifiableArtifact, batch::IngressPayload, consensus::ConsensusMessage, CountBytes,
 };
 
 use super::types::stripped::{
-    MaybeStrippedConsensusMessage, StrippedBlockProposal, StrippedIngressPayload,
+    MaybeStrippedConsensusMessage, MaybeStrippedIngress, StrippedBlockProposal,
+    StrippedIngressPayload,
 };
 
 /// Provides functionality for stripping objects of given information.
@@ -13,14 +14,15 @@ use super::types::stripped::{
 pub(crate) trait Strippable {
     type Output;
 
-    /// Strips ingress messages from the object.
-    fn strip(self) -> Self::Output;
+    /// Strips ingress messages of at least `min_ingress_bytes_to_strip` bytes from the object.
+    /// Smaller ingress messages are kept inline.
+    fn strip(self, min_ingress_bytes_to_strip: usize) -> Self::Output;
 }
 
 impl Strippable for ConsensusMessage {
     type Output = MaybeStrippedConsensusMessage;
 
-    fn strip(self) -> Self::Output {
+    fn strip(self, min_ingress_bytes_to_strip: usize) -> Self::Output {
         let unstripped_consensus_message_id = self.id();
 
         match self {
@@ -37,7 +39,8 @@ impl Strippable for ConsensusMessage {
                 }
 
                 let data_payload = block_proposal.content.as_ref().payload.as_ref().as_data();
-                let stripped_ingress_payload = data_payload.batch.ingress.strip();
+                let stripped_ingress_payload =
+                    data_payload.batch.ingress.strip(min_ingress_bytes_to_strip);
 
                 MaybeStrippedConsensusMessage::StrippedBlockProposal(StrippedBlockProposal {
                     block_proposal_without_ingresses_proto: proto,
@@ -53,16 +56,43 @@ impl Strippable for ConsensusMessage {
 impl Strippable for &IngressPayload {
     type Output = StrippedIngressPayload;
 
-    fn strip(self) -> Self::Output {
-        Self::Output {
-            ingress_messages: self.message_ids(),
+    fn strip(self, min_ingress_bytes_to_strip: usize) -> Self::Output {
+        // Every ingress message is at least this large, so there is no need to deserialize them.
+        if min_ingress_bytes_to_strip == 0 {
+            return Self::Output {
+                ingress_messages: self
+                    .message_ids()
+                    .into_iter()
+                    .map(MaybeStrippedIngress::Stripped)
+                    .collect(),
+            };
         }
+
+        let ingress_messages = self
+            .message_ids()
+            .into_iter()
+            .enumerate()
+            .map(|(index, ingress_message_id)| match self.get(index) {
+                Ok((_, ingress_message))
+                    if ingress_message.count_bytes() < min_ingress_bytes_to_strip =>
+                {
+                    MaybeStrippedIngress::Full(ingress_message)
+                }
+                // If the ingress message can't be deserialized, we strip it and let the receiver
+                // deal with fetching it.
+                _ => MaybeStrippedIngress::Stripped(ingress_message_id),
+            })
+            .collect();
+
+        Self::Output { ingress_messages }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::fetch_stripped_artifact::test_utils::fake_summary_block_proposal;
+    use crate::fetch_stripped_artifact::test_utils::{
+        fake_ingress_message_with_arg_size, fake_summary_block_proposal,
+    };
 
     use super::*;
 
@@ -70,11 +100,45 @@ mod tests {
     fn summary_blocks_are_not_stripped_test() {
         let summary_block = fake_summary_block_proposal();
 
-        let stripped = summary_block.clone().strip();
+        let stripped = summary_block.clone().strip(0);
 
         assert_eq!(
             stripped,
             MaybeStrippedConsensusMessage::Unstripped(summary_block)
         );
     }
+
+    #[test]
+    fn only_ingress_messages_above_threshold_are_stripped_test() {
+        let (small_ingress, _small_ingress_id) = fake_ingress_message_with_arg_size("small", 16);
+        let (large_ingress, large_ingress_id) = fake_ingress_message_with_arg_size("large", 4096);
+        let ingress_payload = IngressPayload::from(vec![small_ingress.clone(), large_ingress]);
+
+        let stripped = ingress_payload.strip(1024);
+
+        assert_eq!(
+            stripped.ingress_messages,
+            vec![
+                MaybeStrippedIngress::Full(small_ingress),
+                MaybeStrippedIngress::Stripped(large_ingress_id),
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_threshold_strips_all_ingress_messages_test() {
+        let (small_ingress, small_ingress_id) = fake_ingress_message_with_arg_size("small", 16);
+        let (large_ingress, large_ingress_id) = fake_ingress_message_with_arg_size("large", 4096);
+        let ingress_payload = IngressPayload::from(vec![small_ingress, large_ingress]);
+
+        let stripped = ingress_payload.strip(0);
+
+        assert_eq!(
+            stripped.ingress_messages,
+            vec![
+                MaybeStrippedIngress::Stripped(small_ingress_id),
+                MaybeStrippedIngress::Stripped(large_ingress_id),
+            ]
+        );
+    }
 }
@@ -13,12 +13,12 @@ use ic_types::{
     },
     crypto::{CryptoHash, CryptoHashOf},
     messages::{Blob, HttpCallContent, HttpCanisterUpdate, HttpRequestEnvelope, SignedIngress},
-    time::expiry_time_from_now,
+    time::{expiry_time_from_now, Time},
     Height,
 };
 use ic_types_test_utils::ids::node_test_id;
 
-use super::types::stripped::{StrippedBlockProposal, StrippedIngressPayload};
+use super::types::stripped::{MaybeStrippedIngress, StrippedBlockProposal, StrippedIngressPayload};
 
 pub(crate) fn fake_ingress_message(method_name: &str) -> (SignedIngress, IngressMessageId) {
     fake_ingress_message_with_arg_size(method_name, 0)
@@ -28,7 +28,29 @@ pub(crate) fn fake_ingress_message_with_arg_size(
     method_name: &str,
     arg_size: usize,
 ) -> (SignedIngress, IngressMessageId) {
-    let ingress_expiry = expiry_time_from_now();
+    let ingress =
+        fake_ingress_message_impl(method_name, arg_size, expiry_time_from_now(), vec![1; 32]);
+    let ingress_id = IngressMessageId::from(&ingress);
+
+    (ingress, ingress_id)
+}
+
+/// Creates a fake ingress message with the given signature. The signature is not part of the
+/// [`IngressMessageId`], so messages which differ only in the signature have the same id.
+pub(crate) fn fake_ingress_message_with_signature(
+    method_name: &str,
+    ingress_expiry: Time,
+    sender_sig: Vec<u8>,
+) -> SignedIngress {
+    fake_ingress_message_impl(method_name, 0, ingress_expiry, sender_sig)
+}
+
+fn fake_ingress_message_impl(
+    method_name: &str,
+    arg_size: usize,
+    ingress_expiry: Time,
+    sender_sig: Vec<u8>,
+) -> SignedIngress {
     let content = HttpCallContent::Call {
         update: HttpCanisterUpdate {
             canister_id: Blob(vec![42; 8]),
@@ -39,17 +61,14 @@ pub(crate) fn fake_ingress_message_with_arg_size(
             ingress_expiry: ingress_expiry.as_nanos_since_unix_epoch(),
         },
     };
-    let ingress = HttpRequestEnvelope::<HttpCallContent> {
+    HttpRequestEnvelope::<HttpCallContent> {
         content,
         sender_pubkey: Some(Blob(vec![2; 32])),
-        sender_sig: Some(Blob(vec![1; 32])),
+        sender_sig: Some(Blob(sender_sig)),
         sender_delegation: None,
     }
     .try_into()
-    .unwrap();
-    let ingress_id = IngressMessageId::from(&ingress);
-
-    (ingress, ingress_id)
+    .unwrap()
 }
 
 pub(crate) fn fake_block_proposal_with_ingresses(
@@ -78,6 +97,17 @@ pub(crate) fn fake_block_proposal_with_ingresses(
 
 pub(crate) fn fake_stripped_block_proposal_with_ingresses(
     ingress_messages: Vec<IngressMessageId>,
+) -> StrippedBlockProposal {
+    fake_stripped_block_proposal_with_maybe_stripped_ingresses(
+        ingress_messages
+            .into_iter()
+            .map(MaybeStrippedIngress::Stripped)
+            .collect(),
+    )
+}
+
+pub(crate) fn fake_stripped_block_proposal_with_maybe_stripped_ingresses(
+    ingress_messages: Vec<MaybeStrippedIngress>,
 ) -> StrippedBlockProposal {
     StrippedBlockProposal {
         block_proposal_without_ingresses_proto: pb::BlockProposal::default(),
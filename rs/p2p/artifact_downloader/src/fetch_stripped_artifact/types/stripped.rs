@@ -5,15 +5,80 @@ use ic_protobuf::{
 use ic_types::{
     artifact::{ConsensusMessageId, IdentifiableArtifact, IngressMessageId, PbArtifact},
     consensus::ConsensusMessage,
+    messages::{SignedIngress, SignedRequestBytes},
 };
 
+use bytes::Bytes;
+use std::collections::HashSet;
+
+/// An ingress message of a [`StrippedIngressPayload`]. It is either sent in full or stripped, in
+/// which case it has to be fetched separately.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum MaybeStrippedIngress {
+    Full(SignedIngress),
+    Stripped(IngressMessageId),
+}
+
+impl MaybeStrippedIngress {
+    pub(crate) fn id(&self) -> IngressMessageId {
+        match self {
+            MaybeStrippedIngress::Full(ingress_message) => IngressMessageId::from(ingress_message),
+            MaybeStrippedIngress::Stripped(ingress_message_id) => ingress_message_id.clone(),
+        }
+    }
+}
+
 /// Stripped version of the [`IngressPayload`].
 #[derive(Clone, Debug, Default, PartialEq)]
 pub(crate) struct StrippedIngressPayload {
-    pub(crate) ingress_messages: Vec<IngressMessageId>,
+    /// The ingress messages in the same order as in the original [`IngressPayload`]. The order
+    /// is covered by the hash of the block proposal, so it must be kept on reconstruction.
+    pub(crate) ingress_messages: Vec<MaybeStrippedIngress>,
+}
+
+/// Number of ingress messages of a [`StrippedIngressPayload`] which were sent in full and which
+/// were stripped.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub(crate) struct StrippedIngressCounts {
+    pub(crate) full: usize,
+    pub(crate) stripped: usize,
+}
+
+impl StrippedIngressPayload {
+    /// Returns the [`IngressMessageId`]s of the ingress messages which have been stripped.
+    pub(crate) fn stripped_ingress_messages(&self) -> Vec<IngressMessageId> {
+        self.ingress_messages
+            .iter()
+            .filter_map(|ingress_message| match ingress_message {
+                MaybeStrippedIngress::Full(_) => None,
+                MaybeStrippedIngress::Stripped(ingress_message_id) => {
+                    Some(ingress_message_id.clone())
+                }
+            })
+            .collect()
+    }
+
+    /// Counts the ingress messages which were sent in full and which were stripped.
+    pub(crate) fn counts(&self) -> StrippedIngressCounts {
+        self.ingress_messages.iter().fold(
+            StrippedIngressCounts::default(),
+            |mut counts, ingress_message| {
+                match ingress_message {
+                    MaybeStrippedIngress::Full(_) => counts.full += 1,
+                    MaybeStrippedIngress::Stripped(_) => counts.stripped += 1,
+                }
+                counts
+            },
+        )
+    }
 }
 
 /// Stripped version of the [`BlockProposal`].
+///
+/// Only the ingress payload is stripped: every ingress message can be refetched, either from the
+/// local ingress pool or from the peers' ingress/consensus pools, by its [`IngressMessageId`].
+/// Other parts of the data payload, such as the XNet payload, have no per-entry identifier nor a
+/// pool on the receiving side to be refetched from, so they are sent inline.
 #[derive(Clone, Debug, PartialEq)]
 pub struct StrippedBlockProposal {
     pub(crate) block_proposal_without_ingresses_proto: pb::BlockProposal,
@@ -72,18 +137,7 @@ impl TryFrom<pb::StrippedBlockProposal> for StrippedBlockProposal {
 
         Ok(Self {
             block_proposal_without_ingresses_proto,
-            stripped_ingress_payload: StrippedIngressPayload {
-                ingress_messages: value
-                    .ingress_messages
-                    .into_iter()
-                    .map(|stripped_ingress| {
-                        try_from_option_field(
-                            stripped_ingress.stripped,
-                            "StrippedIngressMessage::stripped",
-                        )
-                    })
-                    .collect::<Result<Vec<_>, _>>()?,
-            },
+            stripped_ingress_payload: StrippedIngressPayload::try_from(value.ingress_messages)?,
             unstripped_consensus_message_id: try_from_option_field(
                 value.unstripped_consensus_message_id,
                 "unstripped_consensus_message_id",
@@ -102,15 +156,109 @@ impl From<StrippedBlockProposal> for pb::StrippedBlockProposal {
                 .stripped_ingress_payload
                 .ingress_messages
                 .into_iter()
-                .map(|ingress_id| pb::StrippedIngressMessage {
-                    stripped: Some(ingress_id.into()),
-                })
+                .map(pb::StrippedIngressMessage::from)
                 .collect(),
             unstripped_consensus_message_id: Some(value.unstripped_consensus_message_id.into()),
         }
     }
 }
 
+impl From<&StrippedBlockProposal> for pb::StrippedBlockProposal {
+    fn from(value: &StrippedBlockProposal) -> Self {
+        Self {
+            block_proposal_without_ingress_payload: Some(
+                value.block_proposal_without_ingresses_proto.clone(),
+            ),
+            ingress_messages: value
+                .stripped_ingress_payload
+                .ingress_messages
+                .iter()
+                .map(pb::StrippedIngressMessage::from)
+                .collect(),
+            unstripped_consensus_message_id: Some(
+                value.unstripped_consensus_message_id.clone().into(),
+            ),
+        }
+    }
+}
+
+impl TryFrom<Vec<pb::StrippedIngressMessage>> for StrippedIngressPayload {
+    type Error = ProxyDecodeError;
+
+    /// Fails if any ingress message id appears more than once, since a valid ingress payload
+    /// never contains the same ingress message twice.
+    fn try_from(value: Vec<pb::StrippedIngressMessage>) -> Result<Self, Self::Error> {
+        let ingress_messages = value
+            .into_iter()
+            .map(MaybeStrippedIngress::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut ingress_message_ids = HashSet::with_capacity(ingress_messages.len());
+        for ingress_message in &ingress_messages {
+            let ingress_message_id = ingress_message.id();
+            if !ingress_message_ids.insert(ingress_message_id.clone()) {
+                return Err(ProxyDecodeError::Other(format!(
+                    "Duplicate ingress message id {ingress_message_id} in the stripped ingress payload"
+                )));
+            }
+        }
+
+        Ok(Self { ingress_messages })
+    }
+}
+
+impl TryFrom<pb::StrippedIngressMessage> for MaybeStrippedIngress {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::StrippedIngressMessage) -> Result<Self, Self::Error> {
+        use pb::stripped_ingress_message::Msg;
+        let Some(msg) = value.msg else {
+            return Err(ProxyDecodeError::MissingField(
+                "StrippedIngressMessage::msg",
+            ));
+        };
+
+        Ok(match msg {
+            Msg::Stripped(ingress_message_id) => {
+                MaybeStrippedIngress::Stripped(ingress_message_id.try_into()?)
+            }
+            Msg::Full(ingress_message) => {
+                MaybeStrippedIngress::Full(SignedIngress::try_from(Bytes::from(ingress_message))?)
+            }
+        })
+    }
+}
+
+impl From<MaybeStrippedIngress> for pb::StrippedIngressMessage {
+    fn from(value: MaybeStrippedIngress) -> Self {
+        let msg = match value {
+            MaybeStrippedIngress::Full(ingress_message) => pb::stripped_ingress_message::Msg::Full(
+                SignedRequestBytes::from(ingress_message).into(),
+            ),
+            MaybeStrippedIngress::Stripped(ingress_message_id) => {
+                pb::stripped_ingress_message::Msg::Stripped(ingress_message_id.into())
+            }
+        };
+
+        Self { msg: Some(msg) }
+    }
+}
+
+impl From<&MaybeStrippedIngress> for pb::StrippedIngressMessage {
+    fn from(value: &MaybeStrippedIngress) -> Self {
+        let msg = match value {
+            MaybeStrippedIngress::Full(ingress_message) => {
+                pb::stripped_ingress_message::Msg::Full(ingress_message.binary().clone().into())
+            }
+            MaybeStrippedIngress::Stripped(ingress_message_id) => {
+                pb::stripped_ingress_message::Msg::Stripped(ingress_message_id.clone().into())
+            }
+        };
+
+        Self { msg: Some(msg) }
+    }
+}
+
 impl From<MaybeStrippedConsensusMessage> for pb::StrippedConsensusMessage {
     fn from(value: MaybeStrippedConsensusMessage) -> Self {
         let msg = match value {
@@ -126,6 +274,21 @@ impl From<MaybeStrippedConsensusMessage> for pb::StrippedConsensusMessage {
     }
 }
 
+impl From<&MaybeStrippedConsensusMessage> for pb::StrippedConsensusMessage {
+    fn from(value: &MaybeStrippedConsensusMessage) -> Self {
+        let msg = match value {
+            MaybeStrippedConsensusMessage::Unstripped(unstripped) => {
+                pb::stripped_consensus_message::Msg::Unstripped(unstripped.clone().into())
+            }
+            MaybeStrippedConsensusMessage::StrippedBlockProposal(block_proposal) => {
+                pb::stripped_consensus_message::Msg::StrippedBlockProposal(block_proposal.into())
+            }
+        };
+
+        Self { msg: Some(msg) }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Hash)]
 pub struct StrippedConsensusMessageId(ConsensusMessageId);
 
@@ -187,6 +350,7 @@ impl PbArtifact for MaybeStrippedConsensusMessage {
 mod tests {
     use crate::fetch_stripped_artifact::test_utils::{
         fake_ingress_message, fake_stripped_block_proposal_with_ingresses,
+        fake_stripped_block_proposal_with_maybe_stripped_ingresses, fake_summary_block_proposal,
     };
 
     use super::*;
@@ -206,4 +370,117 @@ mod tests {
 
         assert_eq!(consensus_message, original_consensus_message);
     }
+
+    #[test]
+    fn serialize_deserialize_stripped_block_proposal_with_full_ingress_test() {
+        let (ingress_1, _ingress_1_id) = fake_ingress_message("fake_1");
+        let (_ingress_2, ingress_2_id) = fake_ingress_message("fake_2");
+        let stripped_block_proposal =
+            fake_stripped_block_proposal_with_maybe_stripped_ingresses(vec![
+                MaybeStrippedIngress::Full(ingress_1),
+                MaybeStrippedIngress::Stripped(ingress_2_id),
+            ]);
+        let original_consensus_message =
+            MaybeStrippedConsensusMessage::StrippedBlockProposal(stripped_block_proposal);
+
+        let proto = pb::StrippedConsensusMessage::from(original_consensus_message.clone());
+        let consensus_message = MaybeStrippedConsensusMessage::try_from(proto)
+            .expect("Should deserialize a valid proto");
+
+        assert_eq!(consensus_message, original_consensus_message);
+    }
+
+    #[test]
+    fn serialize_deserialize_unstripped_consensus_message_test() {
+        let original_consensus_message =
+            MaybeStrippedConsensusMessage::Unstripped(fake_summary_block_proposal());
+
+        let proto = pb::StrippedConsensusMessage::from(&original_consensus_message);
+        let consensus_message = MaybeStrippedConsensusMessage::try_from(proto)
+            .expect("Should deserialize a valid proto");
+
+        assert_eq!(consensus_message, original_consensus_message);
+    }
+
+    #[test]
+    fn serialize_by_reference_matches_serialize_by_value_test() {
+        let (ingress_1, _ingress_1_id) = fake_ingress_message("fake_1");
+        let (_ingress_2, ingress_2_id) = fake_ingress_message("fake_2");
+        let stripped_block_proposal =
+            fake_stripped_block_proposal_with_maybe_stripped_ingresses(vec![
+                MaybeStrippedIngress::Full(ingress_1),
+                MaybeStrippedIngress::Stripped(ingress_2_id),
+            ]);
+        let consensus_message =
+            MaybeStrippedConsensusMessage::StrippedBlockProposal(stripped_block_proposal);
+
+        assert_eq!(
+            pb::StrippedConsensusMessage::from(&consensus_message),
+            pb::StrippedConsensusMessage::from(consensus_message)
+        );
+    }
+
+    #[test]
+    fn deserialize_stripped_block_proposal_with_duplicate_ingress_ids_fails_test() {
+        let (_ingress_1, ingress_1_id) = fake_ingress_message("fake_1");
+        let (_ingress_2, ingress_2_id) = fake_ingress_message("fake_2");
+        let stripped_block_proposal = fake_stripped_block_proposal_with_ingresses(vec![
+            ingress_1_id.clone(),
+            ingress_2_id,
+            ingress_1_id,
+        ]);
+
+        let proto = pb::StrippedBlockProposal::from(stripped_block_proposal);
+
+        assert!(matches!(
+            StrippedBlockProposal::try_from(proto),
+            Err(ProxyDecodeError::Other(_))
+        ));
+    }
+
+    #[test]
+    fn deserialized_stripped_ingress_counts_test() {
+        let (ingress_1, _ingress_1_id) = fake_ingress_message("fake_1");
+        let (ingress_2, _ingress_2_id) = fake_ingress_message("fake_2");
+        let (_ingress_3, ingress_3_id) = fake_ingress_message("fake_3");
+        let proto = pb::StrippedBlockProposal::from(
+            fake_stripped_block_proposal_with_maybe_stripped_ingresses(vec![
+                MaybeStrippedIngress::Full(ingress_1),
+                MaybeStrippedIngress::Stripped(ingress_3_id.clone()),
+                MaybeStrippedIngress::Full(ingress_2),
+            ]),
+        );
+
+        let stripped_block_proposal =
+            StrippedBlockProposal::try_from(proto).expect("Should deserialize a valid proto");
+
+        assert_eq!(
+            stripped_block_proposal.stripped_ingress_payload.counts(),
+            StrippedIngressCounts {
+                full: 2,
+                stripped: 1
+            }
+        );
+        assert_eq!(
+            stripped_block_proposal
+                .stripped_ingress_payload
+                .stripped_ingress_messages(),
+            vec![ingress_3_id]
+        );
+    }
+
+    #[test]
+    fn deserialized_empty_stripped_ingress_counts_test() {
+        let proto = pb::StrippedBlockProposal::from(
+            fake_stripped_block_proposal_with_maybe_stripped_ingresses(vec![]),
+        );
+
+        let stripped_block_proposal =
+            StrippedBlockProposal::try_from(proto).expect("Should deserialize a valid proto");
+
+        assert_eq!(
+            stripped_block_proposal.stripped_ingress_payload.counts(),
+            StrippedIngressCounts::default()
+        );
+    }
 }
@@ -2,4 +2,4 @@ mod fetch_artifact;
 mod fetch_stripped_artifact;
 
 pub use fetch_artifact::FetchArtifact;
-pub use fetch_stripped_artifact::FetchStrippedConsensusArtifact;
+pub use fetch_stripped_artifact::{FetchStrippedConsensusArtifact, FullBlockFallbackPolicy};
@@ -152,6 +152,10 @@ pub struct MessagePool {
     /// IDs.
     #[prost(uint64, tag = "3")]
     pub message_id_generator: u64,
+    /// Lifetime of outbound guaranteed response call requests, in seconds. Only
+    /// set if different from the default.
+    #[prost(uint64, optional, tag = "4")]
+    pub request_lifetime_seconds: ::core::option::Option<u64>,
 }
 /// Nested message and enum types in `MessagePool`.
 pub mod message_pool {
@@ -229,6 +233,10 @@ pub struct CanisterQueues {
         ::prost::alloc::vec::Vec<super::super::super::types::v1::CanisterId>,
     #[prost(uint64, tag = "11")]
     pub guaranteed_response_memory_reservations: u64,
+    /// If set, responses are not deduplicated (and no set of callbacks with
+    /// enqueued responses is maintained).
+    #[prost(bool, tag = "14")]
+    pub response_deduplication_disabled: bool,
 }
 /// Nested message and enum types in `CanisterQueues`.
 pub mod canister_queues {
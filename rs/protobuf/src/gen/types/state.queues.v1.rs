@@ -229,6 +229,10 @@ pub struct CanisterQueues {
         ::prost::alloc::vec::Vec<super::super::super::types::v1::CanisterId>,
     #[prost(uint64, tag = "11")]
     pub guaranteed_response_memory_reservations: u64,
+    /// If set, responses are not deduplicated (and no set of callbacks with
+    /// enqueued responses is maintained).
+    #[prost(bool, tag = "14")]
+    pub response_deduplication_disabled: bool,
 }
 /// Nested message and enum types in `CanisterQueues`.
 pub mod canister_queues {
@@ -1370,8 +1370,18 @@ pub struct StrippedBlockProposal {
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct StrippedIngressMessage {
-    #[prost(message, optional, tag = "1")]
-    pub stripped: ::core::option::Option<IngressMessageId>,
+    #[prost(oneof = "stripped_ingress_message::Msg", tags = "1, 2")]
+    pub msg: ::core::option::Option<stripped_ingress_message::Msg>,
+}
+/// Nested message and enum types in `StrippedIngressMessage`.
+pub mod stripped_ingress_message {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Msg {
+        #[prost(message, tag = "1")]
+        Stripped(super::IngressMessageId),
+        #[prost(bytes, tag = "2")]
+        Full(::prost::alloc::vec::Vec<u8>),
+    }
 }
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -23,7 +23,10 @@ use ic_types::{LongExecutionMode, NumInstructions};
 use ic_validate_eq::ValidateEq;
 use ic_validate_eq_derive::ValidateEq;
 use phantom_newtype::AmountOf;
-pub use queues::{CanisterQueues, DEFAULT_QUEUE_CAPACITY};
+pub use queues::{
+    CanisterQueues, InputScheduleSplitSummary, MemoryBreakdown, MessageClass, MessageContext,
+    MessageDescriptor, MessageKind, DEFAULT_QUEUE_CAPACITY,
+};
 use std::collections::BTreeSet;
 use std::convert::From;
 use std::sync::Arc;
@@ -195,6 +198,17 @@ impl CanisterState {
         )
     }
 
+    /// See `SystemState::push_input_batch` for documentation.
+    pub fn push_input_batch(
+        &mut self,
+        msgs: Vec<(RequestOrResponse, InputQueueType)>,
+        subnet_available_memory: &mut i64,
+        own_subnet_type: SubnetType,
+    ) -> Result<(), (StateError, Vec<RequestOrResponse>)> {
+        self.system_state
+            .push_input_batch(msgs, subnet_available_memory, own_subnet_type)
+    }
+
     /// See `SystemState::pop_input` for documentation.
     ///
     /// The function is public as we pop directly from the Canister state in
@@ -6,6 +6,9 @@ mod tests;
 
 pub use self::input_schedule::CanisterQueuesLoopDetector;
 use self::input_schedule::InputSchedule;
+pub use self::message_pool::{
+    Class as MessageClass, Context as MessageContext, Kind as MessageKind, MessageDescriptor,
+};
 use self::message_pool::{
     Context, InboundReference, Kind, MessagePool, OutboundReference, SomeReference,
 };
@@ -31,6 +34,7 @@ use prost::Message;
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::convert::{From, TryFrom};
 use std::sync::Arc;
+use std::time::Duration;
 use strum::EnumCount;
 
 pub const DEFAULT_QUEUE_CAPACITY: usize = 500;
@@ -106,7 +110,8 @@ pub const DEFAULT_QUEUE_CAPACITY: usize = 500;
 ///    attempted implementation.
 ///
 ///  * `callbacks_with_enqueued_response` contains the precise set of
-///    `CallbackIds` of all inbound responses and compact responses.
+///    `CallbackIds` of all inbound responses and compact responses (or is empty,
+///    if response deduplication is disabled).
 ///
 /// # Soft invariants
 ///
@@ -165,6 +170,10 @@ pub struct CanisterQueues {
     /// Used for response deduplication (whether due to a locally generated reject
     /// response to a best-effort call; or due to a malicious / buggy subnet).
     callbacks_with_enqueued_response: BTreeSet<CallbackId>,
+
+    /// If set, `callbacks_with_enqueued_response` is not maintained and responses
+    /// are not deduplicated. See `with_response_deduplication()`.
+    response_deduplication_disabled: bool,
 }
 
 /// Circular iterator that consumes output queue messages: loops over output
@@ -304,6 +313,47 @@ impl Iterator for CanisterOutputQueuesIterator<'_> {
     }
 }
 
+/// Breakdown of the memory used by canister queues and their messages, as
+/// returned by `CanisterQueues::memory_usage_breakdown()`.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct MemoryBreakdown {
+    /// Total byte size of best-effort messages.
+    pub best_effort_bytes: usize,
+
+    /// Total byte size of guaranteed responses in input and output queues.
+    pub guaranteed_response_bytes: usize,
+
+    /// Memory reserved for guaranteed responses (`MAX_RESPONSE_COUNT_BYTES` per
+    /// outstanding guaranteed response call).
+    pub guaranteed_reservations_bytes: usize,
+
+    /// Sum total of bytes above `MAX_RESPONSE_COUNT_BYTES` per oversized
+    /// guaranteed response call request.
+    pub oversized_request_extra_bytes: usize,
+
+    /// Total byte size of guaranteed responses already routed into streams and
+    /// not yet garbage collected.
+    pub stream_guaranteed_response_bytes: usize,
+
+    /// Count of slots reserved across input and output queues (not a byte size,
+    /// but a measure of backpressure).
+    pub reserved_slots: usize,
+}
+
+/// Summary of how `CanisterQueues::split_input_schedules()` re-partitioned the
+/// local and remote input schedules.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub struct InputScheduleSplitSummary {
+    /// Number of senders moved from the remote to the local sender schedule.
+    pub moved_to_local: usize,
+
+    /// Number of senders moved from the local to the remote sender schedule.
+    pub moved_to_remote: usize,
+
+    /// Number of senders that remained in the same schedule.
+    pub unchanged: usize,
+}
+
 /// Kinds of canister inputs returned by `CanisterQueues::pop_input()` /
 /// `CanisterQueues::peek_input()`: in addition to the regular ingress messages
 /// and canister requests / responses, `pop_input()` / `peek_input()` may also
@@ -624,6 +674,30 @@ impl InboundMessageStore for MessageStoreImpl {
 }
 
 impl CanisterQueues {
+    /// Creates an empty `CanisterQueues`, with response deduplication enabled or
+    /// disabled. `CanisterQueues::default()` deduplicates responses.
+    ///
+    /// Without response deduplication, `callbacks_with_enqueued_response` is not
+    /// maintained, saving its upkeep on every response push and pop. This is
+    /// only meant for measuring that overhead, e.g. on subnets with best-effort
+    /// traffic only: duplicate responses are then enqueued like any other,
+    /// guaranteed response duplicates are no longer rejected, "deadline expired"
+    /// compact responses may be enqueued for callbacks that already have a
+    /// response.
+    ///
+    /// The setting is persisted, so it survives checkpoints and state sync.
+    pub fn with_response_deduplication(enabled: bool) -> Self {
+        Self {
+            response_deduplication_disabled: !enabled,
+            ..Default::default()
+        }
+    }
+
+    /// Returns `true` if `callbacks_with_enqueued_response` is maintained.
+    fn deduplicates_responses(&self) -> bool {
+        !self.response_deduplication_disabled
+    }
+
     /// Pushes an ingress message into the induction pool.
     pub fn push_ingress(&mut self, msg: Ingress) {
         self.ingress_queue.push(msg)
@@ -684,6 +758,22 @@ impl CanisterQueues {
         CanisterOutputQueuesIterator::new(&mut self.canister_queues, &mut self.store)
     }
 
+    /// Returns an iterator over all messages in output queues, together with
+    /// their destinations, without consuming them. Queues are visited in
+    /// destination order and stale references are skipped.
+    pub fn iter_output_messages(&self) -> impl Iterator<Item = (&CanisterId, &RequestOrResponse)> {
+        self.canister_queues
+            .iter()
+            .flat_map(move |(canister_id, (_, output_queue))| {
+                output_queue.iter().filter_map(move |reference| {
+                    self.store
+                        .pool
+                        .get(*reference)
+                        .map(|msg| (canister_id, msg))
+                })
+            })
+    }
+
     /// See `IngressQueue::filter_messages()` for documentation.
     pub fn filter_ingress_messages<F>(&mut self, filter: F) -> Vec<Arc<Ingress>>
     where
@@ -692,6 +782,13 @@ impl CanisterQueues {
         self.ingress_queue.filter_messages(filter)
     }
 
+    /// Drops all ingress messages sent by `source`.
+    ///
+    /// Returns the dropped ingress messages.
+    pub fn clear_ingress_from(&mut self, source: PrincipalId) -> Vec<Arc<Ingress>> {
+        self.filter_ingress_messages(|ingress| ingress.source.get() != source)
+    }
+
     /// Enqueues a canister-to-canister message into the induction pool.
     ///
     /// If the message is a `Request` and is enqueued successfully, this will also
@@ -746,9 +843,10 @@ impl CanisterQueues {
                 match self.canister_queues.get_mut(&sender) {
                     Some((queue, _)) if queue.check_has_reserved_response_slot().is_ok() => {
                         // Check against duplicate responses.
-                        if !self
-                            .callbacks_with_enqueued_response
-                            .insert(response.originator_reply_callback)
+                        if self.deduplicates_responses()
+                            && !self
+                                .callbacks_with_enqueued_response
+                                .insert(response.originator_reply_callback)
                         {
                             debug_assert_eq!(Ok(()), self.test_invariants());
                             if response.deadline == NO_DEADLINE {
@@ -782,9 +880,12 @@ impl CanisterQueues {
                         } else {
                             // This must be a duplicate best-effort response (since `SystemState` has
                             // aleady checked for a matching callback). Silently drop it.
-                            debug_assert!(self
-                                .callbacks_with_enqueued_response
-                                .contains(&response.originator_reply_callback));
+                            debug_assert!(
+                                !self.deduplicates_responses()
+                                    || self
+                                        .callbacks_with_enqueued_response
+                                        .contains(&response.originator_reply_callback)
+                            );
                             return Ok(());
                         }
                     }
@@ -810,6 +911,110 @@ impl CanisterQueues {
         Ok(())
     }
 
+    /// Pushes a batch of canister-to-canister messages into the induction pool,
+    /// atomically: either all messages are handled as if by `push_input()` (in
+    /// order), or none are and the queues are left unmodified.
+    ///
+    /// All required input and output queue slots and response slot reservations
+    /// are checked upfront, taking into account the earlier messages in the
+    /// batch, before anything is pushed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `StateError` that `push_input()` would have produced for the
+    /// first message that could not be pushed, along with all the messages in the
+    /// batch.
+    pub(super) fn push_input_batch(
+        &mut self,
+        msgs: Vec<(RequestOrResponse, InputQueueType)>,
+    ) -> Result<(), (StateError, Vec<RequestOrResponse>)> {
+        if let Err(e) = self.check_push_input_batch(&msgs) {
+            return Err((e, msgs.into_iter().map(|(msg, _)| msg).collect()));
+        }
+
+        for (msg, input_queue_type) in msgs {
+            // Guaranteed to succeed, as per the checks above.
+            self.push_input(msg, input_queue_type)
+                .map_err(|(e, _)| e)
+                .expect("Failed to push a pre-checked input message");
+        }
+        Ok(())
+    }
+
+    /// Checks that `push_input()` would succeed for every message in the batch,
+    /// if called in order. Does not mutate the queues.
+    fn check_push_input_batch(
+        &self,
+        msgs: &[(RequestOrResponse, InputQueueType)],
+    ) -> Result<(), StateError> {
+        // Slots consumed by earlier messages in the batch, per sender: requests
+        // pushed and responses pushed.
+        let mut used_slots: BTreeMap<CanisterId, (usize, usize)> = BTreeMap::new();
+        let mut callbacks = BTreeSet::new();
+
+        for (msg, _) in msgs {
+            let sender = msg.sender();
+            let queues = self.canister_queues.get(&sender);
+            let (requests, responses) = used_slots.entry(sender).or_default();
+
+            match msg {
+                RequestOrResponse::Request(_) => {
+                    // Each request needs a slot in the input queue and a reserved response
+                    // slot in the output queue.
+                    if let Some((input_queue, output_queue)) = queues {
+                        for (available_slots, capacity) in [
+                            (
+                                input_queue.available_request_slots(),
+                                input_queue.capacity(),
+                            ),
+                            (
+                                output_queue.available_response_slots(),
+                                output_queue.capacity(),
+                            ),
+                        ] {
+                            if *requests >= available_slots {
+                                return Err(StateError::QueueFull { capacity });
+                            }
+                        }
+                    } else if *requests >= DEFAULT_QUEUE_CAPACITY {
+                        return Err(StateError::QueueFull {
+                            capacity: DEFAULT_QUEUE_CAPACITY,
+                        });
+                    }
+                    *requests += 1;
+                }
+
+                RequestOrResponse::Response(response) => {
+                    let reserved_slots = queues
+                        .map(|(input_queue, _)| input_queue.reserved_slots())
+                        .unwrap_or(0);
+                    let callback_id = response.originator_reply_callback;
+                    let duplicate = self.deduplicates_responses()
+                        && (self.callbacks_with_enqueued_response.contains(&callback_id)
+                            || callbacks.contains(&callback_id));
+
+                    if *responses < reserved_slots && !duplicate {
+                        *responses += 1;
+                        callbacks.insert(callback_id);
+                    } else if response.deadline == NO_DEADLINE {
+                        // Best-effort responses would be silently dropped, but guaranteed
+                        // responses are an error.
+                        return Err(StateError::non_matching_response(
+                            if *responses < reserved_slots {
+                                "Duplicate response"
+                            } else {
+                                "No reserved response slot"
+                            },
+                            response,
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Enqueues a "deadline expired" compact response for the given callback, iff a
     /// response for the callback is not already enqueued.
     ///
@@ -839,7 +1044,9 @@ impl CanisterQueues {
         };
 
         // Check against duplicate responses.
-        if !self.callbacks_with_enqueued_response.insert(callback_id) {
+        if self.deduplicates_responses()
+            && !self.callbacks_with_enqueued_response.insert(callback_id)
+        {
             // There is already a response enqueued for the callback.
             return Ok(false);
         }
@@ -906,7 +1113,10 @@ impl CanisterQueues {
 
             if let Some(msg_) = &msg {
                 if let Some(callback_id) = msg_.response_callback_id() {
-                    assert!(self.callbacks_with_enqueued_response.remove(&callback_id));
+                    assert!(
+                        !self.deduplicates_responses()
+                            || self.callbacks_with_enqueued_response.remove(&callback_id)
+                    );
                 }
                 debug_assert_eq!(Ok(()), self.test_invariants());
                 debug_assert_eq!(Ok(()), self.schedules_ok(&|_| InputQueueType::RemoteSubnet));
@@ -1095,16 +1305,23 @@ impl CanisterQueues {
     /// or if the canister directly includes subnet IDs in the request.
     /// Hence, an immediate (reject) `Response` is added to the relevant
     /// input queue.
+    ///
+    /// The reject message is truncated to `MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN`,
+    /// same as for any other synthetic reject response.
+    ///
+    /// Returns `StateError::InvalidSubnetRejection` if the receiver is neither
+    /// `IC_00` nor one of `subnet_ids`.
     pub(crate) fn reject_subnet_output_request(
         &mut self,
         request: Request,
         reject_context: RejectContext,
         subnet_ids: &[PrincipalId],
     ) -> Result<(), StateError> {
-        assert!(
-            request.receiver == IC_00 || subnet_ids.contains(&request.receiver.get()),
-            "reject_subnet_output_request can only be used to reject management canister requests"
-        );
+        if request.receiver != IC_00 && !subnet_ids.contains(&request.receiver.get()) {
+            return Err(StateError::InvalidSubnetRejection {
+                receiver: request.receiver,
+            });
+        }
 
         let (input_queue, _output_queue) =
             get_or_insert_queues(&mut self.canister_queues, &request.receiver);
@@ -1118,7 +1335,11 @@ impl CanisterQueues {
             respondent: request.receiver,
             originator_reply_callback: request.sender_reply_callback,
             refund: request.payment,
-            response_payload: Payload::Reject(reject_context),
+            response_payload: Payload::Reject(RejectContext::new_with_message_length_limit(
+                reject_context.code(),
+                reject_context.message(),
+                MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN,
+            )),
             deadline: request.deadline,
         }));
         self.push_input(response, InputQueueType::LocalSubnet)
@@ -1128,6 +1349,10 @@ impl CanisterQueues {
     /// Returns the number of output requests that can be pushed to each
     /// canister before either the respective input or output queue is full.
     ///
+    /// Only canisters with an existing queue pair are included. Up to
+    /// `DEFAULT_QUEUE_CAPACITY` requests can be pushed to any other canister (see
+    /// `available_output_request_slots_for()`).
+    ///
     /// Time complexity: `O(n)`.
     pub fn available_output_request_slots(&self) -> BTreeMap<CanisterId, usize> {
         // When pushing a request we need to reserve a slot on the input
@@ -1146,6 +1371,25 @@ impl CanisterQueues {
             .collect()
     }
 
+    /// Returns the number of output requests that can be pushed to the given
+    /// canister before either the respective input or output queue is full.
+    ///
+    /// Unlike `available_output_request_slots()`, which only covers canisters that
+    /// already have a queue pair, this returns `DEFAULT_QUEUE_CAPACITY` for a
+    /// canister with no queue pair (since one would be created on push).
+    ///
+    /// Time complexity: `O(log(n))`.
+    pub fn available_output_request_slots_for(&self, canister_id: &CanisterId) -> usize {
+        self.canister_queues.get(canister_id).map_or(
+            DEFAULT_QUEUE_CAPACITY,
+            |(input_queue, output_queue)| {
+                input_queue
+                    .available_response_slots()
+                    .min(output_queue.available_request_slots())
+            },
+        )
+    }
+
     /// Pushes a `Response` into the relevant output queue. The protocol should have
     /// already reserved a slot, so this cannot fail.
     ///
@@ -1202,6 +1446,39 @@ impl CanisterQueues {
         Ok(())
     }
 
+    /// Tests whether `induct_message_to_self()` would succeed, without mutating
+    /// anything: i.e. whether there is a message in the output queue to
+    /// `own_canister_id` and it can be pushed into the matching input queue.
+    ///
+    /// Does not check for available memory, that is up to the caller.
+    pub fn can_induct_message_to_self(&self, own_canister_id: CanisterId) -> bool {
+        let Some(msg) = self.peek_output(&own_canister_id) else {
+            return false;
+        };
+        let (input_queue, output_queue) = self
+            .canister_queues
+            .get(&own_canister_id)
+            .expect("Output queue existed above so lookup should not fail.");
+
+        match msg {
+            RequestOrResponse::Request(_) => {
+                input_queue.check_has_request_slot().is_ok()
+                    && output_queue.available_response_slots() > 0
+            }
+
+            RequestOrResponse::Response(response) => {
+                // Best-effort responses that cannot be enqueued (duplicates) are silently
+                // dropped by `push_input()`, which then succeeds.
+                response.deadline != NO_DEADLINE
+                    || (input_queue.check_has_reserved_response_slot().is_ok()
+                        && !(self.deduplicates_responses()
+                            && self
+                                .callbacks_with_enqueued_response
+                                .contains(&response.originator_reply_callback)))
+            }
+        }
+    }
+
     /// Returns a reference to the pool's message stats.
     fn message_stats(&self) -> &message_pool::MessageStats {
         self.store.pool.message_stats()
@@ -1232,6 +1509,43 @@ impl CanisterQueues {
         self.queue_stats.input_queues_reserved_slots
     }
 
+    /// Returns the number of slots reserved in the input and output queues to /
+    /// from the given canister, as an `(input_reserved, output_reserved)` tuple;
+    /// or `(0, 0)` if there is no such queue pair.
+    ///
+    /// Time complexity: `O(log(n))`.
+    pub fn reserved_slots_for(&self, canister_id: &CanisterId) -> (usize, usize) {
+        self.canister_queues
+            .get(canister_id)
+            .map(|(input_queue, output_queue)| {
+                (input_queue.reserved_slots(), output_queue.reserved_slots())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of non-stale messages in the input and output queues
+    /// to / from each canister, as `(input_len, output_len)` tuples. Only
+    /// canisters with an existing queue pair are included.
+    ///
+    /// Time complexity: `O(n)` in the total number of enqueued references
+    /// (including stale ones), so this is not meant to be called on a hot path.
+    pub fn queue_depths(&self) -> BTreeMap<CanisterId, (usize, usize)> {
+        self.canister_queues
+            .iter()
+            .map(|(canister_id, (input_queue, output_queue))| {
+                let input_len = input_queue
+                    .iter()
+                    .filter(|reference| !self.store.is_stale(**reference))
+                    .count();
+                let output_len = output_queue
+                    .iter()
+                    .filter(|reference| !self.store.is_stale(**reference))
+                    .count();
+                (*canister_id, (input_len, output_len))
+            })
+            .collect()
+    }
+
     /// Returns the total byte size of canister input queues (queues + messages).
     ///
     /// Does not account for callback references for expired callbacks or dropped
@@ -1259,6 +1573,51 @@ impl CanisterQueues {
         self.message_stats().outbound_message_count
     }
 
+    /// Returns the number of guaranteed response requests in output queues. Each
+    /// of these holds a guaranteed response memory reservation for its response.
+    pub fn output_queues_guaranteed_request_count(&self) -> usize {
+        self.store.pool.outbound_guaranteed_request_count()
+    }
+
+    /// Returns the total number of enqueued ingress messages plus non-stale
+    /// canister messages in input and output queues. Reserved slots are not
+    /// counted.
+    pub fn total_message_count(&self) -> usize {
+        self.ingress_queue_message_count()
+            + self.input_queues_message_count()
+            + self.output_queues_message_count()
+    }
+
+    /// Returns the number of non-stale canister messages enqueued in input queues,
+    /// split by class, as a `(best_effort_count, guaranteed_count)` tuple.
+    ///
+    /// Expired callbacks and shed responses are counted as best-effort, since
+    /// only best-effort callbacks can expire and only best-effort responses can
+    /// be shed.
+    pub fn input_queues_message_count_by_class(&self) -> (usize, usize) {
+        let stats = self.message_stats();
+        let guaranteed_count =
+            stats.inbound_guaranteed_request_count + stats.inbound_guaranteed_response_count;
+        (
+            self.input_queues_message_count() - guaranteed_count,
+            guaranteed_count,
+        )
+    }
+
+    /// Returns the number of actual (non-stale) messages in output queues, split
+    /// by class, as a `(best_effort_count, guaranteed_count)` tuple.
+    pub fn output_queues_message_count_by_class(&self) -> (usize, usize) {
+        let stats = self.message_stats();
+        let inbound_best_effort_count = stats.inbound_message_count
+            - stats.inbound_guaranteed_request_count
+            - stats.inbound_guaranteed_response_count;
+        let best_effort_count = stats.best_effort_message_count - inbound_best_effort_count;
+        (
+            best_effort_count,
+            stats.outbound_message_count - best_effort_count,
+        )
+    }
+
     /// Returns the number of reserved slots across all output queues.
     ///
     /// Note that this is different from memory reservations for guaranteed
@@ -1304,6 +1663,25 @@ impl CanisterQueues {
             .oversized_guaranteed_requests_extra_bytes
     }
 
+    /// Returns a breakdown of the memory used by the queues and their messages.
+    ///
+    /// `best_effort_bytes` equals `best_effort_memory_usage()` and the remaining
+    /// byte counts sum up to `guaranteed_response_memory_usage()`.
+    pub fn memory_usage_breakdown(&self) -> MemoryBreakdown {
+        MemoryBreakdown {
+            best_effort_bytes: self.best_effort_memory_usage(),
+            guaranteed_response_bytes: self.guaranteed_responses_size_bytes(),
+            guaranteed_reservations_bytes: self.guaranteed_response_memory_reservations()
+                * MAX_RESPONSE_COUNT_BYTES,
+            oversized_request_extra_bytes: self.oversized_guaranteed_requests_extra_bytes(),
+            stream_guaranteed_response_bytes: self
+                .queue_stats
+                .transient_stream_guaranteed_responses_size_bytes,
+            reserved_slots: self.input_queues_reserved_slots()
+                + self.output_queues_reserved_slots(),
+        }
+    }
+
     /// Sets the (transient) size in bytes of guaranteed responses routed from
     /// output queues into streams and not yet garbage collected.
     pub(super) fn set_stream_guaranteed_responses_size_bytes(&mut self, size_bytes: usize) {
@@ -1330,16 +1708,27 @@ impl CanisterQueues {
         // persist it explicitly).
         if self.canister_queues.is_empty() && self.ingress_queue.is_empty() {
             // The schedules and stats will already have default (zero) values, only `store`
-            // and `input_schedule` must be reset explicitly.
+            // and `input_schedule` must be reset explicitly. The configured request
+            // lifetime is preserved.
             debug_assert!(self.store.is_empty());
+            let request_lifetime = self.store.pool.request_lifetime();
             self.store = MessageStoreImpl::default();
+            self.store.pool.set_request_lifetime(request_lifetime);
             self.input_schedule = InputSchedule::default();
 
-            // Trust but verify. Ensure that the `CanisterQueues` now encodes to zero bytes.
-            debug_assert_eq!(
-                0,
-                pb_queues::CanisterQueues::from(self as &Self).encoded_len()
-            );
+            // Trust but verify. Ensure that the `CanisterQueues` now encodes the same as a
+            // newly created one with the same configuration (i.e. to zero bytes, unless
+            // configured otherwise than by default).
+            #[cfg(debug_assertions)]
+            {
+                let mut expected =
+                    CanisterQueues::with_response_deduplication(self.deduplicates_responses());
+                expected.set_request_lifetime(request_lifetime);
+                debug_assert_eq!(
+                    pb_queues::CanisterQueues::from(&expected).encoded_len(),
+                    pb_queues::CanisterQueues::from(self as &Self).encoded_len()
+                );
+            }
         }
     }
 
@@ -1358,6 +1747,98 @@ impl CanisterQueues {
         debug_assert_eq!(Ok(()), self.test_invariants());
     }
 
+    /// Discards all messages enqueued in the input and output queues to / from the
+    /// given canister (e.g. because the canister was deleted), together with the
+    /// slots reserved in the input queue for responses to outbound calls.
+    ///
+    /// `open_guaranteed_callbacks` is the number of guaranteed response callbacks
+    /// for calls to `canister_id` that have not yet been responded to, as tracked
+    /// by the `CallContextManager`. It is needed because reserved input queue slots
+    /// do not record whether they are for a guaranteed response or a best-effort
+    /// call: the guaranteed response memory reservations held by calls already in
+    /// flight (i.e. whose requests are neither in the output queue nor responded
+    /// to by an enqueued input queue response) are released based on it.
+    ///
+    /// Slots reserved in the output queue for responses to inbound requests that
+    /// are already executing are kept (together with their memory reservations),
+    /// so those requests can still be responded to. The (empty) queue pair is then
+    /// garbage collected once the responses have been pushed and popped.
+    ///
+    /// Updates the stats accordingly and removes the canister from the input
+    /// schedules. Does not generate reject responses for the dropped requests, nor
+    /// does it close any callbacks or call contexts: that is up to the caller.
+    ///
+    /// Time complexity: `O(n * log(n))`.
+    pub fn drop_queues_to(&mut self, canister_id: &CanisterId, open_guaranteed_callbacks: usize) {
+        let Some((input_queue, output_queue)) = self.canister_queues.get_mut(canister_id) else {
+            return;
+        };
+        let mut input_queue =
+            std::mem::replace(input_queue, CanisterQueue::new(DEFAULT_QUEUE_CAPACITY));
+
+        // Guaranteed response callbacks whose memory reservation is not held by a
+        // request in the output queue and was not consumed by an enqueued response.
+        let mut in_flight_guaranteed_calls = open_guaranteed_callbacks;
+        let input_queue_reserved_slots = input_queue.reserved_slots();
+
+        while let Some(reference) = input_queue.pop() {
+            if self.store.is_stale(reference) {
+                continue;
+            }
+            match self.store.take(reference) {
+                CanisterInput::Request(request) => {
+                    // Release the output queue slot reserved for the response.
+                    output_queue.release_reserved_response_slot();
+                    self.queue_stats.on_drop_input_request_with_queues(&request);
+                }
+                msg => {
+                    if let CanisterInput::Response(response) = &msg {
+                        if response.deadline == NO_DEADLINE {
+                            in_flight_guaranteed_calls =
+                                in_flight_guaranteed_calls.saturating_sub(1);
+                        }
+                    }
+                    if let Some(callback_id) = msg.response_callback_id() {
+                        assert!(
+                            self.response_deduplication_disabled
+                                || self.callbacks_with_enqueued_response.remove(&callback_id)
+                        );
+                    }
+                }
+            }
+        }
+        while let Some(reference) = output_queue.pop() {
+            if self.store.is_stale(reference) {
+                continue;
+            }
+            if let RequestOrResponse::Request(request) = self.store.take(reference) {
+                if request.deadline == NO_DEADLINE {
+                    in_flight_guaranteed_calls = in_flight_guaranteed_calls.saturating_sub(1);
+                }
+                self.queue_stats
+                    .on_drop_output_request_with_queues(&request);
+            }
+        }
+        debug_assert!(in_flight_guaranteed_calls <= input_queue_reserved_slots);
+        self.queue_stats
+            .on_drop_input_queue(input_queue_reserved_slots, in_flight_guaranteed_calls);
+
+        if !output_queue.has_used_slots() {
+            self.canister_queues.remove(canister_id);
+        }
+        self.input_schedule.remove(canister_id);
+
+        debug_assert_eq!(Ok(()), self.test_invariants());
+        debug_assert_eq!(Ok(()), self.schedules_ok(&|_| InputQueueType::RemoteSubnet));
+    }
+
+    /// Sets the lifetime of guaranteed response call requests subsequently
+    /// enqueued into output queues, truncated to a whole number of seconds.
+    /// Requests already enqueued keep their deadlines.
+    pub fn set_request_lifetime(&mut self, request_lifetime: Duration) {
+        self.store.pool.set_request_lifetime(request_lifetime);
+    }
+
     /// Queries whether the deadline of any message in the pool has expired.
     ///
     /// Time complexity: `O(1)`.
@@ -1365,6 +1846,38 @@ impl CanisterQueues {
         self.store.pool.has_expired_deadlines(current_time)
     }
 
+    /// Returns the earliest time at which `has_expired_deadlines()` will return
+    /// `true`, if any message in the pool has a deadline. I.e. the time when the
+    /// next message times out.
+    ///
+    /// Only outbound guaranteed response requests and best-effort messages other
+    /// than inbound responses ever time out; `None` is returned if there are no
+    /// such messages.
+    ///
+    /// Time complexity: `O(log(n))`.
+    pub fn next_timeout(&self) -> Option<Time> {
+        // Deadlines have a granularity of one second and a message expires once
+        // `current_time` is past its deadline's second.
+        self.store
+            .pool
+            .next_deadline()
+            .map(|deadline| deadline + Duration::from_secs(1))
+    }
+
+    /// Returns an iterator over all messages that may time out, in the order in
+    /// which they are going to time out, together with their deadlines. Intended
+    /// for inspection (e.g. by debug tooling).
+    ///
+    /// Only outbound guaranteed response requests and best-effort messages other
+    /// than inbound responses ever time out. Messages that were already dropped
+    /// (e.g. shed) are not included.
+    pub fn iter_by_deadline(&self) -> impl Iterator<Item = (Time, &RequestOrResponse)> {
+        self.store
+            .pool
+            .iter_by_deadline()
+            .map(|(deadline, _, msg)| (deadline, msg))
+    }
+
     /// Drops expired messages given a current time, enqueueing a reject response
     /// for own requests into the matching reverse queue (input or output).
     ///
@@ -1415,6 +1928,35 @@ impl CanisterQueues {
         false
     }
 
+    /// Sheds the largest best-effort messages in the underlying pool until the
+    /// memory usage of best-effort messages is at or below
+    /// `target_best_effort_memory_usage`. Returns the number of messages shed.
+    ///
+    /// Updates the stats for the dropped messages and (where applicable) the
+    /// generated responses. `own_canister_id` and `local_canisters` are required
+    /// to determine the correct input queue schedule to update (if applicable).
+    pub fn shed_messages_until(
+        &mut self,
+        target_best_effort_memory_usage: usize,
+        own_canister_id: &CanisterId,
+        local_canisters: &BTreeMap<CanisterId, CanisterState>,
+    ) -> usize {
+        let shed_messages = self
+            .store
+            .pool
+            .shed_messages_until(target_best_effort_memory_usage);
+        let shed_message_count = shed_messages.len();
+
+        let input_queue_type_fn = input_queue_type_fn(own_canister_id, local_canisters);
+        for (reference, msg) in shed_messages.into_iter() {
+            self.on_message_dropped(reference, msg, &input_queue_type_fn);
+        }
+
+        debug_assert_eq!(Ok(()), self.test_invariants());
+        debug_assert_eq!(Ok(()), self.schedules_ok(&input_queue_type_fn));
+        shed_message_count
+    }
+
     /// Handles the timing out or shedding of a message from the pool.
     ///
     /// Updates the stats, replaces shed inbound responses with compact reject
@@ -1515,9 +2057,12 @@ impl CanisterQueues {
                 self.queue_stats
                     .on_push_response(&response, Context::Inbound);
 
-                assert!(self
-                    .callbacks_with_enqueued_response
-                    .insert(response.originator_reply_callback));
+                assert!(
+                    !self.deduplicates_responses()
+                        || self
+                            .callbacks_with_enqueued_response
+                            .insert(response.originator_reply_callback)
+                );
                 let reference = self.store.insert_inbound(response.into());
                 input_queue.push_response(reference);
 
@@ -1545,15 +2090,36 @@ impl CanisterQueues {
     /// the correct schedule), there is no guarantee that a given queue will ever
     /// become empty. Because of that, we explicitly re-partition schedules during
     /// canister migrations.
+    ///
+    /// Returns a summary of how many senders moved between schedules.
     pub(crate) fn split_input_schedules(
         &mut self,
         own_canister_id: &CanisterId,
         local_canisters: &BTreeMap<CanisterId, CanisterState>,
-    ) {
+    ) -> InputScheduleSplitSummary {
         let input_queue_type_fn = input_queue_type_fn(own_canister_id, local_canisters);
-        self.input_schedule.split(&input_queue_type_fn);
+        let summary = self.input_schedule.split(&input_queue_type_fn);
 
         debug_assert_eq!(Ok(()), self.schedules_ok(&input_queue_type_fn));
+        summary
+    }
+
+    /// Checks all `CanisterQueues` invariants, including those of the input
+    /// schedules, returning a description of the first violation found.
+    ///
+    /// Unlike the `debug_assert!` checks, this also runs in release builds, e.g.
+    /// for fuzzers or for validating state after state sync. `own_canister_id`
+    /// and `local_canisters` are required to determine the expected input
+    /// schedule of every sender.
+    ///
+    /// Time complexity: `O(n * log(n))`.
+    pub fn validate(
+        &self,
+        own_canister_id: &CanisterId,
+        local_canisters: &BTreeMap<CanisterId, CanisterState>,
+    ) -> Result<(), String> {
+        self.test_invariants()?;
+        self.schedules_ok(&input_queue_type_fn(own_canister_id, local_canisters))
     }
 
     /// Helper function to concisely validate `CanisterQueues`' input schedule
@@ -1606,6 +2172,17 @@ impl CanisterQueues {
             ));
         }
 
+        // Without response deduplication, `callbacks_with_enqueued_response` is empty.
+        if !self.deduplicates_responses() {
+            if !self.callbacks_with_enqueued_response.is_empty() {
+                return Err(format!(
+                    "Non-empty `callbacks_with_enqueued_response` without response deduplication: {:?}",
+                    self.callbacks_with_enqueued_response
+                ));
+            }
+            return Ok(());
+        }
+
         // `callbacks_with_enqueued_response` contains the precise set of `CallbackIds`
         // of all inbound responses.
         let enqueued_response_callbacks = self
@@ -1736,6 +2313,7 @@ impl From<&CanisterQueues> for pb_queues::CanisterQueues {
                 .queue_stats
                 .guaranteed_response_memory_reservations
                 as u64,
+            response_deduplication_disabled: item.response_deduplication_disabled,
         }
     }
 }
@@ -1824,9 +2402,13 @@ impl TryFrom<(pb_queues::CanisterQueues, &dyn CheckpointLoadingMetrics)> for Can
             expired_callbacks,
             shed_responses,
         };
-        let callbacks_with_enqueued_response = store
-            .callbacks_with_enqueued_response(&canister_queues)
-            .map_err(ProxyDecodeError::Other)?;
+        let callbacks_with_enqueued_response = if item.response_deduplication_disabled {
+            BTreeSet::new()
+        } else {
+            store
+                .callbacks_with_enqueued_response(&canister_queues)
+                .map_err(ProxyDecodeError::Other)?
+        };
 
         let queues = Self {
             ingress_queue: IngressQueue::try_from(item.ingress_queue)?,
@@ -1835,6 +2417,7 @@ impl TryFrom<(pb_queues::CanisterQueues, &dyn CheckpointLoadingMetrics)> for Can
             queue_stats,
             input_schedule,
             callbacks_with_enqueued_response,
+            response_deduplication_disabled: item.response_deduplication_disabled,
         };
 
         // Safe to pretend that all senders are remote, as the validation logic allows
@@ -1952,6 +2535,47 @@ impl QueueStats {
         self.input_queues_reserved_slots = self.input_queues_reserved_slots.saturating_sub(1);
     }
 
+    /// Updates the stats to reflect the dropping of a whole input queue with the
+    /// given number of reserved slots, `in_flight_guaranteed_calls` of which were
+    /// reserved for guaranteed responses (and hold a memory reservation).
+    fn on_drop_input_queue(&mut self, reserved_slots: usize, in_flight_guaranteed_calls: usize) {
+        debug_assert!(self.input_queues_reserved_slots >= reserved_slots);
+        self.input_queues_reserved_slots = self
+            .input_queues_reserved_slots
+            .saturating_sub(reserved_slots);
+        self.release_guaranteed_response_memory_reservations(in_flight_guaranteed_calls);
+    }
+
+    /// Updates the stats to reflect the dropping of the given inbound request
+    /// together with its input queue: releases the output queue slot reserved for
+    /// its response; and, if it is a guaranteed response request, its memory
+    /// reservation.
+    fn on_drop_input_request_with_queues(&mut self, request: &Request) {
+        debug_assert!(self.output_queues_reserved_slots > 0);
+        self.output_queues_reserved_slots = self.output_queues_reserved_slots.saturating_sub(1);
+        if request.deadline == NO_DEADLINE {
+            self.release_guaranteed_response_memory_reservations(1);
+        }
+    }
+
+    /// Updates the stats to reflect the dropping of the given outbound request
+    /// together with its output queue: if it is a guaranteed response request,
+    /// releases its memory reservation. (The input queue slot reserved for its
+    /// response is accounted for by `on_drop_input_queue()`.)
+    fn on_drop_output_request_with_queues(&mut self, request: &Request) {
+        if request.deadline == NO_DEADLINE {
+            self.release_guaranteed_response_memory_reservations(1);
+        }
+    }
+
+    /// Releases `count` guaranteed response memory reservations.
+    fn release_guaranteed_response_memory_reservations(&mut self, count: usize) {
+        debug_assert!(self.guaranteed_response_memory_reservations >= count);
+        self.guaranteed_response_memory_reservations = self
+            .guaranteed_response_memory_reservations
+            .saturating_sub(count);
+    }
+
     /// Updates the stats to reflect the dropping of the given request from an input
     /// queue.
     fn on_drop_input_request(&mut self, request: &Request) {
@@ -2005,13 +2629,34 @@ pub fn memory_required_to_push_request(req: &Request) -> usize {
 
 pub mod testing {
     use super::input_schedule::testing::InputScheduleTesting;
-    use super::{CanisterQueues, MessageStore};
+    use super::{
+        CanisterQueue, CanisterQueues, MessageContext, MessageDescriptor, MessageStore,
+        MessageStoreImpl,
+    };
     use crate::{InputQueueType, StateError};
     use ic_types::messages::{Request, RequestOrResponse};
     use ic_types::{CanisterId, Time};
     use std::collections::VecDeque;
     use std::sync::Arc;
 
+    /// A raw item of a canister queue, as returned by
+    /// `CanisterQueuesTesting::raw_queue_items_for_testing()`.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum CanisterQueueItem {
+        /// A reference to a message or compact response held by the store.
+        Live(MessageDescriptor),
+        /// A stale reference, i.e. to a message that has since been timed out or
+        /// shed and not yet popped from the queue.
+        Stale(MessageDescriptor),
+    }
+
+    impl CanisterQueueItem {
+        /// Returns `true` if this is a stale reference.
+        pub fn is_stale(&self) -> bool {
+            matches!(self, CanisterQueueItem::Stale(_))
+        }
+    }
+
     /// Exposes public testing-only `CanisterQueues` methods to be used in other
     /// crates' unit tests.
     pub trait CanisterQueuesTesting {
@@ -2047,6 +2692,15 @@ pub mod testing {
             &self,
             canister_id: &CanisterId,
         ) -> Option<impl Iterator<Item = &RequestOrResponse>>;
+
+        /// Returns all items of the input or output queue (depending on
+        /// `context`) to / from `canister_id`, in queue order and including stale
+        /// references; or an empty `Vec` if no such queue exists.
+        fn raw_queue_items_for_testing(
+            &self,
+            canister_id: &CanisterId,
+            context: MessageContext,
+        ) -> Vec<CanisterQueueItem>;
     }
 
     impl CanisterQueuesTesting for CanisterQueues {
@@ -2096,6 +2750,43 @@ pub mod testing {
                         .map(|&reference| self.store.get(reference))
                 })
         }
+
+        fn raw_queue_items_for_testing(
+            &self,
+            canister_id: &CanisterId,
+            context: MessageContext,
+        ) -> Vec<CanisterQueueItem> {
+            match (self.canister_queues.get(canister_id), context) {
+                (Some((input_queue, _)), MessageContext::Inbound) => {
+                    raw_queue_items(&self.store, input_queue)
+                }
+                (Some((_, output_queue)), MessageContext::Outbound) => {
+                    raw_queue_items(&self.store, output_queue)
+                }
+                (None, _) => Vec::new(),
+            }
+        }
+    }
+
+    /// Maps each reference in `queue` to a `CanisterQueueItem`.
+    fn raw_queue_items<T>(
+        store: &MessageStoreImpl,
+        queue: &CanisterQueue<T>,
+    ) -> Vec<CanisterQueueItem>
+    where
+        MessageStoreImpl: MessageStore<T>,
+    {
+        queue
+            .iter()
+            .map(|reference| {
+                let descriptor = MessageDescriptor::from(reference);
+                if store.is_stale(*reference) {
+                    CanisterQueueItem::Stale(descriptor)
+                } else {
+                    CanisterQueueItem::Live(descriptor)
+                }
+            })
+            .collect()
     }
 
     #[allow(dead_code)]
@@ -1,5 +1,5 @@
 use super::queue::InputQueue;
-use super::CanisterQueues;
+use super::{CanisterQueues, InputScheduleSplitSummary};
 use crate::{InputQueueType, InputSource};
 use ic_protobuf::proxy::ProxyDecodeError;
 use ic_protobuf::state::queues::v1::canister_queues::NextInputQueue;
@@ -150,18 +150,54 @@ impl InputSchedule {
         Some(sender)
     }
 
+    /// Removes the sender from whichever schedule (local or remote) it is enqueued
+    /// in, if any.
+    ///
+    /// Time complexity: `O(n)`.
+    pub(super) fn remove(&mut self, sender: &CanisterId) {
+        if self.scheduled_senders.remove(sender) {
+            self.local_sender_schedule
+                .retain(|canister_id| canister_id != sender);
+            self.remote_sender_schedule
+                .retain(|canister_id| canister_id != sender);
+        }
+    }
+
     /// Re-partitions `self.local_sender_schedule` and `self.remote_sender_schedule`
     /// based on the determination made by `input_queue_type_fn`.
-    pub(super) fn split(&mut self, input_queue_type_fn: impl Fn(&CanisterId) -> InputQueueType) {
+    ///
+    /// Returns a summary of how many senders changed schedules.
+    pub(super) fn split(
+        &mut self,
+        input_queue_type_fn: impl Fn(&CanisterId) -> InputQueueType,
+    ) -> InputScheduleSplitSummary {
         let local_schedule = std::mem::take(&mut self.local_sender_schedule);
         let remote_schedule = std::mem::take(&mut self.remote_sender_schedule);
 
-        for canister_id in local_schedule.into_iter().chain(remote_schedule) {
-            match input_queue_type_fn(&canister_id) {
+        let mut summary = InputScheduleSplitSummary::default();
+        let local_senders = local_schedule
+            .into_iter()
+            .map(|canister_id| (canister_id, InputQueueType::LocalSubnet));
+        let remote_senders = remote_schedule
+            .into_iter()
+            .map(|canister_id| (canister_id, InputQueueType::RemoteSubnet));
+        for (canister_id, old_type) in local_senders.chain(remote_senders) {
+            let new_type = input_queue_type_fn(&canister_id);
+            match (old_type, new_type) {
+                (InputQueueType::RemoteSubnet, InputQueueType::LocalSubnet) => {
+                    summary.moved_to_local += 1
+                }
+                (InputQueueType::LocalSubnet, InputQueueType::RemoteSubnet) => {
+                    summary.moved_to_remote += 1
+                }
+                _ => summary.unchanged += 1,
+            }
+            match new_type {
                 InputQueueType::LocalSubnet => self.local_sender_schedule.push_back(canister_id),
                 InputQueueType::RemoteSubnet => self.remote_sender_schedule.push_back(canister_id),
             }
         }
+        summary
     }
 
     /// Validates `InputSchedule`'s invariants after checkpoint loading; or in debug
@@ -8,6 +8,8 @@ use ic_types::time::CoarseTime;
 use ic_types::{CountBytes, Time};
 use ic_validate_eq::ValidateEq;
 use ic_validate_eq_derive::ValidateEq;
+#[cfg(test)]
+use std::cell::Cell;
 use std::collections::{BTreeMap, BTreeSet};
 use std::marker::PhantomData;
 use std::ops::{AddAssign, SubAssign};
@@ -17,14 +19,45 @@ use std::time::Duration;
 #[cfg(test)]
 pub(super) mod tests;
 
-/// The lifetime of a guaranteed response call request in an output queue, from
-/// which its deadline is computed (as `now + REQUEST_LIFETIME`).
+/// The default lifetime of a guaranteed response call request in an output
+/// queue, from which its deadline is computed (as `now + request_lifetime`).
 pub const REQUEST_LIFETIME: Duration = Duration::from_secs(300);
 
+#[cfg(test)]
+thread_local! {
+    /// Number of expensive invariant checks performed (by the current thread).
+    static EXPENSIVE_INVARIANT_CHECK_COUNT: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Whether to recompute the stats and validate the priority queues after every
+/// pool operation. This costs `O(n)` per operation, so it is done in unit tests
+/// and debug builds (same as a `debug_assert!()`), except fuzzing builds; or
+/// with the `expensive_invariant_checks` feature.
+fn expensive_invariant_checks_enabled() -> bool {
+    cfg!(test)
+        || expensive_invariant_checks_configured(
+            cfg!(feature = "expensive_invariant_checks"),
+            cfg!(debug_assertions),
+            cfg!(feature = "fuzzing_code"),
+        )
+}
+
+/// Whether expensive invariant checks are enabled by the given build
+/// configuration. Fuzzers build with `debug_assertions` and would be slowed down
+/// to a crawl on large pools, so `fuzzing_code` opts out of them, unless they
+/// are explicitly requested via `expensive_invariant_checks`.
+const fn expensive_invariant_checks_configured(
+    expensive_invariant_checks: bool,
+    debug_assertions: bool,
+    fuzzing_code: bool,
+) -> bool {
+    expensive_invariant_checks || (debug_assertions && !fuzzing_code)
+}
+
 /// Bit encoding the message kind (request or response).
 #[repr(u64)]
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
-pub(super) enum Kind {
+pub enum Kind {
     Request = 0,
     Response = Self::BIT,
 }
@@ -46,7 +79,7 @@ impl From<&RequestOrResponse> for Kind {
 /// Bit encoding the message context (inbound or outbound).
 #[repr(u64)]
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
-pub(super) enum Context {
+pub enum Context {
     Inbound = 0,
     Outbound = Self::BIT,
 }
@@ -59,7 +92,7 @@ impl Context {
 /// Bit encoding the message class (guaranteed response vs best-effort).
 #[repr(u64)]
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
-pub(super) enum Class {
+pub enum Class {
     GuaranteedResponse = 0,
     BestEffort = Self::BIT,
 }
@@ -91,9 +124,6 @@ impl Id {
     /// Number of `Id` bits used as flags.
     const BITMASK_LEN: u32 = 3;
 
-    /// The minimum `Id` value, for use in e.g. `BTreeSet::split_off()` calls.
-    const MIN: Self = Self(0);
-
     fn kind(&self) -> Kind {
         if self.0 & Kind::BIT == Kind::Request as u64 {
             Kind::Request
@@ -131,6 +161,43 @@ impl Id {
     }
 }
 
+/// Public, read-only view of the message kind, context and class encoded in a
+/// `MessagePool` message ID, e.g. for labeling metrics. The bit layout of the
+/// ID itself remains private.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct MessageDescriptor(Id);
+
+impl MessageDescriptor {
+    /// The message kind (request or response).
+    pub fn kind(&self) -> Kind {
+        self.0.kind()
+    }
+
+    /// The message context (inbound or outbound).
+    pub fn context(&self) -> Context {
+        self.0.context()
+    }
+
+    /// The message class (guaranteed response or best-effort).
+    pub fn class(&self) -> Class {
+        self.0.class()
+    }
+}
+
+/// Decodes a message ID as encoded in a canister queue (e.g. in a
+/// `pb_queues::canister_queue::QueueItem`).
+impl From<u64> for MessageDescriptor {
+    fn from(id: u64) -> Self {
+        Self(Id(id))
+    }
+}
+
+impl<T> From<&Reference<T>> for MessageDescriptor {
+    fn from(reference: &Reference<T>) -> Self {
+        Self(reference.into())
+    }
+}
+
 /// A typed reference -- inbound (`CanisterInput`) or outbound
 /// (`RequestOrResponse`) -- to a message in the `MessagePool`.
 #[derive(Debug)]
@@ -368,16 +435,16 @@ impl TryFrom<pb_queues::canister_queues::CallbackReference> for CallbackReferenc
 /// to the load shedding queue.
 ///
 /// All pool operations except `expire_messages()` and
-/// `calculate_message_stats()` (only called during deserialization) execute in
-/// at most `O(log(N))` time.
-#[derive(Clone, Eq, PartialEq, Debug, Default, ValidateEq)]
+/// `calculate_message_stats()` (only called during deserialization and with
+/// expensive invariant checks enabled) execute in at most `O(log(N))` time.
+#[derive(Clone, Eq, PartialEq, Debug, ValidateEq)]
 pub(super) struct MessagePool {
     /// Pool contents.
     #[validate_eq(CompareWithValidateEq)]
     messages: BTreeMap<Id, RequestOrResponse>,
 
     /// Records the (implicit) deadlines of all the outbound guaranteed response
-    /// requests (only).
+    /// requests (only), computed as `now + request_lifetime` at insertion time.
     ///
     /// Invariants:
     ///  * Contains all outbound guaranteed requests:
@@ -391,7 +458,7 @@ pub(super) struct MessagePool {
 
     /// Deadline priority queue. Holds all best-effort messages except responses in
     /// input queues (which we don't want to expire); plus guaranteed response call
-    /// requests in output queues (which expire after `REQUEST_LIFETIME`); ordered
+    /// requests in output queues (which expire after `request_lifetime`); ordered
     /// by deadline.
     ///
     /// Message IDs break ties, ensuring deterministic ordering.
@@ -405,6 +472,24 @@ pub(super) struct MessagePool {
 
     /// A monotonically increasing counter used to generate unique message IDs.
     message_id_generator: u64,
+
+    /// The lifetime of guaranteed response call requests in output queues. A
+    /// whole number of seconds, `REQUEST_LIFETIME` by default.
+    request_lifetime: Duration,
+}
+
+impl Default for MessagePool {
+    fn default() -> Self {
+        Self {
+            messages: Default::default(),
+            outbound_guaranteed_request_deadlines: Default::default(),
+            message_stats: Default::default(),
+            deadline_queue: Default::default(),
+            size_queue: Default::default(),
+            message_id_generator: 0,
+            request_lifetime: REQUEST_LIFETIME,
+        }
+    }
 }
 
 impl MessagePool {
@@ -434,12 +519,24 @@ impl MessagePool {
         self.next_reference(Class::BestEffort, Kind::Response)
     }
 
+    /// Returns the lifetime of guaranteed response call requests inserted from now
+    /// on.
+    pub(super) fn request_lifetime(&self) -> Duration {
+        self.request_lifetime
+    }
+
+    /// Sets the lifetime of guaranteed response call requests inserted from now
+    /// on, truncated to a whole number of seconds.
+    pub(super) fn set_request_lifetime(&mut self, request_lifetime: Duration) {
+        self.request_lifetime = Duration::from_secs(request_lifetime.as_secs());
+    }
+
     /// Inserts an outbound request (one that is to be enqueued in an output queue)
     /// into the pool. Returns the reference assigned to the request.
     ///
     /// The request is always added to the deadline queue: if it is a best-effort
     /// request, with its explicit deadline; if it is a guaranteed response call
-    /// request, with a deadline of `now + REQUEST_LIFETIME`. It is added to the
+    /// request, with a deadline of `now + request_lifetime`. It is added to the
     /// load shedding queue iff it is a best-effort request.
     pub(super) fn insert_outbound_request(
         &mut self,
@@ -448,8 +545,8 @@ impl MessagePool {
     ) -> OutboundReference {
         let actual_deadline = if request.deadline == NO_DEADLINE {
             // Guaranteed response call requests in canister output queues expire after
-            // `REQUEST_LIFETIME`.
-            CoarseTime::floor(now + REQUEST_LIFETIME)
+            // `request_lifetime`.
+            CoarseTime::floor(now + self.request_lifetime)
         } else {
             // Best-effort requests expire as per their specified deadline.
             request.deadline
@@ -508,10 +605,7 @@ impl MessagePool {
 
         // Insert.
         assert!(self.messages.insert(id, msg).is_none());
-        debug_assert_eq!(
-            Self::calculate_message_stats(&self.messages),
-            self.message_stats
-        );
+        self.maybe_check_message_stats();
 
         // Record in deadline queue iff `actual_deadline` is non-zero. This applies to
         // all best-effort messages except responses in input queues; plus guaranteed
@@ -561,7 +655,7 @@ impl MessagePool {
         self.remove_from_deadline_queue(id, &msg);
         self.remove_from_size_queue(id, &msg);
 
-        debug_assert_eq!(Ok(()), self.check_invariants());
+        self.maybe_check_invariants();
         Some(msg)
     }
 
@@ -577,10 +671,7 @@ impl MessagePool {
         );
 
         self.message_stats -= MessageStats::stats_delta(&msg, id.context());
-        debug_assert_eq!(
-            Self::calculate_message_stats(&self.messages),
-            self.message_stats
-        );
+        self.maybe_check_message_stats();
 
         Some(msg)
     }
@@ -638,41 +729,83 @@ impl MessagePool {
         false
     }
 
+    /// Returns the earliest deadline of any message in the deadline queue, if any.
+    /// The respective message expires as soon as `now` is past this deadline.
+    ///
+    /// Does not mutate the pool, so it can be used e.g. to schedule a timer.
+    ///
+    /// Time complexity: `O(log(self.len()))`.
+    pub(super) fn next_deadline(&self) -> Option<Time> {
+        self.deadline_queue
+            .first()
+            .map(|(deadline, _)| (*deadline).into())
+    }
+
+    /// Returns an iterator over the messages in the deadline queue, in ascending
+    /// deadline order (message IDs breaking ties), together with their deadlines.
+    ///
+    /// Messages that do not expire (guaranteed response messages other than
+    /// outbound requests; and inbound best-effort responses) are not included.
+    /// Deadline queue entries without a matching message are skipped.
+    pub(super) fn iter_by_deadline(
+        &self,
+    ) -> impl Iterator<Item = (Time, SomeReference, &RequestOrResponse)> {
+        self.deadline_queue.iter().filter_map(|(deadline, id)| {
+            self.messages
+                .get(id)
+                .map(|msg| ((*deadline).into(), SomeReference::from(*id), msg))
+        })
+    }
+
     /// Removes and returns all messages with expired deadlines (i.e. `deadline <
     /// now`). Updates the stats; and the priority queues, where applicable.
     ///
     /// Time complexity per expired message: `O(log(self.len()))`.
     pub(super) fn expire_messages(&mut self, now: Time) -> Vec<(SomeReference, RequestOrResponse)> {
-        if self.deadline_queue.is_empty() {
-            // No messages with deadlines, bail out.
-            return Vec::new();
-        }
+        self.expire_messages_bounded(now, usize::MAX).0
+    }
 
-        let now = CoarseTime::floor(now);
-        if self.deadline_queue.first().unwrap().0 >= now {
+    /// Removes and returns up to `max` messages with expired deadlines (i.e.
+    /// `deadline < now`), in deadline order. Updates the stats; and the priority
+    /// queues, where applicable.
+    ///
+    /// Also returns a flag indicating whether more expired messages remain in the
+    /// pool, so that the caller may bound the work done per round.
+    ///
+    /// Time complexity per expired message: `O(log(self.len()))`.
+    pub(super) fn expire_messages_bounded(
+        &mut self,
+        now: Time,
+        max: usize,
+    ) -> (Vec<(SomeReference, RequestOrResponse)>, bool) {
+        if !self.has_expired_deadlines(now) {
             // No expired messages, bail out.
-            return Vec::new();
+            return (Vec::new(), false);
         }
 
-        // Split the deadline queue at `now`.
-        let mut temp = self.deadline_queue.split_off(&(now, Id::MIN));
-        std::mem::swap(&mut temp, &mut self.deadline_queue);
+        // Take and return expired messages, up to `max`.
+        let now = CoarseTime::floor(now);
+        let mut expired = Vec::new();
+        while let Some((deadline, id)) = self.deadline_queue.first().copied() {
+            if deadline >= now {
+                break;
+            }
+            if expired.len() >= max {
+                self.maybe_check_invariants();
+                return (expired, true);
+            }
 
-        // Take and return all expired messages.
-        let expired = temp
-            .into_iter()
-            .map(|(_, id)| {
-                let msg = self.take_impl(id).unwrap();
-                if id.is_outbound_guaranteed_request() {
-                    self.outbound_guaranteed_request_deadlines.remove(&id);
-                }
-                self.remove_from_size_queue(id, &msg);
-                (id.into(), msg)
-            })
-            .collect();
+            self.deadline_queue.pop_first();
+            let msg = self.take_impl(id).unwrap();
+            if id.is_outbound_guaranteed_request() {
+                self.outbound_guaranteed_request_deadlines.remove(&id);
+            }
+            self.remove_from_size_queue(id, &msg);
+            expired.push((id.into(), msg));
+        }
 
-        debug_assert_eq!(Ok(()), self.check_invariants());
-        expired
+        self.maybe_check_invariants();
+        (expired, false)
     }
 
     /// Removes and returns the largest best-effort message in the pool, if any.
@@ -686,7 +819,7 @@ impl MessagePool {
             let msg = self.take_impl(id).unwrap();
             self.remove_from_deadline_queue(id, &msg);
 
-            debug_assert_eq!(Ok(()), self.check_invariants());
+            self.maybe_check_invariants();
             return Some((id.into(), msg));
         }
 
@@ -694,18 +827,89 @@ impl MessagePool {
         None
     }
 
+    /// Repeatedly removes the largest best-effort message in the pool, until the
+    /// total byte size of best-effort messages is at or below
+    /// `target_best_effort_bytes`; or there are no more best-effort messages left.
+    /// Returns all removed messages, in the order in which they were shed.
+    ///
+    /// Updates the stats; and the priority queues, where applicable.
+    ///
+    /// Time complexity per shed message: `O(log(self.len()))`.
+    pub(super) fn shed_messages_until(
+        &mut self,
+        target_best_effort_bytes: usize,
+    ) -> Vec<(SomeReference, RequestOrResponse)> {
+        let mut shed = Vec::new();
+        while self.message_stats.best_effort_message_bytes > target_best_effort_bytes {
+            match self.shed_largest_message() {
+                Some(item) => shed.push(item),
+                // Nothing left to shed.
+                None => break,
+            }
+        }
+        shed
+    }
+
     /// Returns the number of messages in the pool.
     pub(super) fn len(&self) -> usize {
         self.messages.len()
     }
 
+    /// Returns `true` if the pool holds a message with the given `Id`.
+    #[cfg(test)]
+    pub(super) fn contains(&self, id: Id) -> bool {
+        self.messages.contains_key(&id)
+    }
+
+    /// Returns the number of messages in the pool with the given context.
+    ///
+    /// Time complexity: `O(1)`.
+    #[cfg(test)]
+    pub(super) fn len_by_context(&self, context: Context) -> usize {
+        match context {
+            Context::Inbound => self.message_stats.inbound_message_count,
+            Context::Outbound => self.message_stats.outbound_message_count,
+        }
+    }
+
+    /// Returns the number of guaranteed response requests in output queues, i.e.
+    /// outbound requests with an implicit deadline of the configured request
+    /// lifetime.
+    ///
+    /// Time complexity: `O(1)`.
+    pub(super) fn outbound_guaranteed_request_count(&self) -> usize {
+        self.message_stats.outbound_guaranteed_request_count
+    }
+
     /// Returns a reference to the pool's message stats.
     pub(super) fn message_stats(&self) -> &MessageStats {
         &self.message_stats
     }
 
+    /// Recomputes the message stats from scratch and compares them with the
+    /// incrementally maintained ones, if expensive invariant checks are enabled.
+    fn maybe_check_message_stats(&self) {
+        if expensive_invariant_checks_enabled() {
+            #[cfg(test)]
+            EXPENSIVE_INVARIANT_CHECK_COUNT.with(|count| count.set(count.get() + 1));
+            assert_eq!(
+                Self::calculate_message_stats(&self.messages),
+                self.message_stats
+            );
+        }
+    }
+
+    /// Checks all invariants, if expensive invariant checks are enabled.
+    fn maybe_check_invariants(&self) {
+        if expensive_invariant_checks_enabled() {
+            #[cfg(test)]
+            EXPENSIVE_INVARIANT_CHECK_COUNT.with(|count| count.set(count.get() + 1));
+            assert_eq!(Ok(()), self.check_invariants());
+        }
+    }
+
     /// Computes message stats from scratch. Used when deserializing and in
-    /// `debug_assert!()` checks.
+    /// expensive invariant checks.
     ///
     /// Time complexity: `O(n)`.
     fn calculate_message_stats(messages: &BTreeMap<Id, RequestOrResponse>) -> MessageStats {
@@ -716,7 +920,7 @@ impl MessagePool {
         stats
     }
 
-    /// Invariant check for use at loading time and in `debug_asserts`.
+    /// Invariant check for use at loading time and in expensive invariant checks.
     ///
     /// Time complexity: `O(n * log(n))`.
     fn check_invariants(&self) -> Result<(), String> {
@@ -859,6 +1063,8 @@ impl From<&MessagePool> for pb_queues::MessagePool {
                 })
                 .collect(),
             message_id_generator: item.message_id_generator,
+            request_lifetime_seconds: (item.request_lifetime != REQUEST_LIFETIME)
+                .then_some(item.request_lifetime.as_secs()),
         }
     }
 }
@@ -902,6 +1108,9 @@ impl TryFrom<pb_queues::MessagePool> for MessagePool {
             deadline_queue,
             size_queue,
             message_id_generator: item.message_id_generator,
+            request_lifetime: item
+                .request_lifetime_seconds
+                .map_or(REQUEST_LIFETIME, Duration::from_secs),
         };
 
         // Ensure that we've built a valid `MessagePool`.
@@ -926,6 +1135,9 @@ pub(super) struct MessageStats {
     /// Total byte size of all best-effort messages in the pool.
     pub(super) best_effort_message_bytes: usize,
 
+    /// Count of all best-effort messages in the pool.
+    pub(super) best_effort_message_count: usize,
+
     /// Total byte size of all guaranteed responses in the pool.
     pub(super) guaranteed_responses_size_bytes: usize,
 
@@ -959,6 +1171,12 @@ pub(super) struct MessageStats {
 
     /// Count of messages in output queues.
     pub(super) outbound_message_count: usize,
+
+    /// Count of guaranteed response requests in output queues.
+    ///
+    /// These are exactly the outbound requests that are assigned an implicit
+    /// deadline of `REQUEST_LIFETIME`.
+    pub(super) outbound_guaranteed_request_count: usize,
 }
 
 impl MessageStats {
@@ -1001,6 +1219,7 @@ impl MessageStats {
             (Inbound, GuaranteedResponse) => MessageStats {
                 size_bytes,
                 best_effort_message_bytes: 0,
+                best_effort_message_count: 0,
                 guaranteed_responses_size_bytes,
                 oversized_guaranteed_requests_extra_bytes: size_bytes
                     .saturating_sub(MAX_RESPONSE_COUNT_BYTES),
@@ -1010,10 +1229,12 @@ impl MessageStats {
                 inbound_guaranteed_request_count: 1,
                 inbound_guaranteed_response_count,
                 outbound_message_count: 0,
+                outbound_guaranteed_request_count: 0,
             },
             (Inbound, BestEffort) => MessageStats {
                 size_bytes,
                 best_effort_message_bytes: size_bytes,
+                best_effort_message_count: 1,
                 guaranteed_responses_size_bytes,
                 oversized_guaranteed_requests_extra_bytes: 0,
                 inbound_size_bytes: size_bytes,
@@ -1022,10 +1243,12 @@ impl MessageStats {
                 inbound_guaranteed_request_count: 0,
                 inbound_guaranteed_response_count,
                 outbound_message_count: 0,
+                outbound_guaranteed_request_count: 0,
             },
             (Outbound, GuaranteedResponse) => MessageStats {
                 size_bytes,
                 best_effort_message_bytes: 0,
+                best_effort_message_count: 0,
                 guaranteed_responses_size_bytes,
                 oversized_guaranteed_requests_extra_bytes: size_bytes
                     .saturating_sub(MAX_RESPONSE_COUNT_BYTES),
@@ -1035,10 +1258,12 @@ impl MessageStats {
                 inbound_guaranteed_request_count: 0,
                 inbound_guaranteed_response_count,
                 outbound_message_count: 1,
+                outbound_guaranteed_request_count: 1,
             },
             (Outbound, BestEffort) => MessageStats {
                 size_bytes,
                 best_effort_message_bytes: size_bytes,
+                best_effort_message_count: 1,
                 guaranteed_responses_size_bytes,
                 oversized_guaranteed_requests_extra_bytes: 0,
                 inbound_size_bytes: 0,
@@ -1047,6 +1272,7 @@ impl MessageStats {
                 inbound_guaranteed_request_count: 0,
                 inbound_guaranteed_response_count,
                 outbound_message_count: 1,
+                outbound_guaranteed_request_count: 0,
             },
         }
     }
@@ -1067,11 +1293,13 @@ impl MessageStats {
         // This is a response, request stats are all unaffected.
         let oversized_guaranteed_requests_extra_bytes = 0;
         let inbound_guaranteed_request_count = 0;
+        let outbound_guaranteed_request_count = 0;
 
         match (context, class) {
             (Inbound, GuaranteedResponse) => MessageStats {
                 size_bytes,
                 best_effort_message_bytes: 0,
+                best_effort_message_count: 0,
                 guaranteed_responses_size_bytes: size_bytes,
                 oversized_guaranteed_requests_extra_bytes,
                 inbound_size_bytes: size_bytes,
@@ -1080,10 +1308,12 @@ impl MessageStats {
                 inbound_guaranteed_request_count,
                 inbound_guaranteed_response_count: 1,
                 outbound_message_count: 0,
+                outbound_guaranteed_request_count,
             },
             (Inbound, BestEffort) => MessageStats {
                 size_bytes,
                 best_effort_message_bytes: size_bytes,
+                best_effort_message_count: 1,
                 guaranteed_responses_size_bytes: 0,
                 oversized_guaranteed_requests_extra_bytes,
                 inbound_size_bytes: size_bytes,
@@ -1092,10 +1322,12 @@ impl MessageStats {
                 inbound_guaranteed_request_count,
                 inbound_guaranteed_response_count: 0,
                 outbound_message_count: 0,
+                outbound_guaranteed_request_count,
             },
             (Outbound, GuaranteedResponse) => MessageStats {
                 size_bytes,
                 best_effort_message_bytes: 0,
+                best_effort_message_count: 0,
                 guaranteed_responses_size_bytes: size_bytes,
                 oversized_guaranteed_requests_extra_bytes,
                 inbound_size_bytes: 0,
@@ -1104,10 +1336,12 @@ impl MessageStats {
                 inbound_guaranteed_request_count,
                 inbound_guaranteed_response_count: 0,
                 outbound_message_count: 1,
+                outbound_guaranteed_request_count,
             },
             (Outbound, BestEffort) => MessageStats {
                 size_bytes,
                 best_effort_message_bytes: size_bytes,
+                best_effort_message_count: 1,
                 guaranteed_responses_size_bytes: 0,
                 oversized_guaranteed_requests_extra_bytes,
                 inbound_size_bytes: 0,
@@ -1116,6 +1350,7 @@ impl MessageStats {
                 inbound_guaranteed_request_count,
                 inbound_guaranteed_response_count: 0,
                 outbound_message_count: 1,
+                outbound_guaranteed_request_count,
             },
         }
     }
@@ -1126,6 +1361,7 @@ impl AddAssign<MessageStats> for MessageStats {
         let MessageStats {
             size_bytes,
             best_effort_message_bytes,
+            best_effort_message_count,
             guaranteed_responses_size_bytes,
             oversized_guaranteed_requests_extra_bytes,
             inbound_size_bytes,
@@ -1134,9 +1370,11 @@ impl AddAssign<MessageStats> for MessageStats {
             inbound_guaranteed_request_count,
             inbound_guaranteed_response_count,
             outbound_message_count,
+            outbound_guaranteed_request_count,
         } = rhs;
         self.size_bytes += size_bytes;
         self.best_effort_message_bytes += best_effort_message_bytes;
+        self.best_effort_message_count += best_effort_message_count;
         self.guaranteed_responses_size_bytes += guaranteed_responses_size_bytes;
         self.oversized_guaranteed_requests_extra_bytes += oversized_guaranteed_requests_extra_bytes;
         self.inbound_size_bytes += inbound_size_bytes;
@@ -1145,6 +1383,7 @@ impl AddAssign<MessageStats> for MessageStats {
         self.inbound_guaranteed_request_count += inbound_guaranteed_request_count;
         self.inbound_guaranteed_response_count += inbound_guaranteed_response_count;
         self.outbound_message_count += outbound_message_count;
+        self.outbound_guaranteed_request_count += outbound_guaranteed_request_count;
     }
 }
 
@@ -1153,6 +1392,7 @@ impl SubAssign<MessageStats> for MessageStats {
         let MessageStats {
             size_bytes,
             best_effort_message_bytes,
+            best_effort_message_count,
             guaranteed_responses_size_bytes,
             oversized_guaranteed_requests_extra_bytes,
             inbound_size_bytes,
@@ -1161,9 +1401,11 @@ impl SubAssign<MessageStats> for MessageStats {
             inbound_guaranteed_request_count,
             inbound_guaranteed_response_count,
             outbound_message_count,
+            outbound_guaranteed_request_count,
         } = rhs;
         self.size_bytes -= size_bytes;
         self.best_effort_message_bytes -= best_effort_message_bytes;
+        self.best_effort_message_count -= best_effort_message_count;
         self.guaranteed_responses_size_bytes -= guaranteed_responses_size_bytes;
         self.oversized_guaranteed_requests_extra_bytes -= oversized_guaranteed_requests_extra_bytes;
         self.inbound_size_bytes -= inbound_size_bytes;
@@ -1172,5 +1414,6 @@ impl SubAssign<MessageStats> for MessageStats {
         self.inbound_guaranteed_request_count -= inbound_guaranteed_request_count;
         self.inbound_guaranteed_response_count -= inbound_guaranteed_response_count;
         self.outbound_message_count -= outbound_message_count;
+        self.outbound_guaranteed_request_count -= outbound_guaranteed_request_count;
     }
 }
@@ -81,28 +81,133 @@ fn test_insert() {
 
 #[test]
 fn test_insert_outbound_request_deadline_rounding() {
-    let mut pool = MessagePool::default();
-
     // Sanity check: REQUEST_LIFETIME is a whole number of seconds.
     assert_eq!(
         REQUEST_LIFETIME,
         Duration::from_secs(REQUEST_LIFETIME.as_secs())
     );
 
-    // Insert an outbound request for a guaranteed response call (i.e. no deadline)
-    // at a timestamp that is not a round number of seconds.
-    let current_time = Time::from_nanos_since_unix_epoch(13_500_000_000);
-    // Sanity check that the above is actually 13+ seconds.
+    for request_lifetime in [
+        REQUEST_LIFETIME,
+        Duration::from_secs(1),
+        Duration::from_secs(30),
+    ] {
+        let mut pool = MessagePool::default();
+        pool.set_request_lifetime(request_lifetime);
+
+        // Insert an outbound request for a guaranteed response call (i.e. no deadline)
+        // at a timestamp that is not a round number of seconds.
+        let current_time = Time::from_nanos_since_unix_epoch(13_500_000_000);
+        // Sanity check that the above is actually 13+ seconds.
+        assert_eq!(
+            CoarseTime::from_secs_since_unix_epoch(13),
+            CoarseTime::floor(current_time)
+        );
+        let expected_deadline =
+            CoarseTime::from_secs_since_unix_epoch(13 + request_lifetime.as_secs() as u32);
+
+        pool.insert_outbound_request(request(NO_DEADLINE).into(), current_time);
+
+        assert_eq!(expected_deadline, pool.deadline_queue.first().unwrap().0);
+    }
+}
+
+#[test]
+fn test_custom_request_lifetime_expiration() {
+    let mut pool = MessagePool::default();
+    pool.set_request_lifetime(Duration::from_secs(5));
+
+    let msg = request(NO_DEADLINE);
+    let id: Id = pool
+        .insert_outbound_request(msg.clone().into(), time(10).into())
+        .into();
+    assert_eq!(Some(&(time(15), id)), pool.deadline_queue.first());
+
+    // Nothing expires at 15 seconds.
+    let t15 = Time::from(time(15));
+    assert!(!pool.has_expired_deadlines(t15));
+    assert!(pool.expire_messages(t15).is_empty());
+
+    // But the request expires at 16 seconds, long before the default
+    // `REQUEST_LIFETIME` has elapsed.
+    let t16 = Time::from(time(16));
+    assert!(pool.has_expired_deadlines(t16));
+    assert_eq!(vec![(id.into(), msg.into())], pool.expire_messages(t16));
+    assert_eq!(0, pool.len());
+}
+
+#[test]
+fn test_contains_and_len_by_context() {
+    let mut pool = MessagePool::default();
+    assert_eq!(0, pool.len_by_context(Context::Inbound));
+    assert_eq!(0, pool.len_by_context(Context::Outbound));
+
+    let id1: Id = pool.insert_inbound(request(NO_DEADLINE).into()).into();
+    let ref2 = pool.insert_inbound(response(time(20)).into());
+    let id2: Id = ref2.into();
+    let id3: Id = pool
+        .insert_outbound_request(request(time(30)).into(), time(25).into())
+        .into();
+    let id4: Id = pool
+        .insert_outbound_response(response(NO_DEADLINE).into())
+        .into();
+    let ref5 = pool.insert_outbound_request(request(NO_DEADLINE).into(), time(50).into());
+    let id5: Id = ref5.into();
+
+    for id in [id1, id2, id3, id4, id5] {
+        assert!(pool.contains(id));
+    }
+    assert_eq!(2, pool.len_by_context(Context::Inbound));
+    assert_eq!(3, pool.len_by_context(Context::Outbound));
+
+    // Remove one inbound and one outbound message.
+    pool.take(ref2).unwrap();
+    pool.take(ref5).unwrap();
+    assert!(!pool.contains(id2));
+    assert!(!pool.contains(id5));
+    assert!(pool.contains(id1));
+
+    // Both helpers agree with the existing stats.
+    let stats = pool.message_stats();
+    assert_eq!(
+        stats.inbound_message_count,
+        pool.len_by_context(Context::Inbound)
+    );
     assert_eq!(
-        CoarseTime::from_secs_since_unix_epoch(13),
-        CoarseTime::floor(current_time)
+        stats.outbound_message_count,
+        pool.len_by_context(Context::Outbound)
     );
-    let expected_deadline =
-        CoarseTime::from_secs_since_unix_epoch(13 + REQUEST_LIFETIME.as_secs() as u32);
+    assert_eq!(
+        pool.len(),
+        pool.len_by_context(Context::Inbound) + pool.len_by_context(Context::Outbound)
+    );
+    assert_eq!(1, pool.len_by_context(Context::Inbound));
+    assert_eq!(2, pool.len_by_context(Context::Outbound));
+}
 
-    pool.insert_outbound_request(request(NO_DEADLINE).into(), current_time);
+#[test]
+fn test_outbound_guaranteed_request_count() {
+    let mut pool = MessagePool::default();
 
-    assert_eq!(expected_deadline, pool.deadline_queue.first().unwrap().0);
+    // Two outbound guaranteed response requests, one outbound best-effort request.
+    let guaranteed1 = pool.insert_outbound_request(request(NO_DEADLINE).into(), time(10).into());
+    let _ = pool.insert_outbound_request(request(NO_DEADLINE).into(), time(20).into());
+    let _ = pool.insert_outbound_request(request(time(30)).into(), time(20).into());
+    // Neither inbound guaranteed requests nor outbound guaranteed responses count.
+    let _ = pool.insert_inbound(request(NO_DEADLINE).into());
+    let _ = pool.insert_outbound_response(response(NO_DEADLINE).into());
+
+    assert_eq!(2, pool.outbound_guaranteed_request_count());
+    assert_eq!(4, pool.len_by_context(Context::Outbound));
+
+    // Taking an outbound guaranteed request decrements the count.
+    pool.take(guaranteed1).unwrap();
+    assert_eq!(1, pool.outbound_guaranteed_request_count());
+
+    // As does timing one out.
+    let expired = pool.expire_messages(time(u32::MAX).into());
+    assert!(!expired.is_empty());
+    assert_eq!(0, pool.outbound_guaranteed_request_count());
 }
 
 #[test]
@@ -303,6 +408,150 @@ fn test_expiration() {
     assert_eq!(empty_vec, pool.expire_messages(t_max));
 }
 
+#[test]
+fn test_expiration_bounded() {
+    let t_max = Time::from_nanos_since_unix_epoch(u64::MAX);
+    let mut pool = MessagePool::default();
+
+    // Nothing to expire.
+    assert_eq!((Vec::new(), false), pool.expire_messages_bounded(t_max, 2));
+
+    // Insert 5 expiring messages with staggered deadlines, plus one that does not
+    // expire.
+    let mut expiring = Vec::new();
+    for i in (1..=5).rev() {
+        let msg: RequestOrResponse = request(time(i * 10)).into();
+        let id: Id = pool.insert_inbound(msg.clone()).into();
+        expiring.push((time(i * 10), id, msg));
+    }
+    pool.insert_inbound(request(NO_DEADLINE).into());
+    expiring.sort_by_key(|(deadline, id, _)| (*deadline, *id));
+    let mut expiring = expiring
+        .into_iter()
+        .map(|(_, id, msg)| (SomeReference::from(id), msg));
+    assert_eq!(6, pool.len());
+
+    // Only the earliest 2 messages are expired, with more remaining.
+    let (expired, more) = pool.expire_messages_bounded(t_max, 2);
+    assert_eq!(expiring.by_ref().take(2).collect::<Vec<_>>(), expired);
+    assert!(more);
+    assert_eq!(4, pool.len());
+    assert_eq!(Some(time(30).into()), pool.next_deadline());
+
+    // A limit of zero expires nothing, but still reports expired messages.
+    assert_eq!((Vec::new(), true), pool.expire_messages_bounded(t_max, 0));
+    assert_eq!(4, pool.len());
+
+    // Only messages that are actually expired are returned and the flag is not set
+    // if no other expired messages remain.
+    let (expired, more) = pool.expire_messages_bounded(time(41).into(), 2);
+    assert_eq!(expiring.by_ref().take(2).collect::<Vec<_>>(), expired);
+    assert!(!more);
+    assert_eq!(2, pool.len());
+
+    // Exactly `max` expired messages left: flag is not set.
+    let (expired, more) = pool.expire_messages_bounded(t_max, 1);
+    assert_eq!(expiring.collect::<Vec<_>>(), expired);
+    assert!(!more);
+    assert_eq!(1, pool.len());
+    assert_eq!(None, pool.next_deadline());
+}
+
+#[test]
+fn test_next_deadline() {
+    let mut pool = MessagePool::default();
+
+    // No messages with deadlines.
+    assert_eq!(None, pool.next_deadline());
+
+    // Non-expiring messages do not affect the next deadline.
+    pool.insert_inbound(request(NO_DEADLINE).into());
+    pool.insert_inbound(response(time(5)).into());
+    assert_eq!(None, pool.next_deadline());
+
+    // Insert messages with staggered deadlines, out of order.
+    let ref30 = pool.insert_outbound_response(response(time(30)).into());
+    assert_eq!(Some(time(30).into()), pool.next_deadline());
+    pool.insert_inbound(request(time(20)).into());
+    assert_eq!(Some(time(20).into()), pool.next_deadline());
+    pool.insert_outbound_request(request(NO_DEADLINE).into(), time(40).into());
+    pool.insert_outbound_request(request(time(10)).into(), time(5).into());
+    assert_eq!(Some(time(10).into()), pool.next_deadline());
+
+    // Querying the next deadline does not mutate the pool.
+    let len = pool.len();
+    assert_eq!(Some(time(10).into()), pool.next_deadline());
+    assert_eq!(len, pool.len());
+
+    // Next deadline moves forward as messages expire.
+    assert_eq!(1, pool.expire_messages(time(11).into()).len());
+    assert_eq!(Some(time(20).into()), pool.next_deadline());
+    assert_eq!(1, pool.expire_messages(time(21).into()).len());
+    assert_eq!(Some(time(30).into()), pool.next_deadline());
+
+    // And when messages are taken.
+    assert!(pool.take(ref30).is_some());
+    assert_eq!(
+        Some(time(40 + REQUEST_LIFETIME.as_secs() as u32).into()),
+        pool.next_deadline()
+    );
+
+    // Expire the last message with a deadline.
+    assert_eq!(
+        1,
+        pool.expire_messages(Time::from_nanos_since_unix_epoch(u64::MAX))
+            .len()
+    );
+    assert_eq!(None, pool.next_deadline());
+}
+
+#[test]
+fn test_iter_by_deadline() {
+    let mut pool = MessagePool::default();
+
+    // No messages with deadlines.
+    assert_eq!(0, pool.iter_by_deadline().count());
+
+    // Non-expiring messages are not included.
+    pool.insert_inbound(request(NO_DEADLINE).into());
+    pool.insert_inbound(response(time(5)).into());
+    assert_eq!(0, pool.iter_by_deadline().count());
+
+    // Insert messages with staggered deadlines, out of order.
+    let msg30: RequestOrResponse = response(time(30)).into();
+    let ref30 = pool.insert_outbound_response(msg30.clone());
+    let msg20: RequestOrResponse = request(time(20)).into();
+    let ref20 = pool.insert_inbound(msg20.clone());
+    let msg40: RequestOrResponse = request(NO_DEADLINE).into();
+    let ref40 = pool.insert_outbound_request(msg40.clone(), time(40).into());
+    let msg10: RequestOrResponse = request(time(10)).into();
+    let ref10 = pool.insert_outbound_request(msg10.clone(), time(5).into());
+    let msg25: RequestOrResponse = request(time(25)).into();
+    let ref25 = pool.insert_inbound(msg25);
+
+    // Remove one of the messages.
+    assert!(pool.take(ref25).is_some());
+
+    // Only live messages are returned, in deadline order.
+    let t40_plus_lifetime = time(40 + REQUEST_LIFETIME.as_secs() as u32);
+    assert_eq!(
+        vec![
+            (time(10).into(), SomeReference::Outbound(ref10), &msg10),
+            (time(20).into(), SomeReference::Inbound(ref20), &msg20),
+            (time(30).into(), SomeReference::Outbound(ref30), &msg30),
+            (
+                t40_plus_lifetime.into(),
+                SomeReference::Outbound(ref40),
+                &msg40
+            ),
+        ],
+        pool.iter_by_deadline().collect::<Vec<_>>()
+    );
+
+    // Iterating does not mutate the pool.
+    assert_eq!(6, pool.len());
+}
+
 #[test]
 fn test_expiration_of_non_expiring_messages() {
     let mut pool = MessagePool::default();
@@ -368,6 +617,56 @@ fn test_shed_message() {
     assert_eq!(0, pool.size_queue.len());
 }
 
+#[test]
+fn test_shed_messages_until() {
+    let mut pool = MessagePool::default();
+
+    // Nothing to shed.
+    assert!(pool.shed_messages_until(0).is_empty());
+
+    // Insert best-effort messages of different sizes, plus a guaranteed response
+    // message (which cannot be shed).
+    let msg1 = request_with_payload(1000, time(10));
+    pool.insert_inbound(msg1.clone().into());
+    let msg2 = response_with_payload(4000, time(20));
+    let id2: Id = pool.insert_inbound(msg2.clone().into()).into();
+    let msg3 = request_with_payload(3000, time(30));
+    let id3: Id = pool
+        .insert_outbound_request(msg3.clone().into(), time(35).into())
+        .into();
+    let msg4 = response_with_payload(2000, time(40));
+    pool.insert_outbound_response(msg4.clone().into());
+    pool.insert_inbound(request_with_payload(5000, NO_DEADLINE).into());
+    assert_eq!(5, pool.len());
+
+    let best_effort_bytes = pool.message_stats().best_effort_message_bytes;
+    let msg1_bytes = msg1.count_bytes();
+    let msg4_bytes = msg4.count_bytes();
+
+    // Target already met: nothing is shed.
+    assert!(pool.shed_messages_until(best_effort_bytes).is_empty());
+    assert_eq!(5, pool.len());
+
+    // Shedding stops as soon as the target is reached: `msg2` and `msg3` need to
+    // be shed for the remaining best-effort messages to fit.
+    assert_eq!(
+        vec![(id2.into(), msg2.into()), (id3.into(), msg3.into())],
+        pool.shed_messages_until(msg1_bytes + msg4_bytes)
+    );
+    assert_eq!(3, pool.len());
+    assert_eq!(
+        msg1_bytes + msg4_bytes,
+        pool.message_stats().best_effort_message_bytes
+    );
+
+    // Shedding everything stops when no best-effort messages are left, retaining
+    // the guaranteed response request.
+    assert_eq!(2, pool.shed_messages_until(0).len());
+    assert_eq!(1, pool.len());
+    assert_eq!(0, pool.message_stats().best_effort_message_bytes);
+    assert!(pool.shed_messages_until(0).is_empty());
+}
+
 #[test]
 fn test_shed_message_guaranteed_response() {
     let mut pool = MessagePool::default();
@@ -523,6 +822,53 @@ fn test_message_id_flags() {
     assert_eq!(bop_id, bop_id);
 }
 
+#[test]
+fn test_message_descriptor() {
+    use Class::*;
+    use Context::*;
+    use Kind::*;
+
+    let mut pool = MessagePool::default();
+
+    let inbound_request = pool.insert_inbound(request(NO_DEADLINE).into());
+    let inbound_response = pool.insert_inbound(response(time(10)).into());
+    let outbound_request = pool.insert_outbound_request(request(time(20)).into(), UNIX_EPOCH);
+    let outbound_response = pool.insert_outbound_response(response(NO_DEADLINE).into());
+
+    for (id, descriptor, expected) in [
+        (
+            Id::from(&inbound_request),
+            MessageDescriptor::from(&inbound_request),
+            (Request, Inbound, GuaranteedResponse),
+        ),
+        (
+            Id::from(&inbound_response),
+            MessageDescriptor::from(&inbound_response),
+            (Response, Inbound, BestEffort),
+        ),
+        (
+            Id::from(&outbound_request),
+            MessageDescriptor::from(&outbound_request),
+            (Request, Outbound, BestEffort),
+        ),
+        (
+            Id::from(&outbound_response),
+            MessageDescriptor::from(&outbound_response),
+            (Response, Outbound, GuaranteedResponse),
+        ),
+    ] {
+        // The public accessors agree with the internal ones.
+        assert_eq!((id.kind(), id.context(), id.class()), expected);
+        assert_eq!(
+            (descriptor.kind(), descriptor.context(), descriptor.class()),
+            expected
+        );
+
+        // Decoding the raw encoded ID yields the same descriptor.
+        assert_eq!(descriptor, MessageDescriptor::from(id.0));
+    }
+}
+
 #[test]
 fn test_message_id_range() {
     use Class::GuaranteedResponse;
@@ -750,6 +1096,7 @@ fn test_message_stats_best_effort() {
         MessageStats {
             size_bytes: 2 * (request_size_bytes + response_size_bytes),
             best_effort_message_bytes: 2 * (request_size_bytes + response_size_bytes),
+            best_effort_message_count: 4,
             guaranteed_responses_size_bytes: 0,
             oversized_guaranteed_requests_extra_bytes: 0,
             inbound_size_bytes: request_size_bytes + response_size_bytes,
@@ -757,7 +1104,8 @@ fn test_message_stats_best_effort() {
             inbound_response_count: 1,
             inbound_guaranteed_request_count: 0,
             inbound_guaranteed_response_count: 0,
-            outbound_message_count: 2
+            outbound_message_count: 2,
+            outbound_guaranteed_request_count: 0,
         },
         pool.message_stats
     );
@@ -829,6 +1177,7 @@ fn test_message_stats_guaranteed_response() {
         MessageStats {
             size_bytes: 2 * (request_size_bytes + response_size_bytes),
             best_effort_message_bytes: 0,
+            best_effort_message_count: 0,
             guaranteed_responses_size_bytes: 2 * response_size_bytes,
             oversized_guaranteed_requests_extra_bytes: 0,
             inbound_size_bytes: request_size_bytes + response_size_bytes,
@@ -836,7 +1185,8 @@ fn test_message_stats_guaranteed_response() {
             inbound_response_count: 1,
             inbound_guaranteed_request_count: 1,
             inbound_guaranteed_response_count: 1,
-            outbound_message_count: 2
+            outbound_message_count: 2,
+            outbound_guaranteed_request_count: 1,
         },
         pool.message_stats
     );
@@ -924,6 +1274,7 @@ fn test_message_stats_oversized_requests() {
         MessageStats {
             size_bytes: 2 * (best_effort_size_bytes + guaranteed_size_bytes),
             best_effort_message_bytes: 2 * best_effort_size_bytes,
+            best_effort_message_count: 2,
             guaranteed_responses_size_bytes: 0,
             oversized_guaranteed_requests_extra_bytes: 2 * guaranteed_extra_bytes,
             inbound_size_bytes: best_effort_size_bytes + guaranteed_size_bytes,
@@ -931,7 +1282,8 @@ fn test_message_stats_oversized_requests() {
             inbound_response_count: 0,
             inbound_guaranteed_request_count: 1,
             inbound_guaranteed_response_count: 0,
-            outbound_message_count: 2
+            outbound_message_count: 2,
+            outbound_guaranteed_request_count: 1,
         },
         pool.message_stats
     );
@@ -973,6 +1325,54 @@ fn test_message_stats_oversized_requests() {
     assert_eq!(MessageStats::default(), pool.message_stats);
 }
 
+/// Tests that every pool operation performs expensive invariant checks in unit
+/// tests.
+#[test]
+fn test_operations_perform_expensive_invariant_checks() {
+    let check_count = || EXPENSIVE_INVARIANT_CHECK_COUNT.with(Cell::get);
+    let mut pool = MessagePool::default();
+
+    let before = check_count();
+    let reference = pool.insert_inbound(request(time(20)).into());
+    let after_insert = check_count();
+    assert!(after_insert > before);
+
+    assert!(pool.take(reference).is_some());
+    assert!(check_count() > after_insert);
+}
+
+/// Tests which build configurations enable expensive invariant checks: debug
+/// builds other than fuzzing builds; and any build with the
+/// `expensive_invariant_checks` feature.
+#[test]
+fn test_expensive_invariant_checks_configured() {
+    // (expensive_invariant_checks, debug_assertions, fuzzing_code) -> enabled
+    let cases = [
+        ((false, false, false), false),
+        ((false, false, true), false),
+        ((false, true, false), true),
+        ((false, true, true), false),
+        ((true, false, false), true),
+        ((true, false, true), true),
+        ((true, true, false), true),
+        ((true, true, true), true),
+    ];
+    for ((expensive_invariant_checks, debug_assertions, fuzzing_code), enabled) in cases {
+        assert_eq!(
+            enabled,
+            expensive_invariant_checks_configured(
+                expensive_invariant_checks,
+                debug_assertions,
+                fuzzing_code
+            ),
+            "expensive_invariant_checks: {}, debug_assertions: {}, fuzzing_code: {}",
+            expensive_invariant_checks,
+            debug_assertions,
+            fuzzing_code
+        );
+    }
+}
+
 /// Tests that an encode-decode roundtrip yields a result equal to the original
 /// (and that the stats and priority queues of an organically constructed
 /// `MessagePool` match those of a deserialized one).
@@ -999,6 +1399,28 @@ fn encode_roundtrip() {
     assert_eq!(pool, decoded);
 }
 
+/// Tests that a non-default request lifetime survives an encode-decode
+/// roundtrip; and that the default one is not encoded.
+#[test]
+fn encode_roundtrip_request_lifetime() {
+    let mut pool = MessagePool::default();
+    let encoded: pb_queues::MessagePool = (&pool).into();
+    assert_eq!(None, encoded.request_lifetime_seconds);
+
+    pool.set_request_lifetime(Duration::from_secs(42));
+    pool.insert_outbound_request(
+        request_with_payload(100, NO_DEADLINE).into(),
+        time(50).into(),
+    );
+
+    let encoded: pb_queues::MessagePool = (&pool).into();
+    assert_eq!(Some(42), encoded.request_lifetime_seconds);
+    let decoded: MessagePool = encoded.try_into().unwrap();
+
+    assert_eq!(pool, decoded);
+    assert_eq!(Duration::from_secs(42), decoded.request_lifetime);
+}
+
 /// Tests an encode-decode roundtrip of an empty `MessagePool`.
 #[test]
 fn encode_roundtrip_empty() {
@@ -1109,9 +1531,13 @@ fn request_stats_delta2(req: &Request, context: Context) -> MessageStats {
     };
 
     let size_bytes = req.count_bytes();
-    let (best_effort_message_bytes, oversized_guaranteed_requests_extra_bytes) = match class {
-        GuaranteedResponse => (0, size_bytes.saturating_sub(MAX_RESPONSE_COUNT_BYTES)),
-        BestEffort => (size_bytes, 0),
+    let (
+        best_effort_message_bytes,
+        best_effort_message_count,
+        oversized_guaranteed_requests_extra_bytes,
+    ) = match class {
+        GuaranteedResponse => (0, 0, size_bytes.saturating_sub(MAX_RESPONSE_COUNT_BYTES)),
+        BestEffort => (size_bytes, 1, 0),
     };
     let (inbound_size_bytes, inbound_message_count, outbound_message_count) = if context == Inbound
     {
@@ -1124,6 +1550,11 @@ fn request_stats_delta2(req: &Request, context: Context) -> MessageStats {
     } else {
         0
     };
+    let outbound_guaranteed_request_count = if context == Outbound && class == GuaranteedResponse {
+        1
+    } else {
+        0
+    };
     // Response stats are unaffected.
     let guaranteed_responses_size_bytes = 0;
     let inbound_response_count = 0;
@@ -1132,6 +1563,7 @@ fn request_stats_delta2(req: &Request, context: Context) -> MessageStats {
     MessageStats {
         size_bytes,
         best_effort_message_bytes,
+        best_effort_message_count,
         guaranteed_responses_size_bytes,
         oversized_guaranteed_requests_extra_bytes,
         inbound_size_bytes,
@@ -1140,6 +1572,7 @@ fn request_stats_delta2(req: &Request, context: Context) -> MessageStats {
         inbound_guaranteed_request_count,
         inbound_guaranteed_response_count,
         outbound_message_count,
+        outbound_guaranteed_request_count,
     }
 }
 
@@ -1156,10 +1589,11 @@ fn response_stats_delta2(rep: &Response, context: Context) -> MessageStats {
     };
 
     let size_bytes = rep.count_bytes();
-    let (best_effort_message_bytes, guaranteed_responses_size_bytes) = match class {
-        GuaranteedResponse => (0, size_bytes),
-        BestEffort => (size_bytes, 0),
-    };
+    let (best_effort_message_bytes, best_effort_message_count, guaranteed_responses_size_bytes) =
+        match class {
+            GuaranteedResponse => (0, 0, size_bytes),
+            BestEffort => (size_bytes, 1, 0),
+        };
     let (inbound_size_bytes, inbound_message_count, inbound_response_count, outbound_message_count) =
         if context == Inbound {
             (size_bytes, 1, 1, 0)
@@ -1175,10 +1609,12 @@ fn response_stats_delta2(rep: &Response, context: Context) -> MessageStats {
     // Request stats are unaffected.
     let oversized_guaranteed_requests_extra_bytes = 0;
     let inbound_guaranteed_request_count = 0;
+    let outbound_guaranteed_request_count = 0;
 
     MessageStats {
         size_bytes,
         best_effort_message_bytes,
+        best_effort_message_count,
         guaranteed_responses_size_bytes,
         oversized_guaranteed_requests_extra_bytes,
         inbound_size_bytes,
@@ -1187,5 +1623,6 @@ fn response_stats_delta2(rep: &Response, context: Context) -> MessageStats {
         inbound_guaranteed_request_count,
         inbound_guaranteed_response_count,
         outbound_message_count,
+        outbound_guaranteed_request_count,
     }
 }
@@ -112,6 +112,11 @@ impl<T> CanisterQueue<T> {
         }
     }
 
+    /// Returns the queue capacity.
+    pub(super) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
     /// Returns the number of slots available for requests.
     pub(super) fn available_request_slots(&self) -> usize {
         debug_assert!(self.request_slots <= self.capacity);
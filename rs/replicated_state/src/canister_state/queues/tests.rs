@@ -1,6 +1,8 @@
 use super::input_schedule::testing::InputScheduleTesting;
 use super::message_pool::{MessageStats, REQUEST_LIFETIME};
-use super::testing::{new_canister_output_queues_for_test, CanisterQueuesTesting};
+use super::testing::{
+    new_canister_output_queues_for_test, CanisterQueueItem, CanisterQueuesTesting,
+};
 use super::*;
 use crate::{CanisterState, InputQueueType::*, SchedulerState, SystemState};
 use assert_matches::assert_matches;
@@ -341,6 +343,123 @@ fn push_input_response_duplicate_best_effort_response() {
     assert_eq!(2, queues.input_queues_response_count());
 }
 
+#[test]
+fn push_input_response_duplicate_best_effort_response_without_deduplication() {
+    for deduplication in [true, false] {
+        let mut queues = CanisterQueues::with_response_deduplication(deduplication);
+
+        // Enqueue two output requests (callback IDs 1 and 2), reserving 2 input queue
+        // slots.
+        for callback in [1, 2] {
+            queues
+                .push_output_request(request(callback, SOME_DEADLINE).into(), UNIX_EPOCH)
+                .unwrap();
+            queues.output_into_iter().pop().unwrap();
+        }
+        assert_eq!(2, queues.input_queues_reserved_slots());
+
+        // Enqueue two responses with the same callback ID. Both attempts succeed,
+        // but only enqueue a second response without deduplication.
+        queues
+            .push_input(response(1, SOME_DEADLINE).into(), LocalSubnet)
+            .unwrap();
+        queues
+            .push_input(response(1, SOME_DEADLINE).into(), LocalSubnet)
+            .unwrap();
+        if deduplication {
+            assert_eq!(1, queues.input_queues_reserved_slots());
+            assert_eq!(1, queues.input_queues_response_count());
+        } else {
+            assert_eq!(0, queues.input_queues_reserved_slots());
+            assert_eq!(2, queues.input_queues_response_count());
+        }
+        assert_eq!(Ok(()), queues.test_invariants());
+
+        // All enqueued responses can be popped.
+        assert!(queues.pop_input().is_some());
+        if !deduplication {
+            assert!(queues.pop_input().is_some());
+        }
+        assert!(queues.pop_input().is_none());
+        assert_eq!(Ok(()), queues.test_invariants());
+    }
+}
+
+#[test]
+fn push_input_batch_is_all_or_nothing() {
+    let mut queues = CanisterQueues::default();
+
+    // Fill up the input queue, all but one slot.
+    for i in 0..DEFAULT_QUEUE_CAPACITY - 1 {
+        queues
+            .push_input(request(i as u64, NO_DEADLINE).into(), LocalSubnet)
+            .unwrap();
+    }
+    let queues_before = queues.clone();
+
+    // A batch that would overflow the input queue on its last message.
+    let batch: Vec<RequestOrResponse> = vec![
+        request(1000, NO_DEADLINE).into(),
+        request(1001, NO_DEADLINE).into(),
+    ];
+    let (err, returned) = queues
+        .push_input_batch(
+            batch
+                .iter()
+                .cloned()
+                .map(|msg| (msg, LocalSubnet))
+                .collect(),
+        )
+        .unwrap_err();
+    assert_matches!(err, StateError::QueueFull { capacity } if capacity == DEFAULT_QUEUE_CAPACITY);
+    assert_eq!(batch, returned);
+
+    // Nothing was inducted.
+    assert_eq!(queues_before, queues);
+
+    // A batch that fits is inducted in full.
+    queues
+        .push_input_batch(vec![(request(1000, NO_DEADLINE).into(), LocalSubnet)])
+        .unwrap();
+    assert_eq!(DEFAULT_QUEUE_CAPACITY, queues.input_queues_message_count());
+}
+
+#[test]
+fn push_input_batch_duplicate_guaranteed_response() {
+    let mut queues = CanisterQueues::default();
+
+    // Enqueue two output requests, reserving 2 input queue slots.
+    for callback in [1, 2] {
+        queues
+            .push_output_request(request(callback, NO_DEADLINE).into(), UNIX_EPOCH)
+            .unwrap();
+        queues.output_into_iter().pop().unwrap();
+    }
+    let queues_before = queues.clone();
+
+    // A batch with a duplicate guaranteed response fails, even though there are
+    // enough reserved slots.
+    let (err, returned) = queues
+        .push_input_batch(vec![
+            (response(1, NO_DEADLINE).into(), LocalSubnet),
+            (response(1, NO_DEADLINE).into(), LocalSubnet),
+        ])
+        .unwrap_err();
+    assert_matches!(err, StateError::NonMatchingResponse { .. });
+    assert_eq!(2, returned.len());
+    assert_eq!(queues_before, queues);
+
+    // A batch without duplicates is inducted in full.
+    queues
+        .push_input_batch(vec![
+            (response(1, NO_DEADLINE).into(), LocalSubnet),
+            (response(2, NO_DEADLINE).into(), LocalSubnet),
+        ])
+        .unwrap();
+    assert_eq!(0, queues.input_queues_reserved_slots());
+    assert_eq!(2, queues.input_queues_response_count());
+}
+
 /// Checks that `available_output_request_slots` doesn't count input requests and
 /// output reserved slots and responses.
 #[test]
@@ -470,6 +589,87 @@ fn test_available_output_request_slots() {
     );
 }
 
+/// Checks that `available_output_request_slots_for` reports the full default
+/// capacity for a canister without a queue pair.
+#[test]
+fn test_available_output_request_slots_for() {
+    let mut fixture = CanisterQueuesFixture::new();
+    let never_seen = canister_test_id(17);
+
+    // No queue pairs yet.
+    assert!(fixture.queues.available_output_request_slots().is_empty());
+    assert_eq!(
+        DEFAULT_QUEUE_CAPACITY,
+        fixture
+            .queues
+            .available_output_request_slots_for(&fixture.other)
+    );
+
+    // Push an output request, creating a queue pair.
+    fixture.push_output_request().unwrap();
+    assert_eq!(
+        DEFAULT_QUEUE_CAPACITY - 1,
+        fixture
+            .queues
+            .available_output_request_slots_for(&fixture.other)
+    );
+    assert_eq!(
+        fixture.available_output_request_slots(),
+        fixture
+            .queues
+            .available_output_request_slots_for(&fixture.other)
+    );
+
+    // A never-seen canister still reports the default capacity.
+    assert_eq!(
+        None,
+        fixture
+            .queues
+            .available_output_request_slots()
+            .get(&never_seen)
+    );
+    assert_eq!(
+        DEFAULT_QUEUE_CAPACITY,
+        fixture
+            .queues
+            .available_output_request_slots_for(&never_seen)
+    );
+}
+
+#[test]
+fn test_reserved_slots_for() {
+    let mut fixture = CanisterQueuesFixture::new();
+    let third = canister_test_id(17);
+
+    // No queue pair, no reservations.
+    assert_eq!((0, 0), fixture.queues.reserved_slots_for(&fixture.other));
+
+    // Reserve 3 input queue slots and 2 output queue slots.
+    for _ in 0..3 {
+        fixture.push_output_request().unwrap();
+    }
+    for _ in 0..2 {
+        fixture.push_input_request().unwrap();
+    }
+
+    // With a single canister involved, the per-canister counts match the totals.
+    assert_eq!((3, 2), fixture.queues.reserved_slots_for(&fixture.other));
+    assert_eq!(
+        (
+            fixture.queues.input_queues_reserved_slots(),
+            fixture.queues.output_queues_reserved_slots()
+        ),
+        fixture.queues.reserved_slots_for(&fixture.other)
+    );
+
+    // Consuming a reserved slot is reflected.
+    fixture.push_input_response().unwrap();
+    assert_eq!((2, 2), fixture.queues.reserved_slots_for(&fixture.other));
+
+    // Other canisters have no reservations.
+    assert_eq!((0, 0), fixture.queues.reserved_slots_for(&third));
+}
+
 #[test]
 fn test_deadline_expired_input() {
     let mut fixture = CanisterQueuesFixture::new();
@@ -627,6 +827,63 @@ fn test_shed_largest_message() {
     assert!(!queues.shed_largest_message(&this, &local_canisters));
 }
 
+#[test]
+fn test_raw_queue_items_keep_stale_reference_until_popped() {
+    let this = canister_test_id(13);
+    const NO_LOCAL_CANISTERS: BTreeMap<CanisterId, CanisterState> = BTreeMap::new();
+
+    let mut queues = CanisterQueues::default();
+
+    // Enqueue three best-effort output requests, the middle one the largest.
+    for (callback, payload_size) in [(1, 100), (2, 1000), (3, 100)] {
+        queues
+            .push_output_request(
+                request_with_payload(payload_size, callback, SOME_DEADLINE).into(),
+                UNIX_EPOCH,
+            )
+            .unwrap();
+    }
+    let items = queues.raw_queue_items_for_testing(&this, MessageContext::Outbound);
+    assert_eq!(3, items.len());
+    assert!(items.iter().all(|item| !item.is_stale()));
+
+    // Shed the middle request: its reference is still in the queue, but stale.
+    assert!(queues.shed_largest_message(&this, &NO_LOCAL_CANISTERS));
+    let items = queues.raw_queue_items_for_testing(&this, MessageContext::Outbound);
+    assert_matches!(
+        items.as_slice(),
+        [
+            CanisterQueueItem::Live(_),
+            CanisterQueueItem::Stale(_),
+            CanisterQueueItem::Live(_)
+        ]
+    );
+    assert_eq!(
+        2,
+        queues.output_queue_iter_for_testing(&this).unwrap().count()
+    );
+
+    // Popping the first request also drops the stale reference behind it.
+    assert_matches!(queues.pop_canister_output(&this), Some(RequestOrResponse::Request(req)) if req.sender_reply_callback.get() == 1);
+    assert_matches!(
+        queues
+            .raw_queue_items_for_testing(&this, MessageContext::Outbound)
+            .as_slice(),
+        [CanisterQueueItem::Live(_)]
+    );
+
+    // Shedding generated a reject response in the input queue.
+    assert_matches!(
+        queues
+            .raw_queue_items_for_testing(&this, MessageContext::Inbound)
+            .as_slice(),
+        [CanisterQueueItem::Live(descriptor)] if descriptor.kind() == MessageKind::Response
+    );
+    assert!(queues
+        .raw_queue_items_for_testing(&canister_test_id(1), MessageContext::Outbound)
+        .is_empty());
+}
+
 #[test]
 fn test_shed_inbound_response() {
     let mut queues = CanisterQueues::default();
@@ -694,6 +951,285 @@ fn test_shed_inbound_response() {
     assert_eq!(0, queues.input_queues_response_count());
 }
 
+#[test]
+fn test_drop_queues_to() {
+    let this = canister_test_id(13);
+    let other = canister_test_id(11);
+    let third = canister_test_id(12);
+
+    let mut queues = CanisterQueues::default();
+
+    // Guaranteed response and best-effort input requests from `other`.
+    for deadline in [NO_DEADLINE, SOME_DEADLINE] {
+        queues
+            .push_input(
+                RequestBuilder::default()
+                    .sender(other)
+                    .receiver(this)
+                    .deadline(deadline)
+                    .build()
+                    .into(),
+                RemoteSubnet,
+            )
+            .unwrap();
+    }
+    // Guaranteed response and best-effort output requests to `other`.
+    for (callback, deadline) in [(1, NO_DEADLINE), (2, SOME_DEADLINE)] {
+        queues
+            .push_output_request(
+                RequestBuilder::default()
+                    .sender(this)
+                    .receiver(other)
+                    .sender_reply_callback(CallbackId::from(callback))
+                    .deadline(deadline)
+                    .build()
+                    .into(),
+                UNIX_EPOCH,
+            )
+            .unwrap();
+    }
+    // A best-effort response from `other`, consuming one reserved slot.
+    queues
+        .push_input(
+            ResponseBuilder::default()
+                .originator(this)
+                .respondent(other)
+                .originator_reply_callback(CallbackId::from(2))
+                .deadline(SOME_DEADLINE)
+                .build()
+                .into(),
+            RemoteSubnet,
+        )
+        .unwrap();
+    // And a guaranteed response input request from `third`.
+    queues
+        .push_input(
+            RequestBuilder::default()
+                .sender(third)
+                .receiver(this)
+                .build()
+                .into(),
+            RemoteSubnet,
+        )
+        .unwrap();
+
+    // Sanity check.
+    assert_eq!(3, queues.guaranteed_response_memory_reservations());
+    assert_eq!(1, queues.input_queues_reserved_slots());
+    assert_eq!(3, queues.output_queues_reserved_slots());
+    assert_eq!(4, queues.input_queues_message_count());
+    assert_eq!(2, queues.output_queues_message_count());
+    assert_eq!(
+        &VecDeque::from(vec![other, third]),
+        queues.remote_sender_schedule()
+    );
+
+    // Dropping a non-existent queue pair is a no-op.
+    let queues_before = queues.clone();
+    queues.drop_queues_to(&canister_test_id(14), 0);
+    assert_eq!(queues_before, queues);
+
+    // Drop the queues to / from `other`. The only open guaranteed response callback
+    // is that of the request still in the output queue.
+    queues.drop_queues_to(&other, 1);
+
+    assert_eq!(Ok(()), queues.test_invariants());
+    assert_eq!(Ok(()), queues.schedules_ok(&|_| RemoteSubnet));
+
+    // Only the request from `third` and its reservations are left.
+    assert_eq!(1, queues.guaranteed_response_memory_reservations());
+    assert_eq!(0, queues.input_queues_reserved_slots());
+    assert_eq!(1, queues.output_queues_reserved_slots());
+    assert_eq!(1, queues.input_queues_message_count());
+    assert_eq!(0, queues.input_queues_response_count());
+    assert_eq!(0, queues.output_queues_message_count());
+    assert_eq!(0, queues.best_effort_memory_usage());
+    assert!(queues.callbacks_with_enqueued_response.is_empty());
+    assert_eq!(
+        &VecDeque::from(vec![third]),
+        queues.remote_sender_schedule()
+    );
+    assert!(!queues.has_output());
+    assert_eq!(None, queues.available_output_request_slots().get(&other));
+
+    assert_matches!(queues.pop_input(), Some(CanisterInput::Request(request)) if request.sender == third);
+    assert_eq!(None, queues.pop_input());
+}
+
+/// Tests that `drop_queues_to()` releases the memory reservations of guaranteed
+/// response calls already in flight.
+#[test]
+fn test_drop_queues_to_with_in_flight_calls() {
+    let this = canister_test_id(13);
+    let other = canister_test_id(11);
+
+    let mut queues = CanisterQueues::default();
+
+    // Guaranteed response and best-effort calls to `other`, already routed.
+    for (callback, deadline) in [(1, NO_DEADLINE), (2, SOME_DEADLINE)] {
+        queues
+            .push_output_request(
+                RequestBuilder::default()
+                    .sender(this)
+                    .receiver(other)
+                    .sender_reply_callback(CallbackId::from(callback))
+                    .deadline(deadline)
+                    .build()
+                    .into(),
+                UNIX_EPOCH,
+            )
+            .unwrap();
+        queues.output_into_iter().pop().unwrap();
+    }
+    assert_eq!(1, queues.guaranteed_response_memory_reservations());
+    assert_eq!(2, queues.input_queues_reserved_slots());
+
+    // Drop the queues to `other`, with one open guaranteed response callback.
+    queues.drop_queues_to(&other, 1);
+
+    assert_eq!(Ok(()), queues.test_invariants());
+    assert_eq!(Ok(()), queues.schedules_ok(&|_| RemoteSubnet));
+    assert_eq!(0, queues.guaranteed_response_memory_reservations());
+    assert_eq!(0, queues.input_queues_reserved_slots());
+    assert_eq!(0, queues.output_queues_reserved_slots());
+    assert!(queues.canister_queues.is_empty());
+}
+
+/// Tests that `drop_queues_to()` retains the output queue slots reserved for
+/// inbound requests already executing, so they can still be responded to.
+#[test]
+fn test_drop_queues_to_with_executing_request() {
+    let this = canister_test_id(13);
+    let other = canister_test_id(11);
+
+    let mut queues = CanisterQueues::default();
+
+    // A guaranteed response request from `other`, popped for execution; and
+    // another one, still enqueued.
+    for _ in 0..2 {
+        queues
+            .push_input(
+                RequestBuilder::default()
+                    .sender(other)
+                    .receiver(this)
+                    .build()
+                    .into(),
+                RemoteSubnet,
+            )
+            .unwrap();
+    }
+    let Some(CanisterInput::Request(request)) = queues.pop_input() else {
+        panic!("Expected a request");
+    };
+    assert_eq!(2, queues.guaranteed_response_memory_reservations());
+    assert_eq!(2, queues.output_queues_reserved_slots());
+
+    // Drop the queues to / from `other`.
+    queues.drop_queues_to(&other, 0);
+
+    // Only the enqueued request and its reservations were dropped.
+    assert_eq!(Ok(()), queues.test_invariants());
+    assert_eq!(Ok(()), queues.schedules_ok(&|_| RemoteSubnet));
+    assert_eq!(1, queues.guaranteed_response_memory_reservations());
+    assert_eq!(1, queues.output_queues_reserved_slots());
+    assert_eq!(0, queues.input_queues_message_count());
+    assert_eq!(None, queues.pop_input());
+
+    // The executing request can still be responded to.
+    queues.push_output_response(Arc::new(
+        ResponseBuilder::default()
+            .originator(other)
+            .respondent(this)
+            .originator_reply_callback(request.sender_reply_callback)
+            .build(),
+    ));
+    assert_eq!(Ok(()), queues.test_invariants());
+    assert_eq!(0, queues.guaranteed_response_memory_reservations());
+    assert_eq!(0, queues.output_queues_reserved_slots());
+    assert_eq!(1, queues.output_queues_message_count());
+
+    // And the queue pair is garbage collected once the response was routed.
+    queues.output_into_iter().pop().unwrap();
+    queues.garbage_collect();
+    assert!(queues.canister_queues.is_empty());
+}
+
+#[test]
+fn test_total_message_count() {
+    let this = canister_test_id(13);
+    let other = canister_test_id(11);
+    let mut queues = CanisterQueues::default();
+    assert_eq!(0, queues.total_message_count());
+
+    // Two ingress messages.
+    for _ in 0..2 {
+        queues.push_ingress(IngressBuilder::default().receiver(this).build());
+    }
+    // One input request.
+    queues
+        .push_input(
+            RequestBuilder::default()
+                .sender(other)
+                .receiver(this)
+                .build()
+                .into(),
+            RemoteSubnet,
+        )
+        .unwrap();
+    // Three output requests, each reserving an input queue slot.
+    for _ in 0..3 {
+        queues
+            .push_output_request(
+                RequestBuilder::default()
+                    .sender(this)
+                    .receiver(other)
+                    .build()
+                    .into(),
+                UNIX_EPOCH,
+            )
+            .unwrap();
+    }
+
+    assert_eq!(2, queues.ingress_queue_message_count());
+    assert_eq!(1, queues.input_queues_message_count());
+    assert_eq!(3, queues.output_queues_message_count());
+    // Reserved slots are not counted.
+    assert_eq!(3, queues.input_queues_reserved_slots());
+    assert_eq!(6, queues.total_message_count());
+}
+
+#[test]
+fn test_can_induct_message_to_self() {
+    let mut fixture =
+        CanisterQueuesFixture::new_with_ids(canister_test_id(13), canister_test_id(13));
+    let this = fixture.this;
+
+    // Nothing to induct.
+    assert!(!fixture.queues.can_induct_message_to_self(this));
+    assert_eq!(Err(()), fixture.queues.induct_message_to_self(this));
+
+    // Fill the input queue with requests, then enqueue an output request to self.
+    for _ in 0..DEFAULT_QUEUE_CAPACITY {
+        fixture.push_input_request().unwrap();
+    }
+    fixture.push_output_request().unwrap();
+
+    // The input queue is full, so the request cannot be inducted.
+    let queues_before = fixture.queues.clone();
+    assert!(!fixture.queues.can_induct_message_to_self(this));
+    // And probing did not mutate anything.
+    assert_eq!(queues_before, fixture.queues);
+    assert_eq!(Err(()), fixture.queues.induct_message_to_self(this));
+
+    // Free up an input queue slot.
+    fixture.pop_input().unwrap();
+    assert!(fixture.queues.can_induct_message_to_self(this));
+    assert_eq!(Ok(()), fixture.queues.induct_message_to_self(this));
+
+    // The output queue is now empty.
+    assert!(!fixture.queues.can_induct_message_to_self(this));
+}
+
 /// Enqueues 3 requests for the same canister and consumes them.
 #[test]
 fn test_message_picking_round_robin_on_one_queue() {
@@ -1077,6 +1613,69 @@ fn test_split_input_schedules() {
     );
 }
 
+#[test]
+fn test_split_input_schedules_summary() {
+    let other_1 = canister_test_id(1);
+    let other_2 = canister_test_id(2);
+    let other_3 = canister_test_id(3);
+    let other_4 = canister_test_id(4);
+
+    let mut fixture = CanisterQueuesMultiFixture::new();
+    let this = fixture.this;
+
+    // 3 local input queues (`other_1`, `this`, `other_2`) and 2 remote ones
+    // (`other_3`, `other_4`).
+    fixture.push_input_request(other_1, LocalSubnet).unwrap();
+    fixture.push_input_request(this, LocalSubnet).unwrap();
+    fixture.push_input_request(other_2, LocalSubnet).unwrap();
+    fixture.push_input_request(other_3, RemoteSubnet).unwrap();
+    fixture.push_input_request(other_4, RemoteSubnet).unwrap();
+
+    // After the migration, `other_1` and `other_3` are on the subnet.
+    let new_canister = |canister_id: CanisterId| {
+        let system_state = SystemState::new_running_for_testing(
+            canister_id,
+            canister_id.get(),
+            Cycles::zero(),
+            0.into(),
+        );
+        CanisterState::new(system_state, None, SchedulerState::new(UNIX_EPOCH))
+    };
+    let local_canisters = btreemap! {
+        other_1 => new_canister(other_1),
+        other_3 => new_canister(other_3),
+    };
+
+    let summary = fixture
+        .queues
+        .split_input_schedules(&this, &local_canisters);
+
+    // `other_3` moved to the local schedule, `other_2` to the remote one.
+    assert_eq!(
+        InputScheduleSplitSummary {
+            moved_to_local: 1,
+            moved_to_remote: 1,
+            unchanged: 3,
+        },
+        summary
+    );
+    assert_eq!(vec![other_1, this, other_3], fixture.local_schedule());
+    assert_eq!(vec![other_2, other_4], fixture.remote_schedule());
+
+    // Splitting again moves nothing.
+    let summary = fixture
+        .queues
+        .split_input_schedules(&this, &local_canisters);
+    assert_eq!(
+        InputScheduleSplitSummary {
+            moved_to_local: 0,
+            moved_to_remote: 0,
+            unchanged: 5,
+        },
+        summary
+    );
+}
+
 #[test]
 fn test_peek_input_round_robin() {
     let mut queues = CanisterQueues::default();
@@ -1300,6 +1899,46 @@ fn test_pop_input_with_stale_references() {
     assert!(queues.store.is_empty());
 }
 
+#[test]
+fn test_queue_depths_with_stale_references() {
+    let (mut queues, _requests) = new_queues_with_stale_references();
+
+    // Two output requests to canister 3.
+    for callback in 1..=2 {
+        let request = RequestBuilder::default()
+            .sender(canister_test_id(13))
+            .receiver(canister_test_id(3))
+            .sender_reply_callback(CallbackId::from(callback))
+            .build();
+        queues
+            .push_output_request(request.into(), UNIX_EPOCH)
+            .unwrap();
+    }
+
+    // Canister 1's only request and the stale reference at the back of canister
+    // 2's input queue are not counted.
+    assert_eq!(
+        btreemap! {
+            canister_test_id(1) => (0, 0),
+            canister_test_id(2) => (1, 0),
+            canister_test_id(3) => (1, 2),
+        },
+        queues.queue_depths()
+    );
+    assert_eq!(
+        queues.input_queues_message_count(),
+        queues.queue_depths().values().map(|(input, _)| input).sum()
+    );
+    assert_eq!(
+        queues.output_queues_message_count(),
+        queues
+            .queue_depths()
+            .values()
+            .map(|(_, output)| output)
+            .sum()
+    );
+}
+
 #[test]
 fn test_skip_input_with_stale_references() {
     let (mut queues, requests) = new_queues_with_stale_references();
@@ -1641,6 +2280,63 @@ fn test_output_into_iter() {
     assert!(queues.store.is_empty());
 }
 
+#[test]
+fn test_iter_output_messages() {
+    let this = canister_test_id(13);
+    let other_1 = canister_test_id(1);
+    let other_2 = canister_test_id(2);
+
+    let mut queues = CanisterQueues::default();
+    assert_eq!(0, queues.iter_output_messages().count());
+
+    // A best-effort request in the middle of `other_1`'s queue, so that it leaves
+    // behind a stale reference when it times out.
+    let destinations = [
+        (other_1, NO_DEADLINE),
+        (other_2, NO_DEADLINE),
+        (other_1, coarse_time(1000)),
+        (other_1, NO_DEADLINE),
+    ];
+    for (i, (id, deadline)) in destinations.iter().enumerate() {
+        queues
+            .push_output_request(
+                RequestBuilder::default()
+                    .sender(this)
+                    .receiver(*id)
+                    .method_payload(vec![i as u8])
+                    .deadline(*deadline)
+                    .build()
+                    .into(),
+                UNIX_EPOCH,
+            )
+            .expect("could not push");
+    }
+    assert_eq!(
+        1,
+        queues.time_out_messages(coarse_time(1001).into(), &this, &BTreeMap::new())
+    );
+    assert_eq!(3, queues.output_message_count());
+
+    let payloads = |queues: &CanisterQueues| {
+        queues
+            .iter_output_messages()
+            .map(|(receiver, msg)| match msg {
+                RequestOrResponse::Request(msg) => {
+                    assert_eq!(*receiver, msg.receiver);
+                    (*receiver, msg.method_payload.clone())
+                }
+                msg => panic!("unexpected message: {:?}", msg),
+            })
+            .collect::<Vec<_>>()
+    };
+    let expected = vec![(other_1, vec![0]), (other_1, vec![3]), (other_2, vec![1])];
+    assert_eq!(expected, payloads(&queues));
+
+    // Nothing was consumed: iterating again yields the same messages.
+    assert_eq!(expected, payloads(&queues));
+    assert_eq!(3, queues.output_message_count());
+}
+
 #[test]
 fn test_peek_canister_input_does_not_affect_schedule() {
     let mut queues = CanisterQueues::default();
@@ -1815,6 +2511,43 @@ fn encode_roundtrip() {
     assert_eq!(queues, decoded);
 }
 
+/// Tests that the response deduplication setting survives an encode-decode
+/// roundtrip, including with duplicate responses enqueued.
+#[test]
+fn encode_roundtrip_without_response_deduplication() {
+    let mut queues = CanisterQueues::with_response_deduplication(false);
+
+    // Enqueue two responses with the same callback ID.
+    for callback in [1, 2] {
+        queues
+            .push_output_request(request(callback, SOME_DEADLINE).into(), UNIX_EPOCH)
+            .unwrap();
+        queues.output_into_iter().pop().unwrap();
+    }
+    for _ in 0..2 {
+        queues
+            .push_input(response(1, SOME_DEADLINE).into(), LocalSubnet)
+            .unwrap();
+    }
+    assert_eq!(2, queues.input_queues_response_count());
+
+    let encoded: pb_queues::CanisterQueues = (&queues).into();
+    let mut decoded: CanisterQueues = (encoded, &StrictMetrics as &dyn CheckpointLoadingMetrics)
+        .try_into()
+        .unwrap();
+    assert_eq!(queues, decoded);
+    assert!(!decoded.deduplicates_responses());
+
+    // Even with no messages left, the setting is still persisted.
+    while decoded.pop_input().is_some() {}
+    decoded.garbage_collect();
+    let encoded: pb_queues::CanisterQueues = (&decoded).into();
+    let decoded: CanisterQueues = (encoded, &StrictMetrics as &dyn CheckpointLoadingMetrics)
+        .try_into()
+        .unwrap();
+    assert!(!decoded.deduplicates_responses());
+}
+
 /// Tests that serializing an empty `CanisterQueues` produces zero bytes.
 #[test]
 fn encode_empty() {
@@ -1871,6 +2604,38 @@ fn decode_invalid_input_schedule() {
     assert_eq!(queues, decoded);
 }
 
+#[test]
+fn test_validate() {
+    let this = canister_test_id(13);
+    let other = canister_test_id(11);
+    const NO_LOCAL_CANISTERS: BTreeMap<CanisterId, CanisterState> = BTreeMap::new();
+
+    let mut queues = CanisterQueues::default();
+    queues
+        .push_input(
+            RequestBuilder::default().sender(this).build().into(),
+            LocalSubnet,
+        )
+        .unwrap();
+    queues
+        .push_input(
+            RequestBuilder::default().sender(other).build().into(),
+            RemoteSubnet,
+        )
+        .unwrap();
+    assert_eq!(Ok(()), queues.validate(&this, &NO_LOCAL_CANISTERS));
+
+    // Drop `other` from the input schedule, even though it has an enqueued message.
+    queues.input_schedule.remove(&other);
+
+    let err = queues.validate(&this, &NO_LOCAL_CANISTERS).unwrap_err();
+    assert!(
+        err.contains(&format!("{:?}", other)) && err.contains("remote_sender_schedule"),
+        "{}",
+        err
+    );
+}
+
 /// Tests that serializing a `CanisterQueues` with an empty but non-default pool
 /// preserves the non-default pool.
 #[test]
@@ -2230,6 +2995,7 @@ fn test_stats_best_effort() {
         &MessageStats {
             size_bytes: 2 * (request_size_bytes + response_size_bytes),
             best_effort_message_bytes: 2 * (request_size_bytes + response_size_bytes),
+            best_effort_message_count: 4,
             guaranteed_responses_size_bytes: 0,
             oversized_guaranteed_requests_extra_bytes: 0,
             inbound_size_bytes: request_size_bytes + response_size_bytes,
@@ -2238,6 +3004,7 @@ fn test_stats_best_effort() {
             inbound_guaranteed_request_count: 0,
             inbound_guaranteed_response_count: 0,
             outbound_message_count: 2,
+            outbound_guaranteed_request_count: 0,
         },
         queues.message_stats()
     );
@@ -2259,6 +3026,7 @@ fn test_stats_best_effort() {
         &MessageStats {
             size_bytes: request_size_bytes + response_size_bytes,
             best_effort_message_bytes: request_size_bytes + response_size_bytes,
+            best_effort_message_count: 2,
             guaranteed_responses_size_bytes: 0,
             oversized_guaranteed_requests_extra_bytes: 0,
             inbound_size_bytes: response_size_bytes,
@@ -2267,6 +3035,7 @@ fn test_stats_best_effort() {
             inbound_guaranteed_request_count: 0,
             inbound_guaranteed_response_count: 0,
             outbound_message_count: 1,
+            outbound_guaranteed_request_count: 0,
         },
         queues.message_stats()
     );
@@ -2295,6 +3064,7 @@ fn test_stats_best_effort() {
         &message_pool::MessageStats {
             size_bytes: reject_response_size_bytes,
             best_effort_message_bytes: reject_response_size_bytes,
+            best_effort_message_count: 1,
             guaranteed_responses_size_bytes: 0,
             oversized_guaranteed_requests_extra_bytes: 0,
             inbound_size_bytes: reject_response_size_bytes,
@@ -2303,6 +3073,7 @@ fn test_stats_best_effort() {
             inbound_guaranteed_request_count: 0,
             inbound_guaranteed_response_count: 0,
             outbound_message_count: 0,
+            outbound_guaranteed_request_count: 0,
         },
         queues.message_stats()
     );
@@ -2386,6 +3157,7 @@ fn test_stats_guaranteed_response() {
         &MessageStats {
             size_bytes: 2 * (request_size_bytes + response_size_bytes),
             best_effort_message_bytes: 0,
+            best_effort_message_count: 0,
             guaranteed_responses_size_bytes: 2 * response_size_bytes,
             oversized_guaranteed_requests_extra_bytes: 0,
             inbound_size_bytes: request_size_bytes + response_size_bytes,
@@ -2394,6 +3166,7 @@ fn test_stats_guaranteed_response() {
             inbound_guaranteed_request_count: 1,
             inbound_guaranteed_response_count: 1,
             outbound_message_count: 2,
+            outbound_guaranteed_request_count: 1,
         },
         queues.message_stats()
     );
@@ -2415,6 +3188,7 @@ fn test_stats_guaranteed_response() {
         &MessageStats {
             size_bytes: request_size_bytes + response_size_bytes,
             best_effort_message_bytes: 0,
+            best_effort_message_count: 0,
             guaranteed_responses_size_bytes: response_size_bytes,
             oversized_guaranteed_requests_extra_bytes: 0,
             inbound_size_bytes: response_size_bytes,
@@ -2423,6 +3197,7 @@ fn test_stats_guaranteed_response() {
             inbound_guaranteed_request_count: 0,
             inbound_guaranteed_response_count: 1,
             outbound_message_count: 1,
+            outbound_guaranteed_request_count: 1,
         },
         queues.message_stats()
     );
@@ -2516,6 +3291,7 @@ fn test_stats_oversized_requests() {
         &MessageStats {
             size_bytes: 2 * (best_effort_size_bytes + guaranteed_size_bytes),
             best_effort_message_bytes: 2 * best_effort_size_bytes,
+            best_effort_message_count: 2,
             guaranteed_responses_size_bytes: 0,
             oversized_guaranteed_requests_extra_bytes: 2 * guaranteed_extra_bytes,
             inbound_size_bytes: best_effort_size_bytes + guaranteed_size_bytes,
@@ -2524,6 +3300,7 @@ fn test_stats_oversized_requests() {
             inbound_guaranteed_request_count: 1,
             inbound_guaranteed_response_count: 0,
             outbound_message_count: 2,
+            outbound_guaranteed_request_count: 1,
         },
         queues.message_stats()
     );
@@ -2545,6 +3322,7 @@ fn test_stats_oversized_requests() {
         &MessageStats {
             size_bytes: best_effort_size_bytes + guaranteed_size_bytes,
             best_effort_message_bytes: best_effort_size_bytes,
+            best_effort_message_count: 1,
             guaranteed_responses_size_bytes: 0,
             oversized_guaranteed_requests_extra_bytes: guaranteed_extra_bytes,
             inbound_size_bytes: 0,
@@ -2553,6 +3331,7 @@ fn test_stats_oversized_requests() {
             inbound_guaranteed_request_count: 0,
             inbound_guaranteed_response_count: 0,
             outbound_message_count: 2,
+            outbound_guaranteed_request_count: 1,
         },
         queues.message_stats()
     );
@@ -2587,6 +3366,129 @@ fn test_stats_oversized_requests() {
     assert_eq!(&MessageStats::default(), queues.message_stats());
 }
 
+#[test]
+fn test_message_count_by_class() {
+    let mut queues = CanisterQueues::default();
+    assert_eq!((0, 0), queues.input_queues_message_count_by_class());
+    assert_eq!((0, 0), queues.output_queues_message_count_by_class());
+
+    let t10 = coarse_time(10);
+
+    // Make a reservation for an inbound guaranteed response.
+    queues
+        .push_output_request(request(4, NO_DEADLINE).into(), UNIX_EPOCH)
+        .unwrap();
+    queues.output_into_iter().next().unwrap();
+
+    // Two best-effort and two guaranteed response messages in input queues.
+    queues
+        .push_input(request(1, t10).into(), LocalSubnet)
+        .unwrap();
+    queues
+        .push_input(request(2, t10).into(), LocalSubnet)
+        .unwrap();
+    queues
+        .push_input(request(3, NO_DEADLINE).into(), LocalSubnet)
+        .unwrap();
+    queues
+        .push_input(response(4, NO_DEADLINE).into(), LocalSubnet)
+        .unwrap();
+
+    // One best-effort and two guaranteed response requests in output queues.
+    queues
+        .push_output_request(request(5, t10).into(), UNIX_EPOCH)
+        .unwrap();
+    queues
+        .push_output_request(request(6, NO_DEADLINE).into(), UNIX_EPOCH)
+        .unwrap();
+    queues
+        .push_output_request(request(7, NO_DEADLINE).into(), UNIX_EPOCH)
+        .unwrap();
+
+    assert_eq!((2, 2), queues.input_queues_message_count_by_class());
+    assert_eq!((1, 2), queues.output_queues_message_count_by_class());
+    assert_eq!(4, queues.input_queues_message_count());
+    assert_eq!(3, queues.output_queues_message_count());
+
+    // Pop a best-effort request from the input queue.
+    assert_eq!(
+        Some(CanisterInput::Request(request(1, t10).into())),
+        queues.pop_input()
+    );
+    assert_eq!((1, 2), queues.input_queues_message_count_by_class());
+    assert_eq!((1, 2), queues.output_queues_message_count_by_class());
+}
+
+#[test]
+fn test_memory_usage_breakdown() {
+    let mut queues = CanisterQueues::default();
+    assert_eq!(MemoryBreakdown::default(), queues.memory_usage_breakdown());
+
+    let t10 = coarse_time(10);
+
+    // Make a reservation for an inbound guaranteed response.
+    queues
+        .push_output_request(request(1, NO_DEADLINE).into(), UNIX_EPOCH)
+        .unwrap();
+    queues.output_into_iter().next().unwrap();
+
+    // A best-effort and a guaranteed response request in input queues; one
+    // guaranteed response.
+    let best_effort = request(2, t10);
+    let guaranteed_response = response_with_payload(1000, 1, NO_DEADLINE);
+    queues
+        .push_input(best_effort.clone().into(), LocalSubnet)
+        .unwrap();
+    queues
+        .push_input(request(3, NO_DEADLINE).into(), LocalSubnet)
+        .unwrap();
+    queues
+        .push_input(guaranteed_response.clone().into(), LocalSubnet)
+        .unwrap();
+
+    // An oversized guaranteed response request in an output queue.
+    let oversized = request_with_payload(
+        MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64 as usize + 1000,
+        4,
+        NO_DEADLINE,
+    );
+    queues
+        .push_output_request(oversized.clone().into(), UNIX_EPOCH)
+        .unwrap();
+
+    queues.set_stream_guaranteed_responses_size_bytes(123);
+
+    let breakdown = queues.memory_usage_breakdown();
+    assert_eq!(
+        MemoryBreakdown {
+            best_effort_bytes: best_effort.count_bytes(),
+            guaranteed_response_bytes: guaranteed_response.count_bytes(),
+            guaranteed_reservations_bytes: 2 * MAX_RESPONSE_COUNT_BYTES,
+            oversized_request_extra_bytes: oversized.count_bytes() - MAX_RESPONSE_COUNT_BYTES,
+            stream_guaranteed_response_bytes: 123,
+            reserved_slots: 3,
+        },
+        breakdown
+    );
+
+    // Consistent with the individual getters.
+    assert_eq!(
+        queues.best_effort_memory_usage(),
+        breakdown.best_effort_bytes
+    );
+    assert_eq!(
+        queues.guaranteed_response_memory_usage(),
+        breakdown.guaranteed_response_bytes
+            + breakdown.guaranteed_reservations_bytes
+            + breakdown.oversized_request_extra_bytes
+            + breakdown.stream_guaranteed_response_bytes
+    );
+    assert_eq!(
+        queues.input_queues_reserved_slots() + queues.output_queues_reserved_slots(),
+        breakdown.reserved_slots
+    );
+}
+
 /// Simulates sending an outgoing request and receiving an incoming response,
 /// calling `garbage_collect()` throughout. This is always a no-op, until after
 /// the response was consumed, when the queue pair is GC-ed and all fields are
@@ -2680,6 +3582,38 @@ fn test_garbage_collect_restores_defaults() {
     assert_eq!(0, pb_queues::CanisterQueues::from(&queues).encoded_len());
 }
 
+#[test]
+fn test_clear_ingress_from() {
+    let mut queues = CanisterQueues::default();
+    let expiry_time = expiry_time_from_now();
+    let ingress = |source: u64, message_id: u64| Ingress {
+        source: user_test_id(source),
+        receiver: canister_test_id(13),
+        method_name: String::from("test"),
+        method_payload: Vec::new(),
+        effective_canister_id: None,
+        message_id: message_test_id(message_id),
+        expiry_time,
+    };
+    queues.push_ingress(ingress(1, 1));
+    queues.push_ingress(ingress(2, 2));
+    queues.push_ingress(ingress(1, 3));
+
+    let cleared = queues.clear_ingress_from(user_test_id(1).get());
+    assert_eq!(
+        vec![Arc::new(ingress(1, 1)), Arc::new(ingress(1, 3))],
+        cleared
+    );
+
+    // Only the ingress message from the other user is left.
+    assert_eq!(1, queues.ingress_queue_message_count());
+    assert_eq!(
+        Some(CanisterInput::Ingress(Arc::new(ingress(2, 2)))),
+        queues.pop_input()
+    );
+    assert!(!queues.has_input());
+}
+
 #[test]
 fn test_reject_subnet_output_request() {
     let this = canister_test_id(1);
@@ -2715,6 +3649,67 @@ fn test_reject_subnet_output_request() {
     assert!(queues.store.is_empty());
 }
 
+#[test]
+fn test_reject_subnet_output_request_with_ineligible_receiver() {
+    let this = canister_test_id(1);
+    let other = canister_test_id(2);
+    let subnet_id = canister_test_id(3).get();
+
+    let request = RequestBuilder::default()
+        .sender(this)
+        .receiver(other)
+        .build();
+    let reject_context = RejectContext::new(ic_error_types::RejectCode::DestinationInvalid, "");
+
+    let mut queues = CanisterQueues::default();
+
+    // Rejecting a request to a receiver that is neither `IC_00` nor a subnet
+    // returns an error instead of panicking.
+    assert_eq!(
+        Err(StateError::InvalidSubnetRejection { receiver: other }),
+        queues.reject_subnet_output_request(request, reject_context, &[subnet_id])
+    );
+
+    // And leaves the queues untouched.
+    assert!(!queues.has_input());
+    assert!(queues.canister_queues.is_empty());
+    assert!(queues.store.is_empty());
+}
+
+#[test]
+fn test_reject_subnet_output_request_truncates_reject_message() {
+    let this = canister_test_id(1);
+
+    let request = RequestBuilder::default()
+        .sender(this)
+        .receiver(IC_00)
+        .build();
+    let reject_context = RejectContext::new(
+        ic_error_types::RejectCode::DestinationInvalid,
+        "x".repeat(2 * MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN),
+    );
+
+    let mut queues = CanisterQueues::default();
+    queues
+        .reject_subnet_output_request(request, reject_context, &[])
+        .unwrap();
+
+    // The enqueued reject response carries a truncated reject message.
+    match queues.pop_input().unwrap() {
+        CanisterInput::Response(response) => match &response.response_payload {
+            Payload::Reject(context) => {
+                assert_eq!(
+                    ic_error_types::RejectCode::DestinationInvalid,
+                    context.code()
+                );
+                assert!(context.message().len() <= MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN);
+            }
+            payload => panic!("Expected a reject payload, got {:?}", payload),
+        },
+        input => panic!("Expected a response, got {:?}", input),
+    }
+}
+
 #[test]
 fn test_output_queues_for_each() {
     let this = canister_test_id(13);
@@ -3202,6 +4197,117 @@ fn has_expired_deadlines_reports_correctly() {
     assert!(canister_queues.has_expired_deadlines(time101));
 }
 
+/// Tests that `next_timeout` reports the earliest time at which
+/// `has_expired_deadlines` returns true, across message kinds and classes.
+#[test]
+fn next_timeout_reports_correctly() {
+    let mut canister_queues = CanisterQueues::default();
+
+    let assert_next_timeout = |canister_queues: &CanisterQueues, expected: Option<Time>| {
+        assert_eq!(expected, canister_queues.next_timeout());
+        if let Some(next_timeout) = expected {
+            let just_before =
+                Time::from_nanos_since_unix_epoch(next_timeout.as_nanos_since_unix_epoch() - 1);
+            assert!(!canister_queues.has_expired_deadlines(just_before));
+            assert!(canister_queues.has_expired_deadlines(next_timeout));
+        }
+    };
+
+    // Empty queues never time out.
+    assert_next_timeout(&canister_queues, None);
+
+    // Guaranteed response inbound requests and best-effort inbound responses do
+    // not time out either.
+    canister_queues
+        .push_input(request(1, NO_DEADLINE).into(), LocalSubnet)
+        .unwrap();
+    canister_queues
+        .push_output_request(request(2, coarse_time(10)).into(), UNIX_EPOCH)
+        .unwrap();
+    canister_queues.output_into_iter().next().unwrap();
+    canister_queues
+        .push_input(response(2, coarse_time(10)).into(), LocalSubnet)
+        .unwrap();
+    assert_next_timeout(&canister_queues, None);
+
+    // A guaranteed response outbound request times out after `REQUEST_LIFETIME`.
+    let time50 = Time::from_secs_since_unix_epoch(50).unwrap();
+    canister_queues
+        .push_output_request(request(3, NO_DEADLINE).into(), time50)
+        .unwrap();
+    let t51_plus_lifetime = Time::from_secs_since_unix_epoch(51).unwrap() + REQUEST_LIFETIME;
+    assert_next_timeout(&canister_queues, Some(t51_plus_lifetime));
+
+    // An earlier best-effort inbound request takes precedence.
+    canister_queues
+        .push_input(request(4, coarse_time(100)).into(), LocalSubnet)
+        .unwrap();
+    let time101 = Time::from_secs_since_unix_epoch(101).unwrap();
+    assert_next_timeout(&canister_queues, Some(time101));
+}
+
+/// Tests that `iter_by_deadline` lists live messages in timeout order.
+#[test]
+fn iter_by_deadline_lists_messages_in_timeout_order() {
+    let mut canister_queues = CanisterQueues::default();
+    assert_eq!(0, canister_queues.iter_by_deadline().count());
+
+    // A best-effort inbound request, popped (and thus removed from the pool).
+    canister_queues
+        .push_input(request(1, coarse_time(30)).into(), LocalSubnet)
+        .unwrap();
+    assert!(canister_queues.pop_input().is_some());
+
+    // A best-effort inbound request, a guaranteed response outbound request and a
+    // best-effort outbound request.
+    canister_queues
+        .push_input(request(2, coarse_time(100)).into(), LocalSubnet)
+        .unwrap();
+    let time50 = Time::from_secs_since_unix_epoch(50).unwrap();
+    canister_queues
+        .push_output_request(request(3, NO_DEADLINE).into(), time50)
+        .unwrap();
+    canister_queues
+        .push_output_request(request(4, coarse_time(20)).into(), time50)
+        .unwrap();
+
+    let expected: Vec<(Time, RequestOrResponse)> = vec![
+        (coarse_time(20).into(), request(4, coarse_time(20)).into()),
+        (time50 + REQUEST_LIFETIME, request(3, NO_DEADLINE).into()),
+        (coarse_time(100).into(), request(2, coarse_time(100)).into()),
+    ];
+    assert_eq!(
+        expected
+            .iter()
+            .map(|(t, msg)| (*t, msg))
+            .collect::<Vec<_>>(),
+        canister_queues.iter_by_deadline().collect::<Vec<_>>()
+    );
+}
+
+/// Tests that the request lifetime set via `set_request_lifetime()` survives
+/// garbage collecting empty queues (which resets the message store).
+#[test]
+fn set_request_lifetime_survives_garbage_collect() {
+    let request_lifetime = Duration::from_secs(42);
+    let mut canister_queues = CanisterQueues::default();
+    canister_queues.set_request_lifetime(request_lifetime);
+
+    canister_queues.garbage_collect();
+
+    let time50 = Time::from_secs_since_unix_epoch(50).unwrap();
+    canister_queues
+        .push_output_request(request(1, NO_DEADLINE).into(), time50)
+        .unwrap();
+    assert_eq!(
+        vec![time50 + request_lifetime],
+        canister_queues
+            .iter_by_deadline()
+            .map(|(deadline, _)| deadline)
+            .collect::<Vec<_>>()
+    );
+}
+
 /// Tests `time_out_messages` on an instance of `CanisterQueues` that contains exactly 4 output messages.
 /// - A guaranteed response output request addressed to self.
 /// - A best-effort output request addressed to a local canister.
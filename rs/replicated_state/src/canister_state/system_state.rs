@@ -9,7 +9,8 @@ use crate::metadata_state::subnet_call_context_manager::InstallCodeCallId;
 use crate::page_map::PageAllocatorFileDescriptor;
 use crate::replicated_state::MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN;
 use crate::{
-    CanisterQueues, CanisterState, CheckpointLoadingMetrics, InputQueueType, PageMap, StateError,
+    CanisterQueues, CanisterState, CheckpointLoadingMetrics, InputQueueType,
+    InputScheduleSplitSummary, PageMap, StateError,
 };
 pub use call_context_manager::{CallContext, CallContextAction, CallContextManager, CallOrigin};
 use ic_base_types::NumSeconds;
@@ -18,7 +19,7 @@ use ic_error_types::RejectCode;
 use ic_interfaces::execution_environment::ExecutionRoundType;
 use ic_logger::{error, ReplicaLogger};
 use ic_management_canister_types::{
-    CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, LogVisibilityV2,
+    CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, LogVisibilityV2, IC_00,
 };
 use ic_protobuf::proxy::{try_from_option_field, ProxyDecodeError};
 use ic_protobuf::state::canister_state_bits::v1 as pb;
@@ -1340,6 +1341,12 @@ impl SystemState {
             "Expected `Request` to have been sent from canister ID {}, but instead got {}",
             self.canister_id, request.sender
         );
+        // Catch callers rejecting non-management requests in tests, but only
+        // return an error in production.
+        debug_assert!(
+            request.receiver == IC_00 || subnet_ids.contains(&request.receiver.get()),
+            "reject_subnet_output_request can only be used to reject management canister requests"
+        );
         self.queues
             .reject_subnet_output_request(request, reject_context, subnet_ids)
     }
@@ -1522,6 +1529,101 @@ impl SystemState {
         }
     }
 
+    /// Pushes a batch of `RequestOrResponse` messages into the induction pool,
+    /// atomically: either all messages are handled as if by `push_input()` (in
+    /// order), or none are and the state is left unmodified.
+    ///
+    /// Best-effort responses whose callback is gone are silently dropped, same as
+    /// with `push_input()`. The subnet memory limit is checked for the batch as a
+    /// whole and conservatively, i.e. without crediting the memory reservations
+    /// consumed by guaranteed responses in the batch.
+    ///
+    /// Updates `subnet_available_memory` to reflect any change in memory usage.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `StateError` that `push_input()` would have produced for the
+    /// first message that could not be pushed (see `push_input()`), along with
+    /// all the messages in the batch.
+    pub(crate) fn push_input_batch(
+        &mut self,
+        msgs: Vec<(RequestOrResponse, InputQueueType)>,
+        subnet_available_memory: &mut i64,
+        own_subnet_type: SubnetType,
+    ) -> Result<(), (StateError, Vec<RequestOrResponse>)> {
+        for (msg, _) in &msgs {
+            assert_eq!(
+                msg.receiver(),
+                self.canister_id,
+                "Expected `RequestOrResponse` to be targeted to canister ID {}, but instead got {}",
+                self.canister_id,
+                msg.receiver()
+            );
+        }
+
+        let call_context_manager = match &self.status {
+            // Requests and responses are both rejected when stopped.
+            CanisterStatus::Stopped { .. } => {
+                return Err((
+                    StateError::CanisterStopped(self.canister_id()),
+                    batch_messages(msgs),
+                ));
+            }
+
+            // Requests (only) are rejected while stopping.
+            CanisterStatus::Stopping {
+                call_context_manager,
+                ..
+            } => {
+                if msgs
+                    .iter()
+                    .any(|(msg, _)| matches!(msg, RequestOrResponse::Request(_)))
+                {
+                    return Err((
+                        StateError::CanisterStopping(self.canister_id()),
+                        batch_messages(msgs),
+                    ));
+                }
+                call_context_manager
+            }
+
+            CanisterStatus::Running {
+                call_context_manager,
+            } => call_context_manager,
+        };
+
+        // Skip best-effort responses whose callback is gone.
+        let enqueue: Result<Vec<bool>, StateError> = msgs
+            .iter()
+            .map(|(msg, _)| match msg {
+                RequestOrResponse::Request(_) => Ok(true),
+                RequestOrResponse::Response(response) => should_enqueue_input(
+                    response,
+                    call_context_manager,
+                    self.aborted_or_paused_response(),
+                ),
+            })
+            .collect();
+        let enqueue = match enqueue {
+            Ok(enqueue) => enqueue,
+            Err(err) => return Err((err, batch_messages(msgs))),
+        };
+
+        let all_msgs: Vec<_> = msgs.iter().map(|(msg, _)| msg.clone()).collect();
+        let msgs = msgs
+            .into_iter()
+            .zip(enqueue)
+            .filter_map(|(msg, should_enqueue)| should_enqueue.then_some(msg))
+            .collect();
+        push_input_batch(
+            &mut self.queues,
+            msgs,
+            subnet_available_memory,
+            own_subnet_type,
+        )
+        .map_err(|(err, _)| (err, all_msgs))
+    }
+
     /// Pushes an ingress message into the induction pool.
     pub(crate) fn push_ingress(&mut self, msg: Ingress) {
         self.queues.push_ingress(msg)
@@ -1790,9 +1892,9 @@ impl SystemState {
         &mut self,
         own_canister_id: &CanisterId,
         local_canisters: &BTreeMap<CanisterId, CanisterState>,
-    ) {
+    ) -> InputScheduleSplitSummary {
         self.queues
-            .split_input_schedules(own_canister_id, local_canisters);
+            .split_input_schedules(own_canister_id, local_canisters)
     }
 
     /// Increments 'cycles_balance' and in case of refund for consumed cycles
@@ -2041,6 +2143,55 @@ pub(crate) fn push_input(
     res
 }
 
+/// Strips the input queue types off a batch of messages.
+fn batch_messages(msgs: Vec<(RequestOrResponse, InputQueueType)>) -> Vec<RequestOrResponse> {
+    msgs.into_iter().map(|(msg, _)| msg).collect()
+}
+
+/// Implements memory limits verification for atomically pushing a batch of
+/// canister-to-canister messages into the induction pool of `queues`.
+///
+/// Returns `StateError::OutOfMemory` (and pushes nothing) if the memory required
+/// by all guaranteed response requests in the batch that are subject to memory
+/// limits exceeds `subnet_available_memory`.
+///
+/// `subnet_available_memory` is updated same as by `push_input()`.
+///
+/// See `CanisterQueues::push_input_batch()` for further details.
+pub(crate) fn push_input_batch(
+    queues: &mut CanisterQueues,
+    msgs: Vec<(RequestOrResponse, InputQueueType)>,
+    subnet_available_memory: &mut i64,
+    own_subnet_type: SubnetType,
+) -> Result<(), (StateError, Vec<RequestOrResponse>)> {
+    // Do not enforce limits for local messages on system subnets.
+    let required_memory: usize = msgs
+        .iter()
+        .filter(|(_, input_queue_type)| {
+            own_subnet_type != SubnetType::System
+                || *input_queue_type != InputQueueType::LocalSubnet
+        })
+        .map(|(msg, _)| match msg {
+            RequestOrResponse::Request(request) => memory_required_to_push_request(request),
+            RequestOrResponse::Response(_) => 0,
+        })
+        .sum();
+    if required_memory > 0 && required_memory as i64 > *subnet_available_memory {
+        return Err((
+            StateError::OutOfMemory {
+                requested: NumBytes::new(required_memory as u64),
+                available: *subnet_available_memory,
+            },
+            batch_messages(msgs),
+        ));
+    }
+
+    *subnet_available_memory += queues.guaranteed_response_memory_usage() as i64;
+    let res = queues.push_input_batch(msgs);
+    *subnet_available_memory -= queues.guaranteed_response_memory_usage() as i64;
+    res
+}
+
 /// Tests whether the given response should be inducted or silently dropped.
 /// Verifies that the stored respondent and originator associated with the
 /// `callback_id`, as well as its deadline match those of the response.
@@ -2136,7 +2287,7 @@ pub mod testing {
             &mut self,
             own_canister_id: &CanisterId,
             local_canisters: &BTreeMap<CanisterId, CanisterState>,
-        );
+        ) -> InputScheduleSplitSummary;
     }
 
     impl SystemStateTesting for SystemState {
@@ -2164,7 +2315,7 @@ pub mod testing {
             &mut self,
             own_canister_id: &CanisterId,
             local_canisters: &BTreeMap<CanisterId, CanisterState>,
-        ) {
+        ) -> InputScheduleSplitSummary {
             self.split_input_schedules(own_canister_id, local_canisters)
         }
     }
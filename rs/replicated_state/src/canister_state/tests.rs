@@ -495,6 +495,119 @@ fn canister_state_induct_messages_to_self_guaranteed_response_duplicate_of_pause
     assert!(fixture.canister_state.has_output());
 }
 
+#[test]
+fn canister_state_push_input_batch_success() {
+    let mut fixture = CanisterStateFixture::new();
+    fixture.with_input_slot_reservation();
+    let response: RequestOrResponse =
+        default_input_response(fixture.make_callback(NO_DEADLINE), NO_DEADLINE).into();
+    // A best-effort response whose callback is gone is silently dropped.
+    let orphan_response = default_input_response(CallbackId::from(13), SOME_DEADLINE).into();
+    let mut subnet_available_memory = SUBNET_AVAILABLE_MEMORY;
+
+    fixture
+        .canister_state
+        .push_input_batch(
+            vec![
+                (
+                    default_input_request(NO_DEADLINE),
+                    InputQueueType::RemoteSubnet,
+                ),
+                (orphan_response, InputQueueType::RemoteSubnet),
+                (response, InputQueueType::RemoteSubnet),
+            ],
+            &mut subnet_available_memory,
+            SubnetType::Application,
+        )
+        .unwrap();
+
+    // The request and the guaranteed response were enqueued. The request made a
+    // memory reservation, the response consumed one.
+    assert_eq!(
+        2,
+        fixture
+            .canister_state
+            .system_state
+            .queues()
+            .input_queues_message_count()
+    );
+    assert_eq!(SUBNET_AVAILABLE_MEMORY, subnet_available_memory);
+}
+
+#[test]
+fn canister_state_push_input_batch_is_all_or_nothing() {
+    let mut fixture = CanisterStateFixture::new();
+    // A guaranteed response without a matching callback.
+    let response = default_input_response(CallbackId::from(13), NO_DEADLINE);
+    let batch = vec![
+        (
+            default_input_request(NO_DEADLINE),
+            InputQueueType::RemoteSubnet,
+        ),
+        (
+            default_input_request(SOME_DEADLINE),
+            InputQueueType::RemoteSubnet,
+        ),
+        (response.clone().into(), InputQueueType::RemoteSubnet),
+    ];
+    let mut subnet_available_memory = SUBNET_AVAILABLE_MEMORY;
+
+    let (err, msgs) = fixture
+        .canister_state
+        .push_input_batch(
+            batch.clone(),
+            &mut subnet_available_memory,
+            SubnetType::Application,
+        )
+        .unwrap_err();
+
+    assert_matches!(err, StateError::NonMatchingResponse { .. });
+    assert_eq!(
+        batch.into_iter().map(|(msg, _)| msg).collect::<Vec<_>>(),
+        msgs
+    );
+    // Nothing was enqueued.
+    assert!(!fixture.canister_state.has_input());
+    assert_eq!(SUBNET_AVAILABLE_MEMORY, subnet_available_memory);
+}
+
+#[test]
+fn canister_state_push_input_batch_not_enough_subnet_memory() {
+    let mut fixture = CanisterStateFixture::new();
+    // Enough memory for one guaranteed response request, but not for two.
+    let initial_subnet_available_memory = MAX_RESPONSE_COUNT_BYTES as i64;
+    let mut subnet_available_memory = initial_subnet_available_memory;
+
+    let (err, _) = fixture
+        .canister_state
+        .push_input_batch(
+            vec![
+                (
+                    default_input_request(NO_DEADLINE),
+                    InputQueueType::RemoteSubnet,
+                ),
+                (
+                    default_input_request(NO_DEADLINE),
+                    InputQueueType::RemoteSubnet,
+                ),
+            ],
+            &mut subnet_available_memory,
+            SubnetType::Application,
+        )
+        .unwrap_err();
+
+    assert_eq!(
+        StateError::OutOfMemory {
+            requested: NumBytes::new(2 * MAX_RESPONSE_COUNT_BYTES as u64),
+            available: initial_subnet_available_memory,
+        },
+        err
+    );
+    // Nothing was enqueued.
+    assert!(!fixture.canister_state.has_input());
+    assert_eq!(initial_subnet_available_memory, subnet_available_memory);
+}
+
 #[test]
 fn application_subnet_remote_push_input_request_not_enough_subnet_memory() {
     canister_state_push_input_request_memory_limit_test_impl(
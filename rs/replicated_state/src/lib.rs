@@ -74,7 +74,8 @@ pub use canister_state::{
         CallOrigin, CanisterMetrics, CanisterStatus, ExecutionTask, SystemState,
     },
     CanisterQueues, CanisterState, EmbedderCache, ExecutionState, ExportedFunctions, Global,
-    NumWasmPages, SchedulerState,
+    InputScheduleSplitSummary, MemoryBreakdown, MessageClass, MessageContext, MessageDescriptor,
+    MessageKind, NumWasmPages, SchedulerState,
 };
 pub use metadata_state::{
     IngressHistoryState, NetworkTopology, Stream, SubnetTopology, SystemMetadata,
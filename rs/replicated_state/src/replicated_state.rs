@@ -122,6 +122,10 @@ pub enum StateError {
 
     /// No corresponding request found when trying to push a response from the bitcoin adapter.
     BitcoinNonMatchingResponse { callback_id: u64 },
+
+    /// Attempted to reject a request as a subnet message, but the receiver is
+    /// neither the management canister nor a subnet.
+    InvalidSubnetRejection { receiver: CanisterId },
 }
 
 /// Circular iterator that consumes messages from all canisters' and the
@@ -264,6 +268,7 @@ pub const LABEL_VALUE_QUEUE_FULL: &str = "QueueFull";
 pub const LABEL_VALUE_OUT_OF_MEMORY: &str = "OutOfMemory";
 pub const LABEL_VALUE_INVALID_RESPONSE: &str = "InvalidResponse";
 pub const LABEL_VALUE_BITCOIN_NON_MATCHING_RESPONSE: &str = "BitcoinNonMatchingResponse";
+pub const LABEL_VALUE_INVALID_SUBNET_REJECTION: &str = "InvalidSubnetRejection";
 
 impl StateError {
     /// Returns a string representation of the `StateError` variant name to be
@@ -280,6 +285,7 @@ impl StateError {
             StateError::BitcoinNonMatchingResponse { .. } => {
                 LABEL_VALUE_BITCOIN_NON_MATCHING_RESPONSE
             }
+            StateError::InvalidSubnetRejection { .. } => LABEL_VALUE_INVALID_SUBNET_REJECTION,
         }
     }
 
@@ -336,6 +342,13 @@ impl std::fmt::Display for StateError {
                     callback_id
                 )
             }
+            StateError::InvalidSubnetRejection { receiver } => {
+                write!(
+                    f,
+                    "Cannot reject request to {} as a subnet message: receiver is neither the management canister nor a subnet",
+                    receiver
+                )
+            }
         }
     }
 }
@@ -473,7 +473,7 @@ impl Canister {
                 anyhow::Error::from(error).context("Unable to run dfx command.")
             }
             RunCommandError::UnsuccessfulExit { output, .. } => {
-                anyhow!("dfx command exited unsuccessfully. {:?}", output)
+                anyhow::Error::new(UnsuccessfulDfxCall { output })
             }
         })?;
 
@@ -491,6 +491,48 @@ impl Canister {
     }
 }
 
+/// dfx ran, but exited unsuccessfully. This covers failures to reach the
+/// network (which may be transient, e.g., a network hiccup), but also canister
+/// rejects and traps, as well as timeouts after the message was already
+/// submitted (and possibly accepted).
+#[derive(Debug)]
+pub(crate) struct UnsuccessfulDfxCall {
+    output: Output,
+}
+
+/// Substrings of dfx's stderr indicating that the HTTP connection to the
+/// replica could not even be established, i.e. the call was never submitted.
+const CONNECTION_FAILED_MARKERS: &[&str] = &[
+    "Connection refused",
+    "error trying to connect",
+    "dns error",
+    "failed to lookup address",
+];
+
+impl UnsuccessfulDfxCall {
+    /// Returns `true` if dfx failed before the call was submitted (e.g. the
+    /// connection was refused or the host name could not be resolved), so it is
+    /// safe to retry the call without risking a duplicate submission.
+    pub(crate) fn failed_before_submission(&self) -> bool {
+        let stderr = String::from_utf8_lossy(&self.output.stderr);
+        CONNECTION_FAILED_MARKERS
+            .iter()
+            .any(|marker| stderr.contains(marker))
+    }
+}
+
+impl Display for UnsuccessfulDfxCall {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "dfx command exited unsuccessfully. {:?}",
+            self.output
+        )
+    }
+}
+
+impl std::error::Error for UnsuccessfulDfxCall {}
+
 struct NnsGovernanceCanister {
     canister: Canister,
 }
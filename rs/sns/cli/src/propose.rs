@@ -1,9 +1,10 @@
 use crate::{
-    fetch_canister_controllers, get_identity, use_test_neuron_1_owner_identity,
-    MakeProposalResponse, NnsGovernanceCanister, SaveOriginalDfxIdentityAndRestoreOnExit,
+    fetch_canister_controllers, get_identity, init_config_file::friendly::SnsConfigurationFile,
+    use_test_neuron_1_owner_identity, MakeProposalResponse, NnsGovernanceCanister,
+    SaveOriginalDfxIdentityAndRestoreOnExit, UnsuccessfulDfxCall,
 };
 use anyhow::{anyhow, bail, Context, Result};
-use clap::{ArgGroup, Parser};
+use clap::{ArgGroup, Parser, ValueEnum};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_nervous_system_common::ledger::compute_neuron_staking_subaccount_bytes;
 use ic_nervous_system_common_test_keys::TEST_NEURON_1_ID;
@@ -18,12 +19,24 @@ use std::{
     collections::HashSet,
     fmt::{Debug, Display, Formatter},
     fs::{write, OpenOptions},
+    io::{BufRead, IsTerminal, Write},
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 #[cfg(test)]
 mod propose_tests;
 
+/// Environment variable that, when set to `1` or `true`, has the same effect as
+/// `--skip-confirmation`.
+const SKIP_CONFIRMATION_ENV_VAR: &str = "SNS_CLI_SKIP_CONFIRMATION";
+
+/// How many times submitting the proposal is attempted, unless `--no-retry` is given.
+const MAKE_PROPOSAL_MAX_ATTEMPTS: usize = 3;
+
+/// How long to wait before the first retry. Doubles with each subsequent retry.
+const MAKE_PROPOSAL_INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+
 #[derive(Debug, Parser)]
 #[clap(group(ArgGroup::new("neuron-selection").multiple(false).required(true)))]
 pub struct ProposeArgs {
@@ -62,45 +75,148 @@ pub struct ProposeArgs {
     /// An optional flag to save the ProposalId of a successfully submitted
     /// CreateServiceNervousSystem proposal to the filesystem. The file must
     /// be writeable, and will be created if it does not exist.    
-    /// The ProposalId will be saved in JSON format. For example:
+    /// By default, the ProposalId will be saved in JSON format. For example:
     ///
     ///  {
     ///      "id": 10
     ///  }
+    ///
+    /// See --save-format for other formats.
     #[clap(long)]
     pub save_to: Option<PathBuf>,
 
+    /// The format in which the ProposalId is saved to the --save-to file.
+    #[clap(long, value_enum, default_value_t = SaveFormat::Json, requires = "save_to")]
+    pub save_format: SaveFormat,
+
     /// If this flag is set, the proposal will be submitted without asking for
-    /// confirmation. This is useful for automated scripts.
+    /// confirmation. This is useful for automated scripts. Setting the
+    /// SNS_CLI_SKIP_CONFIRMATION environment variable to `1` or `true` has the
+    /// same effect. Without either, confirmation fails if stdin is not a
+    /// terminal, rather than waiting for input that never arrives.
     #[clap(long)]
     pub skip_confirmation: bool,
+
+    /// If this flag is set, the configuration is loaded and validated (including
+    /// the check that NNS root controls all dapp canisters), and the resulting
+    /// proposal is printed together with the confirmation messages, but the
+    /// proposal is NOT submitted.
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// An optional template of the URL at which a submitted proposal can be
+    /// viewed, e.g., a dashboard of a testnet. `{id}` is replaced by the id of
+    /// the submitted proposal. If not specified, a URL is only printed when
+    /// the network is "ic".
+    #[clap(long)]
+    pub dashboard_url_template: Option<String>,
+
+    /// By default, submitting the proposal is retried (with exponential
+    /// backoff) a few times if dfx could not connect to the network, e.g.
+    /// because of a network hiccup. Failures after the proposal may have been
+    /// submitted (rejects, timeouts, etc.) are never retried, so as to not
+    /// submit duplicate proposals. If this flag is set, the proposal is
+    /// submitted at most once.
+    #[clap(long)]
+    pub no_retry: bool,
+
+    /// How to report a successfully submitted proposal on stdout. With `json`,
+    /// a single object such as
+    ///
+    ///  {"proposal_id":10,"network":"ic","url":"https://..."}
+    ///
+    /// is printed instead of human-readable lines, and the messages explaining
+    /// the proposal are printed to stderr. `url` is null if not known.
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+/// The formats in which the ProposalId of a submitted proposal can be saved.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum SaveFormat {
+    /// `{"id":10}`
+    Json,
+    /// `10`
+    Plain,
+    /// `PROPOSAL_ID=10`, as in a `.env` file.
+    Env,
+}
+
+/// The formats in which the outcome of a submitted proposal is reported.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable lines.
+    Text,
+    /// A single JSON object.
+    Json,
 }
 
 pub fn exec(args: ProposeArgs) -> Result<()> {
+    exec_with(
+        args,
+        load_configuration_and_validate,
+        |network, proposer, proposal| {
+            NnsGovernanceCanister::new(network).make_proposal(proposer, proposal)
+        },
+    )
+}
+
+/// Same as `exec`, but with the loading of the configuration and the submission of the
+/// proposal supplied by the caller, so that the surrounding flow can be tested.
+fn exec_with(
+    args: ProposeArgs,
+    load_configuration: impl FnOnce(&str, &PathBuf) -> Result<Proposal>,
+    mut make_proposal: impl FnMut(
+        &str,
+        &NeuronIdOrSubaccount,
+        &Proposal,
+    ) -> Result<MakeProposalResponse>,
+) -> Result<()> {
     let ProposeArgs {
         network,
         init_config_file,
         neuron_id,
         neuron_memo,
         save_to,
+        save_format,
         test_neuron_proposer,
         skip_confirmation,
+        dry_run,
+        dashboard_url_template,
+        no_retry,
+        output,
     } = args;
     // We automatically skip confirming with the user if the network is "local", to save time during testing.
-    let skip_confirmation = skip_confirmation || network == "local";
+    let skip_confirmation = skip_confirmation
+        || network == "local"
+        || std::env::var(SKIP_CONFIRMATION_ENV_VAR).is_ok_and(|value| is_truthy(&value));
 
     // Step 0: Load configuration
-    let proposal = load_configuration_and_validate(&network, &init_config_file)?;
+    let proposal = load_configuration(&network, &init_config_file)?;
 
-    // Step 1: Ensure the save-to file exists and is writeable if specified.
-    // We do this check without writing the file to ensure the best chance of successfully
-    // saving the data to a file after the Proposal is submitted.
-    if let Some(save_to) = &save_to {
-        ensure_file_exists_and_is_writeable(save_to.as_path())?
+    // In a dry run, show what would be submitted, and stop before touching the
+    // save-to file or the proposer's identity.
+    if dry_run {
+        print_dry_run(&proposal)?;
+        return Ok(());
     }
 
+    // Step 1: Ensure the save-to file exists and is writeable if specified.
+    // We do this check (by writing a placeholder) before submitting to ensure the best
+    // chance of successfully saving the data to a file after the Proposal is submitted.
+    // Unless a proposal is (or may have been) submitted, the file's original contents are
+    // restored on exit.
+    let restore_save_to = match &save_to {
+        Some(save_to) => {
+            let restore = SaveOriginalSaveToFileAndRestoreOnExit::new(save_to.as_path());
+            ensure_file_exists_and_is_writeable(save_to.as_path(), save_format)?;
+            Some(restore)
+        }
+        None => None,
+    };
+
     // Step 2: Verify with the user that they want to proceed.
-    inform_user_of_sns_behavior(&proposal, skip_confirmation)?;
+    inform_user_of_sns_behavior(&proposal, skip_confirmation, output)?;
 
     // Step 2: Send the proposal.
     eprintln!("Loaded configuration.");
@@ -126,43 +242,66 @@ pub fn exec(args: ProposeArgs) -> Result<()> {
         );
         NeuronIdOrSubaccount::Subaccount(subaccount.to_vec())
     };
-    let result = NnsGovernanceCanister::new(&network).make_proposal(&proposer, &proposal);
+    let max_attempts = if no_retry {
+        1
+    } else {
+        MAKE_PROPOSAL_MAX_ATTEMPTS
+    };
+    let result = retry_with_backoff(
+        max_attempts,
+        MAKE_PROPOSAL_INITIAL_BACKOFF,
+        std::thread::sleep,
+        || make_proposal(&network, &proposer, &proposal),
+    );
 
     // Step 3: Report result.
-    println!();
     match result {
         Ok(MakeProposalResponse {
             proposal_id: Some(proposal_id),
             message,
         }) => {
-            println!("🚀 Success!");
-            if let Some(message) = message {
-                println!("Message from NNS governance: {:?}", message);
-            }
-            if network == "ic" {
-                println!("View the proposal here:");
-                println!(
-                    "https://dashboard.internetcomputer.org/proposal/{}",
-                    proposal_id.id
-                );
-            } else {
-                // TODO: Support other networks.
-                println!("Proposal ID: {}", proposal_id.id);
+            if let Some(restore_save_to) = restore_save_to {
+                restore_save_to.keep_changes();
             }
+            report_success(
+                &mut std::io::stdout(),
+                output,
+                &network,
+                dashboard_url_template.as_deref(),
+                &proposal_id,
+                message.as_deref(),
+            )?;
 
             if let Some(save_to) = &save_to {
-                if let Err(err) = save_proposal_id_to_file(save_to.as_path(), &proposal_id) {
+                if let Err(err) =
+                    save_proposal_id_to_file(save_to.as_path(), &proposal_id, save_format)
+                {
                     bail!("{}", err);
                 };
             }
         }
         err => {
+            // Restoring the save-to file after the proposal may have been submitted would
+            // leave it holding a stale proposal ID, so keep the placeholder instead.
+            let mut save_to_note = String::new();
+            if let (Some(restore_save_to), Some(save_to)) = (restore_save_to, &save_to) {
+                if may_have_submitted_proposal(&err) {
+                    restore_save_to.keep_changes();
+                    save_to_note = format!(
+                        "\n\n\
+                        The proposal may have been submitted regardless, so {} was left\n\
+                        holding a placeholder instead of being restored. Check the NNS\n\
+                        dashboard for the proposal before trying again.",
+                        save_to.display(),
+                    );
+                }
+            }
             bail!(
                 "{err:?}\n\
                 \n\
                 💔 Something went wrong. Look up slightly for diagnostics.\n\
                 Perhaps, share the above error with the community at\n\
-                https://forum.dfinity.org/c/tokenization"
+                https://forum.dfinity.org/c/tokenization{save_to_note}"
             )
         }
     };
@@ -170,6 +309,113 @@ pub fn exec(args: ProposeArgs) -> Result<()> {
     Ok(())
 }
 
+/// Calls `f` until it succeeds, fails with an error other than an
+/// `UnsuccessfulDfxCall` that failed before submission, or `max_attempts`
+/// attempts have been made. Only failures to connect are retried: any other
+/// error (including dfx exiting unsuccessfully because of a reject or a
+/// timeout) may mean that the proposal was submitted, so retrying could submit
+/// it twice. Before the n-th retry, `sleep` is called with
+/// `initial_backoff * 2^(n-1)`.
+fn retry_with_backoff<T>(
+    max_attempts: usize,
+    initial_backoff: Duration,
+    mut sleep: impl FnMut(Duration),
+    mut f: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let mut backoff = initial_backoff;
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(err)
+                if attempt < max_attempts
+                    && err
+                        .downcast_ref::<UnsuccessfulDfxCall>()
+                        .is_some_and(UnsuccessfulDfxCall::failed_before_submission) =>
+            {
+                eprintln!(
+                    "Attempt {} of {} failed, retrying in {:?}: {}",
+                    attempt, max_attempts, backoff, err,
+                );
+                sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Returns `false` if `result` shows that no proposal was submitted: either
+/// governance responded without a proposal ID, or dfx failed before submitting
+/// the call. Any other error (e.g. a timeout) may have occurred after the
+/// proposal was submitted.
+fn may_have_submitted_proposal(result: &Result<MakeProposalResponse>) -> bool {
+    match result {
+        Ok(MakeProposalResponse { proposal_id, .. }) => proposal_id.is_some(),
+        Err(err) => !err
+            .downcast_ref::<UnsuccessfulDfxCall>()
+            .is_some_and(UnsuccessfulDfxCall::failed_before_submission),
+    }
+}
+
+/// Writes the outcome of a successfully submitted proposal to `out` in the
+/// given format.
+fn report_success(
+    out: &mut impl Write,
+    output: OutputFormat,
+    network: &str,
+    dashboard_url_template: Option<&str>,
+    proposal_id: &ProposalId,
+    message: Option<&str>,
+) -> Result<()> {
+    let url = proposal_url(network, dashboard_url_template, proposal_id);
+    match output {
+        OutputFormat::Text => {
+            writeln!(out)?;
+            writeln!(out, "🚀 Success!")?;
+            if let Some(message) = message {
+                writeln!(out, "Message from NNS governance: {:?}", message)?;
+            }
+            match url {
+                Some(url) => {
+                    writeln!(out, "View the proposal here:")?;
+                    writeln!(out, "{}", url)?;
+                }
+                None => writeln!(out, "Proposal ID: {}", proposal_id.id)?,
+            }
+        }
+        OutputFormat::Json => {
+            if let Some(message) = message {
+                eprintln!("Message from NNS governance: {:?}", message);
+            }
+            let summary = serde_json::json!({
+                "proposal_id": proposal_id.id,
+                "network": network,
+                "url": url,
+            });
+            writeln!(out, "{}", summary)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the URL at which the proposal can be viewed, if known. A given
+/// `dashboard_url_template` takes precedence over the mainnet dashboard.
+fn proposal_url(
+    network: &str,
+    dashboard_url_template: Option<&str>,
+    proposal_id: &ProposalId,
+) -> Option<String> {
+    match dashboard_url_template {
+        Some(template) => Some(template.replace("{id}", &proposal_id.id.to_string())),
+        None if network == "ic" => Some(format!(
+            "https://dashboard.internetcomputer.org/proposal/{}",
+            proposal_id.id
+        )),
+        None => None,
+    }
+}
+
 fn confirmation_messages(proposal: &Proposal) -> Result<Vec<String>> {
     let csns = match &proposal.action {
         Some(Action::CreateServiceNervousSystem(csns)) => csns,
@@ -218,32 +464,73 @@ Once the swap is completed, the SNS will be in normal mode and these proposal ac
     Ok(vec![dapp_canister_controllers, allowed_proposals])
 }
 
-fn inform_user_of_sns_behavior(proposal: &Proposal, skip_confirmation: bool) -> Result<()> {
+fn print_dry_run(proposal: &Proposal) -> Result<()> {
     let messages = confirmation_messages(proposal)?;
+    println!("Dry run: the following proposal would be submitted:");
+    println!("{:#?}", proposal);
     for message in messages {
         println!();
         println!("{}", message);
+    }
+    Ok(())
+}
+
+fn inform_user_of_sns_behavior(
+    proposal: &Proposal,
+    skip_confirmation: bool,
+    output: OutputFormat,
+) -> Result<()> {
+    let messages = confirmation_messages(proposal)?;
+    for message in messages {
+        // Keep stdout free for the summary object in JSON mode.
+        match output {
+            OutputFormat::Text => println!("\n{}", message),
+            OutputFormat::Json => eprintln!("\n{}", message),
+        }
         confirm_understanding(skip_confirmation)?;
     }
     Ok(())
 }
 
+/// Whether an environment variable value turns a flag on.
+fn is_truthy(value: &str) -> bool {
+    value == "1" || value.eq_ignore_ascii_case("true")
+}
+
 fn confirm_understanding(skip_confirmation: bool) -> Result<()> {
-    use std::io::{self, Write};
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    confirm_understanding_from(skip_confirmation, interactive, &mut stdin.lock())
+}
 
+/// Asks the user to confirm by reading a line from `input_source`, unless
+/// `skip_confirmation` is set. Fails instead of reading if `input_source` is not
+/// `interactive`, since e.g. a CI job would otherwise wait forever.
+fn confirm_understanding_from(
+    skip_confirmation: bool,
+    interactive: bool,
+    input_source: &mut impl BufRead,
+) -> Result<()> {
     if skip_confirmation {
         return Ok(());
     }
+    if !interactive {
+        bail!(
+            "Cannot ask for confirmation because stdin is not a terminal. \
+             Pass --skip-confirmation or set {}=1 to proceed without confirming.",
+            SKIP_CONFIRMATION_ENV_VAR
+        );
+    }
 
     let mut input = String::new();
-    print!("I understand [y/N]: ");
-    io::stdout().flush().unwrap(); // Make sure the prompt is displayed before input
+    eprint!("I understand [y/N]: ");
+    std::io::stderr().flush().unwrap(); // Make sure the prompt is displayed before input
 
-    match io::stdin().read_line(&mut input) {
+    match input_source.read_line(&mut input) {
         Ok(_) => {
             let input = input.trim().to_lowercase(); // Clean and normalize the input
             if input == "y" || input == "yes" {
-                println!("Confirmed.");
+                eprintln!("Confirmed.");
                 Ok(())
             } else {
                 bail!("Exiting.")
@@ -270,16 +557,7 @@ fn load_configuration_and_validate(
     })?;
 
     // Parse its contents.
-    let init_config_file = serde_yaml::from_str::<
-        crate::init_config_file::friendly::SnsConfigurationFile,
-    >(&init_config_file)
-    .map_err(|err| {
-        anyhow!(
-            "Unable to parse the SNS configuration file ({:?}):\n{}",
-            init_config_file,
-            err,
-        )
-    })?;
+    let init_config_file = parse_configuration_file(configuration_file_path, &init_config_file)?;
     let base_path = match configuration_file_path.parent() {
         Some(ok) => ok,
         None => {
@@ -337,14 +615,71 @@ fn load_configuration_and_validate(
     Ok(proposal)
 }
 
+/// Parses the contents of an SNS configuration file. Files with a `.json` extension are parsed
+/// as JSON, and files with a `.yaml` or `.yml` extension as YAML. For any other extension, YAML
+/// is tried first, and then JSON.
+fn parse_configuration_file(
+    configuration_file_path: &Path,
+    contents: &str,
+) -> Result<SnsConfigurationFile> {
+    let parse_yaml = || {
+        serde_yaml::from_str::<SnsConfigurationFile>(contents).map_err(|err| {
+            anyhow!(
+                "Unable to parse the SNS configuration file ({:?}) as YAML:\n{}",
+                contents,
+                err,
+            )
+        })
+    };
+    let parse_json = || {
+        serde_json::from_str::<SnsConfigurationFile>(contents).map_err(|err| {
+            anyhow!(
+                "Unable to parse the SNS configuration file ({:?}) as JSON:\n{}",
+                contents,
+                err,
+            )
+        })
+    };
+
+    let extension = configuration_file_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("json") => parse_json(),
+        Some("yaml") | Some("yml") => parse_yaml(),
+        _ => parse_yaml().or_else(|yaml_err| {
+            parse_json().map_err(|json_err| anyhow!("{}\n\n{}", yaml_err, json_err))
+        }),
+    }
+}
+
 struct CanistersWithMissingControllers {
+    network: String,
     inspected_canister_count: usize,
     defective_canister_ids: Vec<CanisterId>,
 }
 
+impl CanistersWithMissingControllers {
+    /// Returns, for each defective canister, the command that adds NNS root as
+    /// one of its controllers.
+    fn add_nns_root_commands(&self) -> Vec<String> {
+        self.defective_canister_ids
+            .iter()
+            .map(|canister_id| {
+                format!(
+                    "sns prepare-canisters --network {} add-nns-root {}",
+                    self.network, canister_id
+                )
+            })
+            .collect()
+    }
+}
+
 impl Display for CanistersWithMissingControllers {
     fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
         let CanistersWithMissingControllers {
+            network: _,
             inspected_canister_count,
             defective_canister_ids,
         } = self;
@@ -352,16 +687,18 @@ impl Display for CanistersWithMissingControllers {
         write!(
             formatter,
             "Not all dapp canisters are controlled by the NNS root canister.\n\
-             Use `sns prepare-canisters add-nns-root` to make the necessary changes.\n\
              Defective canisters ({} out of {}):\n  \
-             - {}",
-            inspected_canister_count,
+             - {}\n\
+             Run the following to make the necessary changes:\n  \
+             {}",
             defective_canister_ids.len(),
+            inspected_canister_count,
             defective_canister_ids
                 .iter()
                 .map(CanisterId::to_string)
                 .collect::<Vec<_>>()
                 .join("\n  - "),
+            self.add_nns_root_commands().join("\n  "),
         )
     }
 }
@@ -396,6 +733,7 @@ fn all_canisters_have_all_required_controllers(
     Err(anyhow!(
         "{}",
         CanistersWithMissingControllers {
+            network: network.to_string(),
             inspected_canister_count,
             defective_canister_ids,
         }
@@ -437,33 +775,107 @@ impl Display for SaveToErrors {
     }
 }
 
+/// Written to the save-to file before the proposal is submitted. Deliberately not
+/// parseable as a `ProposalId` in any `SaveFormat`, so that it cannot be mistaken
+/// for the id of a submitted proposal.
+const SAVE_TO_PLACEHOLDER: &str = "PENDING: proposal not submitted (yet)\n";
+
 /// Ensure that a path to a file exists (by creating it if it does not) and is writeable.
-fn ensure_file_exists_and_is_writeable(path: &Path) -> Result<(), SaveToErrors> {
+///
+/// To make sure that the `ProposalId` can be saved once the proposal is submitted, this
+/// serializes a dummy `ProposalId` and writes a placeholder (`SAVE_TO_PLACEHOLDER`) of
+/// similar size to the file. It is overwritten by `save_proposal_id_to_file` later.
+fn ensure_file_exists_and_is_writeable(
+    path: &Path,
+    save_format: SaveFormat,
+) -> Result<(), SaveToErrors> {
     // Make sure the file is writeable. Create it if it does not exist.
-    match OpenOptions::new()
+    let mut file = OpenOptions::new()
         .create(true)
-        .truncate(false)
+        .truncate(true)
         .write(true)
         .open(path)
-    {
-        Ok(_) => (),
-        Err(e) => {
-            return Err(SaveToErrors::FileOpenFailed(
-                path.to_path_buf(),
-                e.to_string(),
-            ))
+        .map_err(|e| SaveToErrors::FileOpenFailed(path.to_path_buf(), e.to_string()))?;
+
+    serialize_proposal_id(&ProposalId { id: u64::MAX }, save_format)?;
+    file.write_all(SAVE_TO_PLACEHOLDER.as_bytes())
+        .and_then(|()| file.flush())
+        .map_err(|e| SaveToErrors::FileWriteFailed(path.to_path_buf(), e.to_string()))?;
+
+    Ok(())
+}
+
+/// The contents of the save-to file before `ensure_file_exists_and_is_writeable` was
+/// called.
+enum OriginalSaveToFile {
+    /// The file existed, with the given contents.
+    Contents(Vec<u8>),
+    /// The file did not exist.
+    Missing,
+    /// The file could not be read, so it cannot be restored either.
+    Unknown,
+}
+
+/// Restores the save-to file to its original contents (or removes it, if it did
+/// not exist) when dropped, unless `keep_changes` was called, i.e. unless a
+/// proposal was (or may have been) submitted. Best effort: errors are ignored.
+#[must_use]
+struct SaveOriginalSaveToFileAndRestoreOnExit {
+    path: PathBuf,
+    original: OriginalSaveToFile,
+}
+
+impl SaveOriginalSaveToFileAndRestoreOnExit {
+    fn new(path: &Path) -> Self {
+        let original = match std::fs::read(path) {
+            Ok(contents) => OriginalSaveToFile::Contents(contents),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => OriginalSaveToFile::Missing,
+            Err(_) => OriginalSaveToFile::Unknown,
+        };
+        Self {
+            path: path.to_path_buf(),
+            original,
         }
     }
 
-    Ok(())
+    /// Leaves the save-to file as is on exit.
+    fn keep_changes(mut self) {
+        self.original = OriginalSaveToFile::Unknown;
+    }
+}
+
+impl Drop for SaveOriginalSaveToFileAndRestoreOnExit {
+    fn drop(&mut self) {
+        let _ = match &self.original {
+            OriginalSaveToFile::Contents(contents) => write(&self.path, contents),
+            OriginalSaveToFile::Missing => std::fs::remove_file(&self.path),
+            OriginalSaveToFile::Unknown => Ok(()),
+        };
+    }
+}
+
+/// Serialize a `ProposalId` in the given format.
+fn serialize_proposal_id(
+    proposal_id: &ProposalId,
+    save_format: SaveFormat,
+) -> Result<String, SaveToErrors> {
+    match save_format {
+        SaveFormat::Json => serde_json::to_string(&proposal_id)
+            .map_err(|e| SaveToErrors::InvalidData(e.to_string())),
+        SaveFormat::Plain => Ok(proposal_id.id.to_string()),
+        SaveFormat::Env => Ok(format!("PROPOSAL_ID={}\n", proposal_id.id)),
+    }
 }
 
-/// Save a `ProposalId` to a file in JSON format
-fn save_proposal_id_to_file(path: &Path, proposal_id: &ProposalId) -> Result<(), SaveToErrors> {
-    let json_str = serde_json::to_string(&proposal_id)
-        .map_err(|e| SaveToErrors::InvalidData(e.to_string()))?;
+/// Save a `ProposalId` to a file in the given format
+fn save_proposal_id_to_file(
+    path: &Path,
+    proposal_id: &ProposalId,
+    save_format: SaveFormat,
+) -> Result<(), SaveToErrors> {
+    let serialized = serialize_proposal_id(proposal_id, save_format)?;
 
-    write(path, json_str)
+    write(path, serialized)
         .map_err(|e| SaveToErrors::FileWriteFailed(path.to_path_buf(), e.to_string()))?;
     Ok(())
 }
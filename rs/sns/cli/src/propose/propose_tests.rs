@@ -1,6 +1,27 @@
-use crate::propose::{ensure_file_exists_and_is_writeable, save_proposal_id_to_file, SaveToErrors};
+use crate::{
+    init_config_file::friendly::SnsConfigurationFile,
+    propose::{
+        confirm_understanding_from, ensure_file_exists_and_is_writeable, exec_with, is_truthy,
+        may_have_submitted_proposal, parse_configuration_file, proposal_url, report_success,
+        retry_with_backoff, save_proposal_id_to_file, CanistersWithMissingControllers,
+        OutputFormat, ProposeArgs, SaveFormat, SaveOriginalSaveToFileAndRestoreOnExit,
+        SaveToErrors, SAVE_TO_PLACEHOLDER,
+    },
+    MakeProposalResponse, UnsuccessfulDfxCall,
+};
+use anyhow::anyhow;
+use clap::Parser;
+use ic_base_types::CanisterId;
 use ic_nns_common::pb::v1::ProposalId;
-use std::{fs, os::unix::fs::PermissionsExt, path::Path};
+use ic_nns_governance_api::pb::v1::{proposal::Action, Proposal};
+use std::{
+    cell::Cell,
+    fs,
+    os::unix::{fs::PermissionsExt, process::ExitStatusExt},
+    path::Path,
+    process::{ExitStatus, Output},
+    time::Duration,
+};
 use tempfile::NamedTempFile;
 
 /// read-only for owner, group, and others
@@ -16,7 +37,59 @@ fn test_ensure_file_exists_and_is_writeable_succeeds_with_existing_file() {
     let temp_file_path = temp_file.path();
 
     // Exercise/verify
-    assert_eq!(ensure_file_exists_and_is_writeable(temp_file_path), Ok(()));
+    assert_eq!(
+        ensure_file_exists_and_is_writeable(temp_file_path, SaveFormat::Json),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_ensure_file_exists_and_is_writeable_writes_placeholder_proposal_id() {
+    // Setup
+    let temp_file = NamedTempFile::new().expect("Failed to create tmp file");
+    let temp_file_path = temp_file.path();
+    fs::write(temp_file_path, "some previous, much longer contents").unwrap();
+
+    // Exercise
+    assert_eq!(
+        ensure_file_exists_and_is_writeable(temp_file_path, SaveFormat::Json),
+        Ok(())
+    );
+
+    // Verify
+    let file_string = fs::read_to_string(temp_file_path).expect("Failed to read temp file");
+    assert_eq!(file_string, SAVE_TO_PLACEHOLDER);
+    // The placeholder cannot be mistaken for the id of a submitted proposal.
+    assert!(serde_json::from_str::<ProposalId>(&file_string).is_err());
+    assert!(file_string.trim().parse::<u64>().is_err());
+    assert!(!file_string.starts_with("PROPOSAL_ID="));
+}
+
+#[test]
+fn test_save_to_file_is_restored_unless_changes_are_kept() {
+    // Setup
+    let temp_dir = tempfile::tempdir().expect("Failed to create tmp dir");
+    let existing_path = temp_dir.path().join("existing.json");
+    let missing_path = temp_dir.path().join("missing.json");
+    fs::write(&existing_path, "{\"id\":42}").unwrap();
+
+    // Nothing submitted: the original contents are restored and a newly created
+    // file is removed.
+    for path in [&existing_path, &missing_path] {
+        let restore = SaveOriginalSaveToFileAndRestoreOnExit::new(path);
+        ensure_file_exists_and_is_writeable(path, SaveFormat::Json).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), SAVE_TO_PLACEHOLDER);
+        drop(restore);
+    }
+    assert_eq!(fs::read_to_string(&existing_path).unwrap(), "{\"id\":42}");
+    assert!(!missing_path.exists());
+
+    // Proposal submitted: the file is left as is.
+    let restore = SaveOriginalSaveToFileAndRestoreOnExit::new(&existing_path);
+    ensure_file_exists_and_is_writeable(&existing_path, SaveFormat::Json).unwrap();
+    save_proposal_id_to_file(&existing_path, &ProposalId { id: 7 }, SaveFormat::Json).unwrap();
+    restore.keep_changes();
+    assert_eq!(fs::read_to_string(&existing_path).unwrap(), "{\"id\":7}");
 }
 
 #[test]
@@ -25,7 +98,10 @@ fn test_ensure_file_exists_and_is_writeable_succeeds_when_creating_file() {
     let temp_file_path = Path::new("temp_file.json");
 
     // Exercise/verify
-    assert_eq!(ensure_file_exists_and_is_writeable(temp_file_path), Ok(()));
+    assert_eq!(
+        ensure_file_exists_and_is_writeable(temp_file_path, SaveFormat::Json),
+        Ok(())
+    );
     assert!(temp_file_path.exists());
 
     // Teardown
@@ -44,7 +120,7 @@ fn test_ensure_file_exists_and_is_writeable_fails_if_non_writeable() {
 
     // Exercise/verify
     assert!(matches!(
-        ensure_file_exists_and_is_writeable(temp_file_path),
+        ensure_file_exists_and_is_writeable(temp_file_path, SaveFormat::Json),
         Err(SaveToErrors::FileOpenFailed(ref path, _)) if path == &temp_file_path.to_path_buf(),
 
     ));
@@ -64,7 +140,7 @@ fn test_save_proposal_id_to_file_succeeds() {
     // Exercise/verify
     let expected_proposal_id = ProposalId { id: 1 };
     assert_eq!(
-        save_proposal_id_to_file(temp_file_path, &expected_proposal_id),
+        save_proposal_id_to_file(temp_file_path, &expected_proposal_id, SaveFormat::Json),
         Ok(())
     );
     let file_string = fs::read_to_string(temp_file_path).expect("Failed to read temp file");
@@ -73,6 +149,29 @@ fn test_save_proposal_id_to_file_succeeds() {
     assert_eq!(expected_proposal_id, actual_proposal_id);
 }
 
+#[test]
+fn test_save_proposal_id_to_file_formats() {
+    for (save_format, expected_contents) in [
+        (SaveFormat::Json, r#"{"id":42}"#),
+        (SaveFormat::Plain, "42"),
+        (SaveFormat::Env, "PROPOSAL_ID=42\n"),
+    ] {
+        // Setup
+        let temp_file = NamedTempFile::new().expect("Failed to create tmp file");
+        let temp_file_path = temp_file.path();
+
+        // Exercise
+        assert_eq!(
+            save_proposal_id_to_file(temp_file_path, &ProposalId { id: 42 }, save_format),
+            Ok(())
+        );
+
+        // Verify
+        let file_string = fs::read_to_string(temp_file_path).expect("Failed to read temp file");
+        assert_eq!(file_string, expected_contents, "{:?}", save_format);
+    }
+}
+
 #[test]
 fn test_save_proposal_id_to_file_fails_if_write_fails() {
     // Setup
@@ -85,7 +184,7 @@ fn test_save_proposal_id_to_file_fails_if_write_fails() {
 
     // Exercise/verify
     assert!(matches!(
-        save_proposal_id_to_file(temp_file_path, &ProposalId { id: 1 }),
+        save_proposal_id_to_file(temp_file_path, &ProposalId { id: 1 }, SaveFormat::Json),
         Err(SaveToErrors::FileWriteFailed(ref path, _)) if path == &temp_file_path.to_path_buf(),
     ));
 
@@ -94,3 +193,507 @@ fn test_save_proposal_id_to_file_fails_if_write_fails() {
     let permissions = fs::Permissions::from_mode(READ_WRITE_PERMISSION);
     fs::set_permissions(temp_file_path, permissions).expect("Failed to reset permissions");
 }
+
+/// Loads the proposal described by `test_sns_init_v2.yaml`, without checking the controllers
+/// of the dapp canisters.
+fn test_proposal() -> Proposal {
+    let test_root_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let test_root_dir = Path::new(&test_root_dir);
+
+    let contents: String = fs::read_to_string(test_root_dir.join("test_sns_init_v2.yaml")).unwrap();
+    let sns_configuration_file = serde_yaml::from_str::<SnsConfigurationFile>(&contents).unwrap();
+    let create_service_nervous_system = sns_configuration_file
+        .try_convert_to_create_service_nervous_system(test_root_dir)
+        .unwrap();
+
+    Proposal {
+        title: Some("Test Proposal".to_string()),
+        action: Some(Action::CreateServiceNervousSystem(
+            create_service_nervous_system,
+        )),
+        summary: "Test Proposal Summary".to_string(),
+        url: "https://example.com".to_string(),
+    }
+}
+
+#[test]
+fn test_exec_dry_run_does_not_submit_proposal() {
+    // Setup
+    let args = ProposeArgs::try_parse_from([
+        "propose",
+        "--network",
+        "ic",
+        "--neuron-id",
+        "42",
+        "--dry-run",
+        "sns_init.yaml",
+    ])
+    .unwrap();
+    let configuration_loaded = Cell::new(false);
+
+    // Exercise
+    let result = exec_with(
+        args,
+        |_network, _init_config_file| {
+            configuration_loaded.set(true);
+            Ok(test_proposal())
+        },
+        |_network, _proposer, _proposal| panic!("A dry run must not submit the proposal"),
+    );
+
+    // Verify
+    assert!(result.is_ok(), "{:?}", result);
+    assert!(configuration_loaded.get());
+}
+
+#[test]
+fn test_confirm_understanding_does_not_block_without_terminal() {
+    // A non-interactive stdin that would block forever if read from.
+    struct BlockingInput;
+    impl std::io::Read for BlockingInput {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            panic!("Must not read from a non-interactive input");
+        }
+    }
+    let mut input = std::io::BufReader::new(BlockingInput);
+
+    let err = confirm_understanding_from(false, false, &mut input).unwrap_err();
+    assert!(
+        err.to_string().contains("stdin is not a terminal"),
+        "{}",
+        err
+    );
+
+    // Skipping the confirmation works regardless.
+    confirm_understanding_from(true, false, &mut input).unwrap();
+}
+
+#[test]
+fn test_confirm_understanding_reads_answer() {
+    confirm_understanding_from(false, true, &mut "y\n".as_bytes()).unwrap();
+    confirm_understanding_from(false, true, &mut "Yes\n".as_bytes()).unwrap();
+    confirm_understanding_from(false, true, &mut "n\n".as_bytes()).unwrap_err();
+    // A closed stdin is treated as a refusal.
+    confirm_understanding_from(false, true, &mut "".as_bytes()).unwrap_err();
+}
+
+#[test]
+fn test_is_truthy() {
+    assert!(is_truthy("1"));
+    assert!(is_truthy("true"));
+    assert!(is_truthy("TRUE"));
+    assert!(!is_truthy("0"));
+    assert!(!is_truthy("false"));
+    assert!(!is_truthy(""));
+}
+
+#[test]
+fn test_parse_configuration_file_json_matches_yaml() {
+    // Setup
+    let test_root_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let test_root_dir = Path::new(&test_root_dir);
+    let yaml_path = test_root_dir.join("test_sns_init_v2.yaml");
+    let yaml_contents = fs::read_to_string(&yaml_path).unwrap();
+    let json_contents =
+        serde_json::to_string(&parse_configuration_file(&yaml_path, &yaml_contents).unwrap())
+            .unwrap();
+
+    // Exercise
+    let from_yaml = parse_configuration_file(&yaml_path, &yaml_contents).unwrap();
+    let from_json =
+        parse_configuration_file(&test_root_dir.join("sns_init.json"), &json_contents).unwrap();
+    // Without a recognized extension, both formats are accepted.
+    let from_json_without_extension =
+        parse_configuration_file(&test_root_dir.join("sns_init"), &json_contents).unwrap();
+
+    // Verify
+    let to_proposal = |configuration_file: SnsConfigurationFile| {
+        configuration_file
+            .try_convert_to_nns_proposal(test_root_dir)
+            .unwrap()
+    };
+    let expected_proposal = to_proposal(from_yaml);
+    assert_eq!(to_proposal(from_json), expected_proposal);
+    assert_eq!(to_proposal(from_json_without_extension), expected_proposal);
+}
+
+#[test]
+fn test_parse_configuration_file_reports_failed_parser() {
+    let test_root_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let test_root_dir = Path::new(&test_root_dir);
+
+    let err = parse_configuration_file(&test_root_dir.join("sns_init.json"), "not: [json")
+        .unwrap_err()
+        .to_string();
+
+    assert!(err.contains("as JSON"), "{}", err);
+}
+
+#[test]
+fn test_proposal_url() {
+    let proposal_id = ProposalId { id: 123 };
+
+    assert_eq!(
+        proposal_url(
+            "local",
+            Some("http://localhost:8080/proposal/{id}"),
+            &proposal_id
+        ),
+        Some("http://localhost:8080/proposal/123".to_string())
+    );
+    // The template takes precedence over the mainnet dashboard.
+    assert_eq!(
+        proposal_url(
+            "ic",
+            Some("https://example.com/proposals/{id}"),
+            &proposal_id
+        ),
+        Some("https://example.com/proposals/123".to_string())
+    );
+    assert_eq!(
+        proposal_url("ic", None, &proposal_id),
+        Some("https://dashboard.internetcomputer.org/proposal/123".to_string())
+    );
+    assert_eq!(proposal_url("local", None, &proposal_id), None);
+}
+
+#[test]
+fn test_report_success_json() {
+    // Setup: the response a mock NNS governance would give.
+    let MakeProposalResponse {
+        proposal_id,
+        message,
+    } = successful_make_proposal_response();
+    let proposal_id = proposal_id.unwrap();
+    let mut out = Vec::new();
+
+    // Exercise
+    report_success(
+        &mut out,
+        OutputFormat::Json,
+        "ic",
+        None,
+        &proposal_id,
+        message.as_deref(),
+    )
+    .unwrap();
+
+    // Verify: stdout holds exactly one JSON object.
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out.lines().count(), 1, "{}", out);
+    let summary: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(
+        summary,
+        serde_json::json!({
+            "proposal_id": proposal_id.id,
+            "network": "ic",
+            "url": format!("https://dashboard.internetcomputer.org/proposal/{}", proposal_id.id),
+        })
+    );
+
+    // Without a known URL, `url` is null.
+    let mut out = Vec::new();
+    report_success(
+        &mut out,
+        OutputFormat::Json,
+        "local",
+        None,
+        &proposal_id,
+        None,
+    )
+    .unwrap();
+    let summary: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(summary["network"], "local");
+    assert!(summary["url"].is_null(), "{}", summary);
+}
+
+#[test]
+fn test_report_success_text() {
+    let mut out = Vec::new();
+
+    report_success(
+        &mut out,
+        OutputFormat::Text,
+        "local",
+        None,
+        &ProposalId { id: 7 },
+        None,
+    )
+    .unwrap();
+
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("🚀 Success!"), "{}", out);
+    assert!(out.contains("Proposal ID: 7"), "{}", out);
+}
+
+fn unsuccessful_dfx_call(stderr: &str) -> anyhow::Error {
+    anyhow::Error::new(UnsuccessfulDfxCall {
+        output: Output {
+            status: ExitStatus::from_raw(1 << 8),
+            stdout: vec![],
+            stderr: stderr.as_bytes().to_vec(),
+        },
+    })
+    .context("Failed calling the canister")
+}
+
+/// dfx output when the replica cannot be reached.
+const CONNECTION_REFUSED_STDERR: &str = "Error: Failed update call.\nCaused by: \
+    error sending request for url (https://icp-api.io/api/v2/canister/.../call): \
+    error trying to connect: tcp connect error: Connection refused (os error 111)";
+
+fn successful_make_proposal_response() -> MakeProposalResponse {
+    MakeProposalResponse {
+        proposal_id: Some(ProposalId { id: 7 }),
+        message: None,
+    }
+}
+
+#[test]
+fn test_retry_with_backoff_retries_transient_errors() {
+    // Setup
+    let mut attempts = 0;
+    let mut successful_proposals = 0;
+    let mut sleeps = vec![];
+
+    // Exercise
+    let result = retry_with_backoff(
+        3,
+        Duration::from_secs(1),
+        |duration| sleeps.push(duration),
+        || {
+            attempts += 1;
+            if attempts <= 2 {
+                return Err(unsuccessful_dfx_call(CONNECTION_REFUSED_STDERR));
+            }
+            successful_proposals += 1;
+            Ok(successful_make_proposal_response())
+        },
+    );
+
+    // Verify
+    assert_eq!(result.unwrap(), successful_make_proposal_response());
+    assert_eq!(attempts, 3);
+    assert_eq!(successful_proposals, 1);
+    assert_eq!(sleeps, vec![Duration::from_secs(1), Duration::from_secs(2)]);
+}
+
+#[test]
+fn test_retry_with_backoff_does_not_retry_definitive_errors() {
+    let mut attempts = 0;
+
+    let result: anyhow::Result<MakeProposalResponse> = retry_with_backoff(
+        3,
+        Duration::from_secs(1),
+        |_| panic!("Should not back off"),
+        || {
+            attempts += 1;
+            Err(anyhow!("Received an invalid response"))
+        },
+    );
+
+    assert!(result.is_err());
+    assert_eq!(attempts, 1);
+}
+
+#[test]
+fn test_retry_with_backoff_gives_up_after_max_attempts() {
+    let mut attempts = 0;
+
+    // A single attempt corresponds to `--no-retry`.
+    let result: anyhow::Result<MakeProposalResponse> = retry_with_backoff(
+        1,
+        Duration::from_secs(1),
+        |_| panic!("Should not back off"),
+        || {
+            attempts += 1;
+            Err(unsuccessful_dfx_call(CONNECTION_REFUSED_STDERR))
+        },
+    );
+
+    assert!(result.unwrap_err().is::<UnsuccessfulDfxCall>());
+    assert_eq!(attempts, 1);
+}
+
+#[test]
+fn test_retry_with_backoff_does_not_retry_after_submission() {
+    for stderr in [
+        // The canister rejected the call.
+        "Error: Failed update call.\nCaused by: The replica returned a rejection error: \
+        reject code CanisterReject, reject message Neuron not found",
+        // The canister trapped.
+        "Error: Failed update call.\nCaused by: The replica returned a rejection error: \
+        reject code CanisterError, reject message Canister trapped explicitly",
+        // The call was submitted, but no response was received in time.
+        "Error: Failed update call.\nCaused by: Timeout expired.",
+        // No recognizable output at all.
+        "",
+    ] {
+        let mut attempts = 0;
+
+        let result: anyhow::Result<MakeProposalResponse> = retry_with_backoff(
+            3,
+            Duration::from_secs(1),
+            |_| panic!("Should not back off"),
+            || {
+                attempts += 1;
+                Err(unsuccessful_dfx_call(stderr))
+            },
+        );
+
+        assert!(result.unwrap_err().is::<UnsuccessfulDfxCall>());
+        assert_eq!(attempts, 1, "{}", stderr);
+    }
+}
+
+#[test]
+fn test_may_have_submitted_proposal() {
+    // Submitted.
+    assert!(may_have_submitted_proposal(&Ok(
+        successful_make_proposal_response()
+    )));
+
+    // Definitely not submitted: governance did not create a proposal, or dfx could
+    // not connect.
+    assert!(!may_have_submitted_proposal(&Ok(MakeProposalResponse {
+        proposal_id: None,
+        message: Some("Neuron not found".to_string()),
+    })));
+    assert!(!may_have_submitted_proposal(&Err(unsuccessful_dfx_call(
+        CONNECTION_REFUSED_STDERR
+    ))));
+
+    // Possibly submitted: the save-to file must not be restored.
+    assert!(may_have_submitted_proposal(&Err(unsuccessful_dfx_call(
+        "Error: Failed update call.\nCaused by: Timeout expired."
+    ))));
+    assert!(may_have_submitted_proposal(&Err(anyhow!(
+        "Unable to hex decode the response"
+    ))));
+}
+
+#[test]
+fn test_exec_fails_before_submission_if_save_to_is_not_writeable() {
+    // Setup
+    let temp_file = NamedTempFile::new().expect("Failed to create tmp file");
+    let temp_file_path = temp_file.path();
+    let permissions = fs::Permissions::from_mode(READ_ONLY_PERMISSION);
+    fs::set_permissions(temp_file_path, permissions).expect("Failed to set permissions");
+    let args = ProposeArgs::try_parse_from([
+        "propose",
+        "--network",
+        "ic",
+        "--neuron-id",
+        "42",
+        "--skip-confirmation",
+        "--save-to",
+        temp_file_path.to_str().unwrap(),
+        "sns_init.yaml",
+    ])
+    .unwrap();
+
+    // Exercise
+    let result = exec_with(
+        args,
+        |_network, _init_config_file| Ok(test_proposal()),
+        |_network, _proposer, _proposal| panic!("The proposal must not be submitted"),
+    );
+
+    // Verify
+    let err = result.unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref::<SaveToErrors>(),
+            Some(SaveToErrors::FileOpenFailed(path, _)) if path == &temp_file_path.to_path_buf(),
+        ),
+        "{:?}",
+        err
+    );
+
+    // Teardown
+    // Reset permissions so the file can be deleted
+    let permissions = fs::Permissions::from_mode(READ_WRITE_PERMISSION);
+    fs::set_permissions(temp_file_path, permissions).expect("Failed to reset permissions");
+}
+
+#[test]
+fn test_exec_fails_before_submission_if_save_to_directory_is_read_only() {
+    // Setup
+    let temp_dir = tempfile::tempdir().expect("Failed to create tmp dir");
+    let permissions = fs::Permissions::from_mode(0o555);
+    fs::set_permissions(temp_dir.path(), permissions).expect("Failed to set permissions");
+    let save_to = temp_dir.path().join("proposal_id.json");
+    let args = ProposeArgs::try_parse_from([
+        "propose",
+        "--network",
+        "ic",
+        "--neuron-id",
+        "42",
+        "--skip-confirmation",
+        "--save-to",
+        save_to.to_str().unwrap(),
+        "sns_init.yaml",
+    ])
+    .unwrap();
+
+    // Exercise
+    let result = exec_with(
+        args,
+        |_network, _init_config_file| Ok(test_proposal()),
+        |_network, _proposer, _proposal| panic!("The proposal must not be submitted"),
+    );
+
+    // Verify
+    let err = result.unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref::<SaveToErrors>(),
+            Some(SaveToErrors::FileOpenFailed(path, _)) if path == &save_to,
+        ),
+        "{:?}",
+        err
+    );
+    assert!(!save_to.exists());
+
+    // Teardown
+    // Reset permissions so the directory can be deleted
+    let permissions = fs::Permissions::from_mode(0o755);
+    fs::set_permissions(temp_dir.path(), permissions).expect("Failed to reset permissions");
+}
+
+#[test]
+fn test_canisters_with_missing_controllers_display() {
+    let canisters_with_missing_controllers = CanistersWithMissingControllers {
+        network: "ic".to_string(),
+        inspected_canister_count: 3,
+        defective_canister_ids: vec![CanisterId::from_u64(1), CanisterId::from_u64(2)],
+    };
+
+    assert_eq!(
+        canisters_with_missing_controllers.add_nns_root_commands(),
+        vec![
+            format!(
+                "sns prepare-canisters --network ic add-nns-root {}",
+                CanisterId::from_u64(1)
+            ),
+            format!(
+                "sns prepare-canisters --network ic add-nns-root {}",
+                CanisterId::from_u64(2)
+            ),
+        ]
+    );
+    assert_eq!(
+        canisters_with_missing_controllers.to_string(),
+        format!(
+            "Not all dapp canisters are controlled by the NNS root canister.\n\
+             Defective canisters (2 out of 3):\n  \
+             - {canister_1}\n  \
+             - {canister_2}\n\
+             Run the following to make the necessary changes:\n  \
+             sns prepare-canisters --network ic add-nns-root {canister_1}\n  \
+             sns prepare-canisters --network ic add-nns-root {canister_2}",
+            canister_1 = CanisterId::from_u64(1),
+            canister_2 = CanisterId::from_u64(2),
+        )
+    );
+}
@@ -1,9 +1,15 @@
 use anyhow::{anyhow, Result};
+use ic_crypto_sha2::Sha256;
+use reqwest::header::{HeaderMap, ETAG};
 use reqwest::Body;
 use std::path::Path;
 use tokio_util::codec::{BytesCodec, FramedRead};
 use tracing::*;
 
+/// Header carrying the base64-encoded SHA-256 of an object in S3-compatible object stores. If
+/// sent with a PUT, the store rejects a body that does not match it, and echoes it back.
+const CHECKSUM_SHA256_HEADER: &str = "x-amz-checksum-sha256";
+
 pub async fn upload_image<P: AsRef<Path>>(path: P, url: &str) -> Result<()> {
     let client = reqwest::Client::new();
     info!(
@@ -11,9 +17,11 @@ pub async fn upload_image<P: AsRef<Path>>(path: P, url: &str) -> Result<()> {
         path.as_ref().display().to_string(),
         url
     );
+    let digest = sha256_of_file(path.as_ref())?;
     let file = tokio::fs::File::open(path.as_ref()).await?;
     let res = client
         .put(url)
+        .header(CHECKSUM_SHA256_HEADER, base64::encode(digest))
         .body({
             let stream = FramedRead::new(file, BytesCodec::new());
             Body::wrap_stream(stream)
@@ -28,5 +36,148 @@ pub async fn upload_image<P: AsRef<Path>>(path: P, url: &str) -> Result<()> {
             url
         ));
     }
+    verify_upload_checksum(res.headers(), &digest).map_err(|e| {
+        anyhow!(
+            "Failed to verify upload of {} to {}: {}",
+            path.as_ref().display(),
+            url,
+            e
+        )
+    })
+}
+
+fn sha256_of_file(path: &Path) -> Result<[u8; 32]> {
+    let mut reader = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut reader, &mut hasher)?;
+    Ok(hasher.finish())
+}
+
+/// Checks the checksum reported by the object store in response to an upload against the
+/// `expected` SHA-256 of the uploaded file. This uses the `x-amz-checksum-sha256` header if
+/// present, else the ETag if it is a SHA-256 (e.g. not the MD5 that S3 uses). If the store reports
+/// neither, the upload cannot be verified and is accepted.
+fn verify_upload_checksum(headers: &HeaderMap, expected: &[u8; 32]) -> Result<()> {
+    if let Some(checksum) = headers.get(CHECKSUM_SHA256_HEADER) {
+        let expected = base64::encode(expected);
+        if checksum.to_str()? != expected {
+            return Err(anyhow!(
+                "{} mismatch: expected {}, got {:?}",
+                CHECKSUM_SHA256_HEADER,
+                expected,
+                checksum
+            ));
+        }
+        return Ok(());
+    }
+
+    let etag = headers
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(|etag| etag.trim_start_matches("W/").trim_matches('"'));
+    match etag {
+        Some(etag) if etag.len() == 64 && etag.chars().all(|c| c.is_ascii_hexdigit()) => {
+            let expected = hex::encode(expected);
+            if !etag.eq_ignore_ascii_case(&expected) {
+                return Err(anyhow!(
+                    "ETag mismatch: expected {}, got {}",
+                    expected,
+                    etag
+                ));
+            }
+        }
+        _ => debug!("Object store reported no SHA-256 checksum, skipping verification"),
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const IMAGE: &[u8] = b"config image";
+
+    fn image_file() -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(IMAGE).unwrap();
+        file
+    }
+
+    fn image_sha256() -> [u8; 32] {
+        Sha256::hash(IMAGE)
+    }
+
+    #[tokio::test]
+    async fn test_upload_image_verifies_checksum() {
+        let mut server = mockito::Server::new_async().await;
+        let file = image_file();
+        let checksum = base64::encode(image_sha256());
+
+        let ok = server
+            .mock("PUT", "/ok")
+            .match_header(CHECKSUM_SHA256_HEADER, checksum.as_str())
+            .with_status(200)
+            .with_header(CHECKSUM_SHA256_HEADER, &checksum)
+            .create_async()
+            .await;
+        upload_image(file.path(), &format!("{}/ok", server.url()))
+            .await
+            .expect("upload with a matching checksum should succeed");
+        ok.assert_async().await;
+
+        // No checksum reported, nothing to verify.
+        server
+            .mock("PUT", "/unverified")
+            .with_status(200)
+            .with_header("ETag", "\"d41d8cd98f00b204e9800998ecf8427e\"")
+            .create_async()
+            .await;
+        upload_image(file.path(), &format!("{}/unverified", server.url()))
+            .await
+            .expect("upload without a SHA-256 checksum should succeed");
+    }
+
+    #[tokio::test]
+    async fn test_upload_image_fails_on_checksum_mismatch() {
+        let mut server = mockito::Server::new_async().await;
+        let file = image_file();
+
+        server
+            .mock("PUT", "/wrong-checksum")
+            .with_status(200)
+            .with_header(CHECKSUM_SHA256_HEADER, &base64::encode([0; 32]))
+            .create_async()
+            .await;
+        let err = upload_image(file.path(), &format!("{}/wrong-checksum", server.url()))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("x-amz-checksum-sha256 mismatch"),
+            "{}",
+            err
+        );
+
+        server
+            .mock("PUT", "/wrong-etag")
+            .with_status(200)
+            .with_header("ETag", &format!("\"{}\"", hex::encode([0; 32])))
+            .create_async()
+            .await;
+        let err = upload_image(file.path(), &format!("{}/wrong-etag", server.url()))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("ETag mismatch"), "{}", err);
+
+        // Non-200 responses are still reported as failures.
+        server
+            .mock("PUT", "/error")
+            .with_status(500)
+            .create_async()
+            .await;
+        let err = upload_image(file.path(), &format!("{}/error", server.url()))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Failed to upload"), "{}", err);
+    }
+}
@@ -9,6 +9,7 @@ use url::Url;
 use anyhow::Result;
 use backon::Retryable;
 use backon::{ConstantBuilder, ExponentialBuilder};
+use futures::future::BoxFuture;
 use k8s_openapi::api::core::v1::{
     ConfigMap, PersistentVolumeClaim, Pod, Secret, Service, TypedLocalObjectReference,
 };
@@ -17,7 +18,7 @@ use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
 use k8s_openapi::chrono::DateTime;
 use k8s_openapi::chrono::Duration;
 use k8s_openapi::chrono::Utc;
-use kube::api::{DeleteParams, PostParams};
+use kube::api::{DeleteParams, ListParams, PostParams};
 use kube::core::ObjectMeta;
 use kube::ResourceExt;
 use kube::{
@@ -54,24 +55,24 @@ pub struct K8sClient {
 }
 
 impl K8sClient {
-    pub async fn new() -> Result<Self> {
+    pub async fn new(namespace: &str) -> Result<Self> {
         let client = Client::try_default().await?;
-        let api_pod: Api<Pod> = Api::namespaced(client.clone(), &TNET_NAMESPACE);
-        let api_pvc: Api<PersistentVolumeClaim> = Api::namespaced(client.clone(), &TNET_NAMESPACE);
-        let api_svc: Api<Service> = Api::namespaced(client.clone(), &TNET_NAMESPACE);
-        let api_ingress: Api<Ingress> = Api::namespaced(client.clone(), &TNET_NAMESPACE);
+        let api_pod: Api<Pod> = Api::namespaced(client.clone(), namespace);
+        let api_pvc: Api<PersistentVolumeClaim> = Api::namespaced(client.clone(), namespace);
+        let api_svc: Api<Service> = Api::namespaced(client.clone(), namespace);
+        let api_ingress: Api<Ingress> = Api::namespaced(client.clone(), namespace);
 
         let gvk = GroupVersionKind::gvk("cdi.kubevirt.io", "v1beta1", "DataVolume");
         let (ar, _caps) = kube::discovery::pinned_kind(&client, &gvk).await?;
-        let api_dv = Api::<DynamicObject>::namespaced_with(client.clone(), &TNET_NAMESPACE, &ar);
+        let api_dv = Api::<DynamicObject>::namespaced_with(client.clone(), namespace, &ar);
 
         let gvk = GroupVersionKind::gvk("kubevirt.io", "v1", "VirtualMachine");
         let (ar, _caps) = kube::discovery::pinned_kind(&client, &gvk).await?;
-        let api_vm = Api::<DynamicObject>::namespaced_with(client.clone(), &TNET_NAMESPACE, &ar);
+        let api_vm = Api::<DynamicObject>::namespaced_with(client.clone(), namespace, &ar);
 
         let gvk = GroupVersionKind::gvk("kubevirt.io", "v1", "VirtualMachineInstance");
         let (ar, _caps) = kube::discovery::pinned_kind(&client, &gvk).await?;
-        let api_vmi = Api::<DynamicObject>::namespaced_with(client.clone(), &TNET_NAMESPACE, &ar);
+        let api_vmi = Api::<DynamicObject>::namespaced_with(client.clone(), namespace, &ar);
 
         let gvk = GroupVersionKind::gvk("crd.projectcalico.org", "v1", "IPReservation");
         let (ar, _caps) = kube::discovery::pinned_kind(&client, &gvk).await?;
@@ -97,9 +98,16 @@ pub struct TNode {
     pub ipv6_addr: Option<Ipv6Addr>,
     pub config_url: Option<String>,
     owner: ConfigMap,
+    /// The namespace of the testnet, if not the default `TNET_NAMESPACE`.
+    #[serde(default)]
+    namespace: Option<String>,
 }
 
 impl TNode {
+    fn k8s_namespace(&self) -> &str {
+        namespace_or_default(self.namespace.as_deref())
+    }
+
     fn owner_reference(&self) -> OwnerReference {
         OwnerReference {
             api_version: k8s_openapi::api_version(&self.owner).to_owned(),
@@ -124,7 +132,7 @@ impl TNode {
         let client = Client::try_default().await?;
         let gvk = GroupVersionKind::gvk("cdi.kubevirt.io", "v1beta1", "DataVolume");
         let (ar, _) = kube::discovery::pinned_kind(&client, &gvk).await?;
-        let api = Api::<DynamicObject>::namespaced_with(client, &TNET_NAMESPACE, &ar);
+        let api = Api::<DynamicObject>::namespaced_with(client, self.k8s_namespace(), &ar);
 
         let dvname = format!("{}-{}", self.name.clone().unwrap(), dv_name);
         let source = DvSource::url(format!(
@@ -139,15 +147,28 @@ impl TNode {
     }
 
     pub async fn start(&self) -> Result<()> {
-        start_vm(&self.name.clone().expect("name missing")).await
+        start_vm_in(
+            self.k8s_namespace(),
+            &self.name.clone().expect("name missing"),
+        )
+        .await
     }
 
     pub async fn stop(&self) -> Result<()> {
-        stop_vm(&self.name.clone().expect("name missing")).await
+        stop_vm_in(
+            self.k8s_namespace(),
+            &self.name.clone().expect("name missing"),
+        )
+        .await
     }
 
     pub async fn add_volume(&self, name: &str) -> Result<()> {
-        add_volume(&self.name.clone().expect("name missing"), name).await
+        add_volume_in(
+            self.k8s_namespace(),
+            &self.name.clone().expect("name missing"),
+            name,
+        )
+        .await
     }
 }
 
@@ -157,11 +178,20 @@ pub struct TNet {
     pub unique_name: Option<String>,
     pub version: String,
     pub image_url: String,
+    /// Whether `image_url` was set explicitly, in which case `version` does not override it.
+    #[serde(default)]
+    image_url_overridden: bool,
     pub config_url: Option<String>,
     pub access_key: Option<String>,
     pub nodes: Vec<TNode>,
     pub owner: ConfigMap,
     terminate_time: Option<DateTime<Utc>>,
+    /// The number of vCPUs and the memory (in GiB) of the VMs of the IC nodes. If not set, the
+    /// resources of the `CreateVmRequest` are used.
+    node_resources: Option<(u32, u32)>,
+    /// The namespace of all resources of the testnet. If not set, `TNET_NAMESPACE` is used.
+    #[serde(default)]
+    namespace: Option<String>,
     #[serde(skip)]
     pub logger: Option<Logger>,
 }
@@ -188,9 +218,96 @@ impl TNet {
         tnet
     }
 
+    /// Attaches to a testnet that was created (e.g. by another process) with `create`, given the
+    /// unique name of its owner config map. The nodes are reconstructed from the VMs labeled with
+    /// that name. Their `node_id`s are not recorded on the VMs and are left unset.
+    ///
+    /// The testnet is looked up in `namespace`, or in `TNET_NAMESPACE` if not given.
+    pub async fn from_existing(unique_name: &str, namespace: Option<&str>) -> Result<Self> {
+        let k8s_client = &K8sClient::new(namespace_or_default(namespace)).await?;
+        let client = Client::try_default().await?;
+        let config_map_api = Api::<ConfigMap>::namespaced(client, namespace_or_default(namespace));
+
+        let owner = (|| async { config_map_api.get(unique_name).await })
+            .retry(&ExponentialBuilder::default())
+            .await?;
+        let mut tnet = Self::from_owner(owner, namespace)?;
+
+        let list_params =
+            ListParams::default().labels(&format!("{}={}", TNET_NAME_LABEL, unique_name));
+        let mut vms = (|| async { k8s_client.api_vm.list(&list_params).await })
+            .retry(&ExponentialBuilder::default())
+            .await?
+            .items;
+        // Restore the creation order, which `vm_create` relies on for naming further VMs.
+        vms.sort_by_key(|vm| vm_index(unique_name, &vm.name_any()));
+        tnet.nodes = vms.iter().map(|vm| tnet.node_from_vm(vm)).collect();
+        Ok(tnet)
+    }
+
+    /// Reconstructs a testnet without nodes from its owner config map, found in `namespace` (or in
+    /// `TNET_NAMESPACE`, if not given).
+    fn from_owner(owner: ConfigMap, namespace: Option<&str>) -> Result<Self> {
+        let unique_name = owner
+            .metadata
+            .name
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("owner config map has no name"))?;
+        let group_name = owner
+            .labels()
+            .get(TNET_NAME_LABEL)
+            .cloned()
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "config map {} has no {} label",
+                    unique_name,
+                    TNET_NAME_LABEL
+                )
+            })?;
+        let terminate_time = owner
+            .annotations()
+            .get(TNET_TERMINATE_TIME_ANNOTATION)
+            .map(|time| DateTime::parse_from_rfc3339(time).map(|time| time.with_timezone(&Utc)))
+            .transpose()?;
+
+        Ok(Self {
+            group_name,
+            config_url: Some(format!(
+                "{}/{}/{}",
+                *TNET_CONFIG_URL, *TNET_BUCKET, unique_name
+            )),
+            unique_name: Some(unique_name),
+            owner,
+            terminate_time,
+            namespace: namespace.map(str::to_string),
+            ..Default::default()
+        })
+    }
+
+    /// Reconstructs the node backed by the given VM, as created by `vm_create`.
+    fn node_from_vm(&self, vm: &DynamicObject) -> TNode {
+        let name = vm.name_any();
+        let ipv6_addr = vm.data["spec"]["template"]["metadata"]["annotations"]
+            ["cni.projectcalico.org/ipAddrs"]
+            .as_str()
+            .and_then(|ips| serde_json::from_str::<Vec<String>>(ips).ok())
+            .and_then(|ips| ips.iter().find_map(|ip| Ipv6Addr::from_str(ip).ok()));
+        TNode {
+            node_id: None,
+            ipv6_addr,
+            config_url: self
+                .config_url
+                .as_ref()
+                .map(|config_url| format!("{}/{}", config_url, name)),
+            name: Some(name),
+            owner: self.owner.clone(),
+            namespace: self.namespace.clone(),
+        }
+    }
+
     pub fn version(mut self, version: &str) -> Self {
         self.version = version.to_string();
-        if self.image_url.is_empty() {
+        if !self.image_url_overridden {
             self.image_url = format!(
                 "{}/ic/{}/guest-os/disk-img-dev/disk-img.tar.zst",
                 *TNET_CDN_URL, self.version
@@ -199,11 +316,39 @@ impl TNet {
         self
     }
 
+    /// Sets the URL of the guestos image, taking precedence over the one derived from `version`.
     pub fn image_url(mut self, url: &str) -> Self {
         self.image_url = url.to_string();
+        self.image_url_overridden = true;
         self
     }
 
+    pub fn node_resources(mut self, cpu: u32, memory_gib: u32) -> Self {
+        self.node_resources = Some((cpu, memory_gib));
+        self
+    }
+
+    /// Sets the namespace of the testnet's resources, overriding `TNET_NAMESPACE`.
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.to_string());
+        self
+    }
+
+    /// Returns the namespace of the testnet's resources.
+    pub fn k8s_namespace(&self) -> &str {
+        namespace_or_default(self.namespace.as_deref())
+    }
+
+    /// Returns the number of vCPUs and the memory (in KiB) of a VM of the given type.
+    fn vm_resources(&self, vm_req: &CreateVmRequest, vm_type: &ImageType) -> (u64, u64) {
+        match (self.node_resources, vm_type) {
+            (Some((cpu, memory_gib)), ImageType::IcOsImage) => {
+                (cpu.into(), u64::from(memory_gib) * 1024 * 1024)
+            }
+            _ => (vm_req.vcpus.get(), vm_req.memory_kibibytes.get()),
+        }
+    }
+
     pub fn ttl(mut self, ttl: Duration) -> Result<Self> {
         self.terminate_time = Some(
             k8s_openapi::chrono::Utc::now()
@@ -230,7 +375,7 @@ impl TNet {
 
     pub async fn create(&mut self) -> Result<&Self> {
         let client = Client::try_default().await?;
-        let config_map_api = Api::<ConfigMap>::namespaced(client.clone(), &TNET_NAMESPACE);
+        let config_map_api = Api::<ConfigMap>::namespaced(client.clone(), self.k8s_namespace());
 
         debug!("Creating owner configmap");
         let config_map = (|| async {
@@ -279,9 +424,32 @@ impl TNet {
         Ok(self)
     }
 
+    /// Creates the testnet and runs `setup` on it. If `setup` fails, the owner config map, which
+    /// owns all other resources of the testnet, is deleted so that nothing is leaked.
+    pub async fn create_with_cleanup<F>(&mut self, setup: F) -> Result<&Self>
+    where
+        F: for<'a> FnOnce(&'a TNet) -> BoxFuture<'a, Result<()>>,
+    {
+        self.create().await?;
+        let this = &*self;
+        cleanup_on_error(setup(this).await, || async {
+            warn!(
+                "Setup of tnet {} failed, deleting it",
+                this.unique_name.clone().unwrap_or_default()
+            );
+            this.delete_owner().await
+        })
+        .await?;
+        Ok(self)
+    }
+
     pub async fn delete(self) -> Result<()> {
+        self.delete_owner().await
+    }
+
+    async fn delete_owner(&self) -> Result<()> {
         let client = Client::try_default().await?;
-        let api: Api<ConfigMap> = Api::namespaced(client.clone(), &TNET_NAMESPACE);
+        let api: Api<ConfigMap> = Api::namespaced(client.clone(), self.k8s_namespace());
         api.delete(
             &self.unique_name.clone().expect("missing unique name"),
             &Default::default(),
@@ -290,6 +458,40 @@ impl TNet {
         Ok(())
     }
 
+    /// Deletes the testnet(s) with the given (human-readable) group name by deleting their owner
+    /// config maps, which own all other resources of the testnet.
+    ///
+    /// Fails if no testnet has the given name, or if more than one has and `all` is not set. The
+    /// testnets are looked up in `namespace`, or in `TNET_NAMESPACE` if not given.
+    pub async fn delete_by_name(name: &str, all: bool, namespace: Option<&str>) -> Result<()> {
+        let client = Client::try_default().await?;
+        let api: Api<ConfigMap> = Api::namespaced(client.clone(), namespace_or_default(namespace));
+
+        let config_maps = api.list(&Default::default()).await?.items;
+        let owners = owner_config_maps_named(&config_maps, name);
+        match owners.len() {
+            0 => anyhow::bail!("no testnet named {}", name),
+            1 => (),
+            n if !all => anyhow::bail!(
+                "{} testnets named {}: {}",
+                n,
+                name,
+                owners
+                    .iter()
+                    .map(|cm| cm.name_any())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            _ => (),
+        }
+
+        for owner in owners {
+            debug!("Deleting owner configmap {}", owner.name_any());
+            api.delete(&owner.name_any(), &Default::default()).await?;
+        }
+        Ok(())
+    }
+
     pub async fn deploy_guestos_image(&self) -> Result<()> {
         let image_name = &format!("{}-image-guestos", self.owner.name_any());
         self.deploy_image(image_name, &self.image_url).await?;
@@ -306,7 +508,7 @@ impl TNet {
         let client = Client::try_default().await?;
         let gvk = GroupVersionKind::gvk("cdi.kubevirt.io", "v1beta1", "DataVolume");
         let (ar, _) = kube::discovery::pinned_kind(&client, &gvk).await?;
-        let api_dv = Api::<DynamicObject>::namespaced_with(client, &TNET_NAMESPACE, &ar);
+        let api_dv = Api::<DynamicObject>::namespaced_with(client, self.k8s_namespace(), &ar);
 
         let source = DvSource::url(url.into());
         let dvinfo = DvInfo::new(name, source, DataVolumeContentType::Archive, "50Gi");
@@ -337,7 +539,7 @@ impl TNet {
         vm_req: CreateVmRequest,
         vm_type: ImageType,
     ) -> Result<VMCreateResponse> {
-        let k8s_client = &K8sClient::new().await?;
+        let k8s_client = &K8sClient::new(self.k8s_namespace()).await?;
         let vm_name = format!(
             "{}-{}",
             self.unique_name.clone().expect("no unique name"),
@@ -467,11 +669,12 @@ spec:
         .retry(&ExponentialBuilder::default())
         .await?;
 
+        let (vcpus, memory_kibibytes) = self.vm_resources(&vm_req, &vm_type);
         create_vm(
             &k8s_client.api_vm,
             &vm_name.clone(),
-            &vm_req.vcpus.to_string(),
-            &vm_req.memory_kibibytes.to_string(),
+            &vcpus.to_string(),
+            &memory_kibibytes.to_string(),
             ipv4,
             ipv6,
             false,
@@ -624,6 +827,7 @@ spec:
             ipv6_addr: ipv6.into(),
             config_url: format!("{}/{}", self.config_url.clone().unwrap(), vm_name.clone()).into(),
             owner: self.owner.clone(),
+            namespace: self.namespace.clone(),
         });
 
         Ok(VMCreateResponse {
@@ -632,8 +836,8 @@ spec:
             mac6: "00:11:22:33:44:55".to_string(),
             hostname: vm_name,
             spec: VmSpec {
-                v_cpus: vm_req.vcpus.get(),
-                memory_ki_b: vm_req.memory_kibibytes.get(),
+                v_cpus: vcpus,
+                memory_ki_b: memory_kibibytes,
             },
         })
     }
@@ -652,9 +856,27 @@ spec:
         Ok(())
     }
 
+    /// Waits until the `VirtualMachineInstance`s of all nodes are running. Fails early if any of
+    /// them has failed, or once `timeout` has elapsed.
+    pub async fn wait_until_ready(&self, timeout: tokio::time::Duration) -> Result<()> {
+        let k8s_client = &K8sClient::new(self.k8s_namespace()).await?;
+        let names: Vec<String> = self.nodes.iter().filter_map(|n| n.name.clone()).collect();
+        wait_until_vmis_running(&names, timeout, |name| async move {
+            let vmi = (|| async { k8s_client.api_vmi.get_opt(&name).await })
+                .retry(&ExponentialBuilder::default())
+                .await?;
+            Ok(vmi.and_then(|vmi| {
+                vmi.data["status"]["phase"]
+                    .as_str()
+                    .map(|phase| phase.to_string())
+            }))
+        })
+        .await
+    }
+
     pub async fn acquire_playnet_certificate(&self) -> Result<PlaynetCertificate> {
         let client = Client::try_default().await?;
-        let config_map_api = Api::<ConfigMap>::namespaced(client.clone(), &TNET_NAMESPACE);
+        let config_map_api = Api::<ConfigMap>::namespaced(client.clone(), self.k8s_namespace());
 
         let playnet_prefix = format!("{}-playnet-", self.unique_name.clone().unwrap());
         let config_map = (|| async {
@@ -738,7 +960,7 @@ spec:
 
         slog::info!(self.logger.as_ref().unwrap(), "Using playnet {playnet_id}");
 
-        let secret_api = Api::<Secret>::namespaced(client.clone(), &TNET_NAMESPACE);
+        let secret_api = Api::<Secret>::namespaced(client.clone(), self.k8s_namespace());
         let playnet_secret = secret_api.get(TNET_PLAYNET_SECRET).await?;
 
         let playnet = playnet_secret
@@ -787,7 +1009,7 @@ spec:
 
     pub async fn create_playnet_dns_records(&self, dns_records: Vec<DnsRecord>) -> Result<String> {
         let client = Client::try_default().await?;
-        let api_svc: Api<Service> = Api::namespaced(client.clone(), &TNET_NAMESPACE);
+        let api_svc: Api<Service> = Api::namespaced(client.clone(), self.k8s_namespace());
         for dns_record in dns_records {
             for record in dns_record.records {
                 let mut svc: Service = serde_yaml::from_str(&format!(
@@ -828,9 +1050,93 @@ spec:
     }
 }
 
+/// Runs `cleanup` if `result` is an error. The original error is returned either way; a failed
+/// cleanup is only logged.
+async fn cleanup_on_error<T, C, Fut>(result: Result<T>, cleanup: C) -> Result<T>
+where
+    C: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    if result.is_err() {
+        if let Err(err) = cleanup().await {
+            error!("Cleanup failed: {:?}", err);
+        }
+    }
+    result
+}
+
+/// Polls the phase of the VMIs with the given names until all of them are `Running`. A VMI that
+/// does not exist yet has no phase.
+async fn wait_until_vmis_running<F, Fut>(
+    names: &[String],
+    timeout: tokio::time::Duration,
+    get_phase: F,
+) -> Result<()>
+where
+    F: Fn(String) -> Fut,
+    Fut: std::future::Future<Output = Result<Option<String>>>,
+{
+    tokio::time::timeout(timeout, async {
+        loop {
+            let mut all_running = true;
+            for name in names {
+                match get_phase(name.clone()).await?.as_deref() {
+                    Some("Running") => {}
+                    Some("Failed") => anyhow::bail!("VMI {} has failed", name),
+                    _ => all_running = false,
+                }
+            }
+            if all_running {
+                return anyhow::Ok(());
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        }
+    })
+    .await
+    .map_err(|_| {
+        anyhow::anyhow!(
+            "timed out after {:?} waiting for VMIs to be running",
+            timeout
+        )
+    })?
+}
+
+/// Returns the index of a VM named `{unique_name}-{index}[-{name}]` by `vm_create`, or `None` if
+/// the name does not have that form.
+fn vm_index(unique_name: &str, vm_name: &str) -> Option<usize> {
+    vm_name
+        .strip_prefix(unique_name)?
+        .strip_prefix('-')?
+        .split('-')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Returns the given namespace, or `TNET_NAMESPACE` if none is given.
+fn namespace_or_default(namespace: Option<&str>) -> &str {
+    namespace.unwrap_or(&TNET_NAMESPACE)
+}
+
+/// Returns the owner config maps of the testnets with the given group name.
+fn owner_config_maps_named<'a>(config_maps: &'a [ConfigMap], name: &str) -> Vec<&'a ConfigMap> {
+    config_maps
+        .iter()
+        .filter(|cm| {
+            cm.metadata
+                .labels
+                .as_ref()
+                .and_then(|l| l.get(TNET_NAME_LABEL))
+                .is_some_and(|n| n == name)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::driver::farm::VmType;
+    use crate::driver::ic::{AmountOfMemoryKiB, NrOfVCPUs};
 
     #[tokio::test]
     async fn test_tnet_new() {
@@ -841,6 +1147,42 @@ mod tests {
         assert_eq!(tnet.config_url, None);
     }
 
+    #[tokio::test]
+    async fn test_tnet_namespace() {
+        let tnet = TNet::new("testnet").expect("should create a testnet");
+        assert_eq!(tnet.k8s_namespace(), *TNET_NAMESPACE);
+
+        let tnet = tnet.namespace("custom");
+        assert_eq!(tnet.namespace.as_deref(), Some("custom"));
+        assert_eq!(tnet.k8s_namespace(), "custom");
+
+        // Nodes inherit the namespace of their testnet.
+        let vm = DynamicObject::new(
+            "testnet-abcde-0",
+            &kube::core::ApiResource::erase::<ConfigMap>(&()),
+        );
+        assert_eq!(tnet.node_from_vm(&vm).k8s_namespace(), "custom");
+
+        // The namespace used by `from_existing` and `delete_by_name` to look up testnets.
+        assert_eq!(namespace_or_default(None), *TNET_NAMESPACE);
+        assert_eq!(namespace_or_default(Some("custom")), "custom");
+
+        // Testnets attached to via `from_existing` (and their nodes) keep the namespace they
+        // were found in.
+        let owner = ConfigMap {
+            metadata: ObjectMeta {
+                name: "testnet-abcde".to_string().into(),
+                labels: BTreeMap::from([(TNET_NAME_LABEL.to_string(), "testnet".to_string())])
+                    .into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let tnet = TNet::from_owner(owner, Some("custom")).expect("should reconstruct the testnet");
+        assert_eq!(tnet.k8s_namespace(), "custom");
+        assert_eq!(tnet.node_from_vm(&vm).k8s_namespace(), "custom");
+    }
+
     #[tokio::test]
     async fn test_tnet_version() {
         let tnet = TNet::new("testnet")
@@ -852,4 +1194,291 @@ mod tests {
             "https://download.dfinity.systems/ic/1.0.0/guest-os/disk-img-dev/disk-img.tar.zst"
         );
     }
+
+    #[tokio::test]
+    async fn test_tnet_image_url_overrides_version() {
+        let url = "http://localhost:8080/disk-img.tar.zst";
+        for tnet in [
+            TNet::new("testnet")
+                .expect("should create a testnet")
+                .image_url(url)
+                .version("1.0.0"),
+            TNet::new("testnet")
+                .expect("should create a testnet")
+                .version("1.0.0")
+                .image_url(url),
+        ] {
+            assert_eq!(tnet.version, "1.0.0");
+            assert_eq!(tnet.image_url, url);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tnet_version_updates_image_url() {
+        let tnet = TNet::new("testnet")
+            .expect("should create a testnet")
+            .version("1.0.0")
+            .version("2.0.0");
+        assert_eq!(
+            tnet.image_url,
+            "https://download.dfinity.systems/ic/2.0.0/guest-os/disk-img-dev/disk-img.tar.zst"
+        );
+    }
+
+    #[test]
+    fn test_owner_config_maps_named() {
+        let config_map = |name: &str, labels: &[(&str, &str)]| ConfigMap {
+            metadata: ObjectMeta {
+                name: name.to_string().into(),
+                labels: labels
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect::<BTreeMap<String, String>>()
+                    .into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let config_maps = vec![
+            config_map("testnet-abcde", &[(TNET_NAME_LABEL, "testnet")]),
+            config_map("other-fghij", &[(TNET_NAME_LABEL, "other")]),
+            config_map("testnet-klmno", &[(TNET_NAME_LABEL, "testnet")]),
+            config_map("testnet-abcde-playnet-1", &[(TNET_PLAYNET_LABEL, "1")]),
+            config_map("unlabeled", &[]),
+        ];
+
+        let names = |name| {
+            owner_config_maps_named(&config_maps, name)
+                .into_iter()
+                .map(|cm| cm.name_any())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names("testnet"), vec!["testnet-abcde", "testnet-klmno"]);
+        assert_eq!(names("other"), vec!["other-fghij"]);
+        assert!(names("missing").is_empty());
+    }
+
+    #[test]
+    fn test_tnet_from_owner() {
+        let owner = ConfigMap {
+            metadata: ObjectMeta {
+                name: "testnet-abcde".to_string().into(),
+                labels: BTreeMap::from([(TNET_NAME_LABEL.to_string(), "testnet".to_string())])
+                    .into(),
+                annotations: BTreeMap::from([(
+                    TNET_TERMINATE_TIME_ANNOTATION.to_string(),
+                    "2024-01-02T03:04:05Z".to_string(),
+                )])
+                .into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let tnet = TNet::from_owner(owner.clone(), None).expect("should reconstruct the testnet");
+        assert_eq!(tnet.group_name, "testnet");
+        assert_eq!(tnet.k8s_namespace(), *TNET_NAMESPACE);
+        assert_eq!(tnet.unique_name.as_deref(), Some("testnet-abcde"));
+        assert_eq!(
+            tnet.config_url,
+            Some(format!(
+                "{}/{}/testnet-abcde",
+                *TNET_CONFIG_URL, *TNET_BUCKET
+            ))
+        );
+        assert_eq!(
+            tnet.terminate_time.map(|time| time.to_rfc3339()),
+            Some("2024-01-02T03:04:05+00:00".to_string())
+        );
+        assert!(tnet.nodes.is_empty());
+
+        // Reconstruct a node from a VM as created by `vm_create`.
+        let ipv6 = Ipv6Addr::from_str("2001:db8::1").unwrap();
+        let vm = vm_spec(
+            "testnet-abcde-0",
+            "2",
+            "1024",
+            Ipv4Addr::new(10, 0, 0, 1),
+            ipv6,
+            false,
+            OwnerReference {
+                name: "testnet-abcde".to_string(),
+                ..Default::default()
+            },
+            None,
+            ImageType::IcOsImage,
+        )
+        .unwrap();
+        let node = tnet.node_from_vm(&vm);
+        assert_eq!(node.name.as_deref(), Some("testnet-abcde-0"));
+        assert_eq!(node.ipv6_addr, Some(ipv6));
+        assert_eq!(
+            node.config_url,
+            Some(format!(
+                "{}/{}/testnet-abcde/testnet-abcde-0",
+                *TNET_CONFIG_URL, *TNET_BUCKET
+            ))
+        );
+
+        // The group name label is required.
+        let mut unlabeled = owner;
+        unlabeled.metadata.labels = None;
+        assert!(TNet::from_owner(unlabeled, None).is_err());
+    }
+
+    #[test]
+    fn test_vm_index() {
+        assert_eq!(vm_index("testnet-abcde", "testnet-abcde-0"), Some(0));
+        assert_eq!(vm_index("testnet-abcde", "testnet-abcde-12"), Some(12));
+        assert_eq!(
+            vm_index("testnet-abcde", "testnet-abcde-3-prometheus"),
+            Some(3)
+        );
+        assert_eq!(vm_index("testnet-abcde", "other-fghij-0"), None);
+        assert_eq!(vm_index("testnet-abcde", "testnet-abcde"), None);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_on_error_deletes_owner_on_failure() {
+        let owners = std::sync::Mutex::new(vec!["testnet-abcde".to_string()]);
+        let delete_owner = || async {
+            owners.lock().unwrap().clear();
+            Ok(())
+        };
+
+        let result: Result<()> = cleanup_on_error(Ok(()), delete_owner).await;
+        assert!(result.is_ok());
+        assert_eq!(*owners.lock().unwrap(), vec!["testnet-abcde".to_string()]);
+
+        let result: Result<()> = cleanup_on_error(
+            Err(anyhow::anyhow!("DataVolume import timed out")),
+            delete_owner,
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "DataVolume import timed out"
+        );
+        assert!(owners.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_on_error_keeps_original_error() {
+        let result: Result<()> = cleanup_on_error(
+            Err(anyhow::anyhow!("DataVolume import timed out")),
+            || async { anyhow::bail!("failed to delete owner") },
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "DataVolume import timed out"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_vmis_running_times_out_without_vmis() {
+        let names = vec!["testnet-abcde-0".to_string()];
+        let err =
+            wait_until_vmis_running(&names, tokio::time::Duration::from_millis(100), |_| async {
+                Ok(None)
+            })
+            .await
+            .expect_err("should time out");
+        assert!(err.to_string().contains("timed out"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_vmis_running_aborts_on_failed_vmi() {
+        let names = vec!["testnet-abcde-0".to_string(), "testnet-abcde-1".to_string()];
+        let err = wait_until_vmis_running(
+            &names,
+            tokio::time::Duration::from_secs(60),
+            |name| async move {
+                Ok(Some(
+                    if name.ends_with("-1") {
+                        "Failed"
+                    } else {
+                        "Scheduling"
+                    }
+                    .to_string(),
+                ))
+            },
+        )
+        .await
+        .expect_err("should fail");
+        assert_eq!(err.to_string(), "VMI testnet-abcde-1 has failed");
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_vmis_running_succeeds() {
+        let names = vec!["testnet-abcde-0".to_string(), "testnet-abcde-1".to_string()];
+        wait_until_vmis_running(&names, tokio::time::Duration::from_secs(60), |_| async {
+            Ok(Some("Running".to_string()))
+        })
+        .await
+        .expect("should be ready");
+    }
+
+    fn test_vm_request() -> CreateVmRequest {
+        CreateVmRequest::new(
+            "node".to_string(),
+            VmType::Production,
+            NrOfVCPUs::new(6),
+            AmountOfMemoryKiB::new(25165824),
+            vec![],
+            ImageLocation::PersistentVolumeClaim {
+                name: "image".to_string(),
+            },
+            None,
+            false,
+            None,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_tnet_node_resources() {
+        let tnet = TNet::new("testnet")
+            .expect("should create a testnet")
+            .node_resources(16, 64);
+        let vm_req = test_vm_request();
+
+        let (vcpus, memory_kibibytes) = tnet.vm_resources(&vm_req, &ImageType::IcOsImage);
+        assert_eq!((vcpus, memory_kibibytes), (16, 64 * 1024 * 1024));
+        // Only the VMs of IC nodes are affected.
+        assert_eq!(
+            tnet.vm_resources(&vm_req, &ImageType::UniversalImage),
+            (6, 25165824)
+        );
+
+        let vm = vm_spec(
+            "testnet-abcde-0",
+            &vcpus.to_string(),
+            &memory_kibibytes.to_string(),
+            Ipv4Addr::LOCALHOST,
+            Ipv6Addr::LOCALHOST,
+            false,
+            OwnerReference {
+                name: "testnet-abcde".to_string(),
+                ..Default::default()
+            },
+            None,
+            ImageType::IcOsImage,
+        )
+        .expect("should build the VM spec");
+        let domain = &vm.data["spec"]["template"]["spec"]["domain"];
+        assert_eq!(domain["cpu"]["cores"], 16);
+        assert_eq!(domain["resources"]["requests"]["memory"], "67108864Ki");
+    }
+
+    #[test]
+    fn test_tnet_default_vm_resources() {
+        let tnet = TNet::new("testnet").expect("should create a testnet");
+        let vm_req = test_vm_request();
+
+        assert_eq!(
+            tnet.vm_resources(&vm_req, &ImageType::IcOsImage),
+            (6, 25165824)
+        );
+    }
 }
@@ -180,6 +180,33 @@ pub async fn create_vm(
     vm_type: ImageType,
 ) -> Result<()> {
     info!("Creating virtual machine {}", name);
+    let data = vm_spec(
+        name, cpus, memory, ipv4, ipv6, running, owner, access_key, vm_type,
+    )?;
+    let response = api
+        .patch(
+            name,
+            &PatchParams::apply("system-driver"),
+            &Patch::Apply(data),
+        )
+        .await?;
+    debug!("Creating virtual machine response: {:?}", response);
+    info!("Creating virtual machine {} complete", name);
+    Ok(())
+}
+
+/// Builds the `VirtualMachine` object created by `create_vm`.
+pub(crate) fn vm_spec(
+    name: &str,
+    cpus: &str,
+    memory: &str,
+    ipv4: Ipv4Addr,
+    ipv6: Ipv6Addr,
+    running: bool,
+    owner: OwnerReference,
+    access_key: Option<String>,
+    vm_type: ImageType,
+) -> Result<DynamicObject> {
     let template = match vm_type {
         ImageType::IcOsImage => NODE_TEMPLATE.to_string(),
         _ => UVM_TEMPLATE.replace("{pub_key}", &access_key.unwrap()),
@@ -194,16 +221,7 @@ pub async fn create_vm(
         .replace("{ipv6}", &ipv6.to_string());
     let mut data: DynamicObject = serde_yaml::from_str(&yaml)?;
     data.metadata.owner_references = vec![owner].into();
-    let response = api
-        .patch(
-            name,
-            &PatchParams::apply("system-driver"),
-            &Patch::Apply(data),
-        )
-        .await?;
-    debug!("Creating virtual machine response: {:?}", response);
-    info!("Creating virtual machine {} complete", name);
-    Ok(())
+    Ok(data)
 }
 
 #[derive(Debug, AsRefStr)]
@@ -218,26 +236,34 @@ enum Action {
 }
 
 pub async fn start_vm(name: &str) -> Result<()> {
-    action_vm(name, Action::Start).await
+    action_vm(&TNET_NAMESPACE, name, Action::Start).await
+}
+
+pub async fn start_vm_in(namespace: &str, name: &str) -> Result<()> {
+    action_vm(namespace, name, Action::Start).await
 }
 
 pub async fn restart_vm(name: &str) -> Result<()> {
-    action_vm(name, Action::Restart).await
+    action_vm(&TNET_NAMESPACE, name, Action::Restart).await
 }
 
 pub async fn stop_vm(name: &str) -> Result<()> {
-    action_vm(name, Action::Stop).await
+    action_vm(&TNET_NAMESPACE, name, Action::Stop).await
+}
+
+pub async fn stop_vm_in(namespace: &str, name: &str) -> Result<()> {
+    action_vm(namespace, name, Action::Stop).await
 }
 
 pub async fn delete_vm(name: &str) -> Result<()> {
-    action_vm(name, Action::Delete).await
+    action_vm(&TNET_NAMESPACE, name, Action::Delete).await
 }
 
 pub async fn destroy_vm(name: &str) -> Result<()> {
-    action_vm(name, Action::Destroy).await
+    action_vm(&TNET_NAMESPACE, name, Action::Destroy).await
 }
 
-async fn action_vm(name: &str, action: Action) -> Result<()> {
+async fn action_vm(namespace: &str, name: &str, action: Action) -> Result<()> {
     let client = Client::try_default().await?;
 
     (|| async {
@@ -248,7 +274,7 @@ async fn action_vm(name: &str, action: Action) -> Result<()> {
                         .method("PUT")
                         .uri(format!(
                             "/apis/subresources.kubevirt.io/v1/namespaces/{}/virtualmachines/{}/{}",
-                            *TNET_NAMESPACE,
+                            namespace,
                             name,
                             action.as_ref(),
                         ))
@@ -267,7 +293,7 @@ async fn action_vm(name: &str, action: Action) -> Result<()> {
                     .method("DELETE")
                     .uri(format!(
                         "/apis/kubevirt.io/v1/namespaces/{}/virtualmachines/{}",
-                        *TNET_NAMESPACE, name
+                        namespace, name
                     ))
                     .body("{}".as_bytes().to_vec())
                     .unwrap(),
@@ -286,6 +312,10 @@ async fn action_vm(name: &str, action: Action) -> Result<()> {
 }
 
 pub async fn add_volume(name: &str, volume_name: &str) -> Result<()> {
+    add_volume_in(&TNET_NAMESPACE, name, volume_name).await
+}
+
+pub async fn add_volume_in(namespace: &str, name: &str, volume_name: &str) -> Result<()> {
     let client = Client::try_default().await?;
 
     (|| async {
@@ -295,7 +325,7 @@ pub async fn add_volume(name: &str, volume_name: &str) -> Result<()> {
                         .method("PUT")
                         .uri(format!(
                             "/apis/subresources.kubevirt.io/v1/namespaces/{}/virtualmachines/{}/addvolume",
-                            *TNET_NAMESPACE,
+                            namespace,
                             name,
                         ))
                         .body(serde_json::to_vec(&serde_json::json!({
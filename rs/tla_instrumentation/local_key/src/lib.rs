@@ -57,7 +57,7 @@ macro_rules! __task_local_inner {
                 static __KEY: std::cell::RefCell<Option<$t>> = const { std::cell::RefCell::new(None) };
             }
 
-            $crate::LocalKey { inner: __KEY }
+            $crate::LocalKey { inner: __KEY, name: stringify!($name) }
         };
     };
 }
@@ -99,6 +99,8 @@ macro_rules! __task_local_inner {
 pub struct LocalKey<T: 'static> {
     #[doc(hidden)]
     pub inner: thread::LocalKey<RefCell<Option<T>>>,
+    #[doc(hidden)]
+    pub name: &'static str,
 }
 
 impl<T: 'static> LocalKey<T> {
@@ -139,6 +141,52 @@ impl<T: 'static> LocalKey<T> {
         }
     }
 
+    /// Sets the value produced by `make` as the task-local value for the
+    /// future `F`.
+    ///
+    /// Unlike [`scope`], the value is only constructed when the returned
+    /// future is first polled, so no value is built for futures that are
+    /// never polled. On completion of `scope_with`, the task-local will be
+    /// dropped.
+    ///
+    /// ### Panics
+    ///
+    /// If you poll the returned future inside a call to [`with`] or
+    /// [`try_with`] on the same `LocalKey`, then the call to `poll` will panic.
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # async fn dox() {
+    /// local_key::task_local! {
+    ///     static TRACE: Vec<String>;
+    /// }
+    ///
+    /// TRACE.scope_with(Vec::new, async move {
+    ///     println!("trace length: {}", TRACE.with(|t| t.len()));
+    /// }).await;
+    /// # }
+    /// ```
+    ///
+    /// [`scope`]: fn@Self::scope
+    /// [`with`]: fn@Self::with
+    /// [`try_with`]: fn@Self::try_with
+    pub fn scope_with<G, F>(&'static self, make: G, f: F) -> LazyTaskLocalFuture<T, G, F>
+    where
+        G: FnOnce() -> T,
+        F: Future,
+    {
+        LazyTaskLocalFuture {
+            make: Some(make),
+            inner: TaskLocalFuture {
+                local: self,
+                slot: None,
+                future: Some(f),
+                _pinned: PhantomPinned,
+            },
+        }
+    }
+
     /// Sets a value `T` as the task-local value for the closure `F`.
     ///
     /// On completion of `sync_scope`, the task-local will be dropped.
@@ -233,7 +281,10 @@ impl<T: 'static> LocalKey<T> {
     {
         match self.try_with(f) {
             Ok(res) => res,
-            Err(_) => panic!("cannot access a task-local storage value without setting it first"),
+            Err(err) => panic!(
+                "cannot access a task-local storage value without setting it first: {}",
+                err
+            ),
         }
     }
 
@@ -259,7 +310,7 @@ impl<T: 'static> LocalKey<T> {
 
         match try_with_res {
             Ok(Some(res)) => Ok(res),
-            Ok(None) | Err(_) => Err(AccessError { _private: () }),
+            Ok(None) | Err(_) => Err(AccessError { name: self.name }),
         }
     }
 }
@@ -275,11 +326,64 @@ impl<T: Clone + 'static> LocalKey<T> {
     pub fn get(&'static self) -> T {
         self.with(|v| v.clone())
     }
+
+    /// Returns a copy of the task-local value, or `None` if it is not set.
+    ///
+    /// The value is stored in a thread-local while the owning future is
+    /// polled, so a future spawned onto another task (and possibly another
+    /// worker thread) does not see it, and there is no way to propagate it
+    /// automatically. Instead, capture the value before spawning and seed
+    /// the spawned future with [`scope_captured`].
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # async fn dox() {
+    /// local_key::task_local! {
+    ///     static NUMBER: u32;
+    /// }
+    ///
+    /// let future = NUMBER.scope(1, async move {
+    ///     let captured = NUMBER.capture();
+    ///     // E.g. to be spawned onto another task.
+    ///     NUMBER.scope_captured(captured, async move {
+    ///         assert_eq!(NUMBER.get(), 1);
+    ///     })
+    /// }).await;
+    /// future.await;
+    /// # }
+    /// ```
+    ///
+    /// [`scope_captured`]: fn@Self::scope_captured
+    pub fn capture(&'static self) -> Option<T> {
+        self.try_with(|v| v.clone()).ok()
+    }
+
+    /// Sets a value previously returned by [`capture`] as the task-local
+    /// value for the future `F`.
+    ///
+    /// If `captured` is `None`, the task-local is not set inside `F`, just
+    /// as it was not set where it was captured.
+    ///
+    /// [`capture`]: fn@Self::capture
+    pub fn scope_captured<F>(&'static self, captured: Option<T>, f: F) -> TaskLocalFuture<T, F>
+    where
+        F: Future,
+    {
+        TaskLocalFuture {
+            local: self,
+            slot: captured,
+            future: Some(f),
+            _pinned: PhantomPinned,
+        }
+    }
 }
 
 impl<T: 'static> fmt::Debug for LocalKey<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.pad("LocalKey { .. }")
+        f.debug_struct("LocalKey")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
     }
 }
 
@@ -430,21 +534,74 @@ where
     }
 }
 
+pin_project! {
+    /// A future that sets a lazily constructed value `T` of a task local for
+    /// the future `F` during its execution.
+    ///
+    /// The value is produced by calling `G` when the future is first polled.
+    ///
+    /// Created by the function [`LocalKey::scope_with`](self::LocalKey::scope_with).
+    pub struct LazyTaskLocalFuture<T, G, F>
+    where
+        T: 'static,
+    {
+        make: Option<G>,
+        #[pin]
+        inner: TaskLocalFuture<T, F>,
+    }
+}
+
+impl<T: 'static, G: FnOnce() -> T, F: Future> Future for LazyTaskLocalFuture<T, G, F> {
+    type Output = F::Output;
+
+    #[track_caller]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        if let Some(make) = this.make.take() {
+            *this.inner.as_mut().project().slot = Some(make());
+        }
+        this.inner.poll(cx)
+    }
+}
+
+impl<T: 'static, G, F> fmt::Debug for LazyTaskLocalFuture<T, G, F>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyTaskLocalFuture")
+            .field("initialized", &self.make.is_none())
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
 /// An error returned by [`LocalKey::try_with`](method@LocalKey::try_with).
+///
+/// The error names the key that was accessed without being set.
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub struct AccessError {
-    _private: (),
+    name: &'static str,
+}
+
+impl AccessError {
+    /// Returns the name of the task-local key that was not set.
+    pub fn key_name(&self) -> &'static str {
+        self.name
+    }
 }
 
 impl fmt::Debug for AccessError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("AccessError").finish()
+        f.debug_struct("AccessError")
+            .field("name", &self.name)
+            .finish()
     }
 }
 
 impl fmt::Display for AccessError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Display::fmt("task-local value not set", f)
+        write!(f, "task-local value `{}` not set", self.name)
     }
 }
 
@@ -476,3 +633,100 @@ impl From<std::thread::AccessError> for ScopeInnerErr {
         Self::AccessError
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::task::{RawWaker, RawWakerVTable, Waker};
+
+    fn noop_waker() -> Waker {
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+    }
+
+    task_local! {
+        static NUMBER: u32;
+    }
+
+    #[test]
+    fn scope_with_makes_value_on_first_poll() {
+        let calls = Cell::new(0);
+        let make = || {
+            calls.set(calls.get() + 1);
+            42
+        };
+        // Yields once before reading the task-local, so the future is polled twice.
+        let mut yielded = false;
+        let fut = NUMBER.scope_with(
+            make,
+            std::future::poll_fn(|cx| {
+                if !yielded {
+                    yielded = true;
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                Poll::Ready(NUMBER.get())
+            }),
+        );
+        let mut fut = Box::pin(fut);
+        assert_eq!(calls.get(), 0);
+
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert_eq!(fut.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(calls.get(), 1);
+        assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(42));
+        assert_eq!(calls.get(), 1);
+        assert!(NUMBER.try_with(|_| ()).is_err());
+    }
+
+    #[test]
+    fn access_error_names_unset_key() {
+        let err = NUMBER.try_with(|n| *n).unwrap_err();
+        assert_eq!(err.key_name(), "NUMBER");
+        assert!(err.to_string().contains("NUMBER"), "{}", err);
+    }
+
+    #[test]
+    fn captured_value_is_restored_on_another_thread() {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let spawned = NUMBER.sync_scope(7, || {
+            let captured = NUMBER.capture();
+            assert_eq!(captured, Some(7));
+            NUMBER.scope_captured(captured, async { NUMBER.get() })
+        });
+        let handle = thread::spawn(move || {
+            let waker = noop_waker();
+            let mut cx = Context::from_waker(&waker);
+            Box::pin(spawned).as_mut().poll(&mut cx)
+        });
+        assert_eq!(handle.join().unwrap(), Poll::Ready(7));
+
+        // Nothing was captured outside of a scope.
+        assert_eq!(NUMBER.capture(), None);
+        let mut unset =
+            Box::pin(NUMBER.scope_captured(None, async { NUMBER.try_with(|_| ()).is_err() }));
+        assert_eq!(unset.as_mut().poll(&mut cx), Poll::Ready(true));
+    }
+
+    #[test]
+    fn scope_with_skips_make_for_unpolled_future() {
+        let calls = Cell::new(0);
+        let fut = NUMBER.scope_with(
+            || {
+                calls.set(calls.get() + 1);
+                1
+            },
+            async {},
+        );
+        drop(fut);
+        assert_eq!(calls.get(), 0);
+    }
+}